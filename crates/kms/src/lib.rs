@@ -93,7 +93,7 @@ impl SignerExt for Keyring {
     async fn verification_method(&self) -> anyhow::Result<Key> {
         let vk = self.keys.verifying_key("signing").await?;
         let jwk = PublicKeyJwk::from_bytes(&vk)?;
-        let vm = did::key::did_from_jwk(&jwk)?;
+        let vm = did::key::DidKey::from_jwk(&jwk)?.verification_method_id();
         Ok(Key::KeyId(vm))
     }
 }
@@ -1,11 +1,10 @@
 use axum::{
     extract::{Query, State},
     http::StatusCode,
-    response::{IntoResponse, Response}, BoxError,
+    response::{IntoResponse, Response},
 };
-use axum_extra::{TypedHeader, headers::Host, json_lines::JsonLines};
-use credibil_identity::did::{webvh::{resolve_log, DidLog, DidLogEntry}, Document, QueryParams};
-use futures_util::Stream;
+use axum_extra::{TypedHeader, headers::Host};
+use credibil_identity::did::{webvh::{resolve_log, to_jsonl}, Document, QueryParams};
 
 use crate::{AppError, AppJson};
 use crate::state::AppState;
@@ -22,15 +21,10 @@ pub async fn read(State(state): State<AppState>, TypedHeader(host): TypedHeader<
         return AppError::Status(StatusCode::NOT_FOUND, "No log found".into()).into_response();
     };
 
-    let values = entries_as_stream(entries);
-    JsonLines::new(values).into_response()
-}
-
-fn entries_as_stream(entries: DidLog) -> impl Stream<Item = Result<DidLogEntry, BoxError>> {
-    futures_util::stream::iter(entries.into_iter().map(|entry| {
-        // Convert each entry to a Result
-        Ok(entry)
-    }))
+    match to_jsonl(&entries) {
+        Ok(jsonl) => jsonl.into_response(),
+        Err(e) => AppError::Other(e).into_response(),
+    }
 }
 
 // Handler to resolve a DID document from a DID log file.
@@ -13,46 +13,119 @@ use std::{
     str::FromStr,
 };
 
-use anyhow::anyhow;
+use crate::Error;
 
+mod cbor;
+mod context;
+mod diff;
 mod document;
+mod hashlink;
+pub mod jwk;
 pub mod key;
+mod multikey;
+mod patch;
+pub mod peer;
+pub mod pkh;
+pub mod plc;
+mod registry;
+mod representation;
+mod resolution;
 mod resolve;
 mod url;
+mod validate;
 pub mod web;
 pub mod webvh;
+pub mod x509;
 
+pub use context::{DID_CONTEXT, service_context};
+pub use diff::{Changes, DocumentDiff, SetChanges};
 pub use document::{
     Document, DocumentBuilder, DocumentMetadata, DocumentMetadataBuilder, KeyPurpose, MethodType,
     PublicKeyFormat, Service, ServiceBuilder, VerificationMethod, VerificationMethodBuilder,
     VmKeyId,
 };
-pub use resolve::{dereference, deref_url, document_resource, Resource};
+pub use hashlink::{hashlink, verify_hashlink};
+pub use multikey::{KeyCodec, Multikey};
+pub use patch::{PatchOperation, apply_patch};
+pub use registry::{BoxFuture, DidMethod, Registry};
+pub use representation::{DID_CBOR, DID_JSON, DID_LD_JSON, Representation};
+pub use resolution::{
+    ErrorCode, ProblemDetails, ResolutionError, ResolutionMetadata, ResolutionOptions,
+    ResolutionResult, resolve_did,
+};
+pub use resolve::{
+    Resource, deref_url, dereference, document_resource, fragment_resource, service_endpoints,
+};
 pub use url::{QueryParams, Url};
+pub use validate::{Finding, FindingKind};
 
-// TODO: set context based on key format:
-// - Ed25519VerificationKey2020	https://w3id.org/security/suites/ed25519-2020/v1
-// - JsonWebKey2020	https://w3id.org/security/suites/jws-2020/v1
-// Perhaps this needs to be an enum with Display impl?
 /// Candidate contexts to add to a DID document.
+#[deprecated(note = "contexts are computed from the document: see `Document::update_context`")]
 pub const BASE_CONTEXT: [&str; 3] = [
     "https://www.w3.org/ns/did/v1",
     "https://w3id.org/security/multikey/v1",
     "https://w3id.org/security/suites/jws-2020/v1",
 ];
 
-/// DID methods supported by this crate.
+/// DID methods built into this crate.
+///
+/// Each method implements [`DidMethod`] and is registered with the default
+/// [`Registry`]. Other methods can be added by implementing [`DidMethod`].
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub enum Method {
     /// `did:key`
     #[default]
     Key,
 
+    /// `did:jwk`
+    Jwk,
+
+    /// `did:peer`
+    Peer,
+
+    /// `did:pkh`
+    Pkh,
+
+    /// `did:plc`
+    Plc,
+
     /// `did:web`
     Web,
 
     /// `did:webvh`
     WebVh,
+
+    /// `did:x509`
+    X509,
+}
+
+impl Method {
+    /// All methods built into this crate.
+    pub const ALL: [Self; 8] = [
+        Self::Key,
+        Self::Jwk,
+        Self::Peer,
+        Self::Pkh,
+        Self::Plc,
+        Self::Web,
+        Self::WebVh,
+        Self::X509,
+    ];
+
+    /// The method name as it appears in a DID.
+    #[must_use]
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Key => "key",
+            Self::Jwk => "jwk",
+            Self::Peer => "peer",
+            Self::Pkh => "pkh",
+            Self::Plc => "plc",
+            Self::Web => "web",
+            Self::WebVh => "webvh",
+            Self::X509 => "x509",
+        }
+    }
 }
 
 impl FromStr for Method {
@@ -64,21 +137,22 @@ impl FromStr for Method {
     ///
     /// Returns an error if the string is not a valid method.
     fn from_str(s: &str) -> anyhow::Result<Self> {
-        match s {
-            "key" => Ok(Self::Key),
-            "web" => Ok(Self::Web),
-            "webvh" => Ok(Self::WebVh),
-            _ => Err(anyhow!("method not supported: {s}")),
-        }
+        Self::ALL.into_iter().find(|method| method.as_str() == s).ok_or_else(|| {
+            let detail = format!("method not supported: {s}");
+            Error::resolution(ErrorCode::MethodNotSupported, detail).into()
+        })
     }
 }
 
 impl Display for Method {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Self::Key => write!(f, "key"),
-            Self::Web => write!(f, "web"),
-            Self::WebVh => write!(f, "webvh"),
-        }
+        f.write_str(self.as_str())
+    }
+}
+
+/// Compare a method name, such as [`Url::method`], with a built-in method.
+impl PartialEq<Method> for String {
+    fn eq(&self, other: &Method) -> bool {
+        self == other.as_str()
     }
 }
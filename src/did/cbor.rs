@@ -0,0 +1,296 @@
+//! # CBOR
+//!
+//! A minimal CBOR ([RFC 8949](https://www.rfc-editor.org/rfc/rfc8949)) codec
+//! for the JSON data model, used for the CBOR representation of DID
+//! documents and to sign and hash `did:plc` operations.
+//!
+//! Encoding uses definite lengths and the shortest form of each integer and
+//! length. Map key order and the handling of floating-point numbers depend on
+//! the [`Encoding`].
+//!
+//! Decoding accepts the data items that have a JSON equivalent: integers,
+//! floats, text strings, arrays, maps with text string keys, booleans and
+//! null. Byte strings, tags, `undefined` and indefinite-length items are
+//! rejected.
+
+use anyhow::{anyhow, bail};
+use serde_json::{Map, Number, Value};
+
+const UNSIGNED: u8 = 0;
+const NEGATIVE: u8 = 1;
+const BYTES: u8 = 2;
+const TEXT: u8 = 3;
+const ARRAY: u8 = 4;
+const MAP: u8 = 5;
+const TAG: u8 = 6;
+const SIMPLE: u8 = 7;
+
+// Nesting deeper than this is rejected rather than risking the stack.
+const MAX_DEPTH: usize = 128;
+
+/// The deterministic encoding rules to follow.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Encoding {
+    /// Core deterministic encoding (RFC 8949 section 4.2.1): map keys are
+    /// sorted by their encoded bytes. Floating-point numbers use single
+    /// precision when it is lossless, otherwise double precision.
+    Core,
+
+    /// [`DAG-CBOR`](https://ipld.io/specs/codecs/dag-cbor/spec): map keys are
+    /// sorted by length and then bytewise. Floating-point numbers are not
+    /// supported.
+    DagCbor,
+}
+
+/// Encode a JSON value as CBOR.
+///
+/// # Errors
+/// Will fail if the value contains a floating-point number and the encoding
+/// does not support them.
+pub fn encode(value: &Value, encoding: Encoding) -> anyhow::Result<Vec<u8>> {
+    let mut out = Vec::new();
+    encode_value(value, encoding, &mut out)?;
+    Ok(out)
+}
+
+/// Decode CBOR into a JSON value.
+pub fn decode(bytes: &[u8]) -> anyhow::Result<Value> {
+    let mut decoder = Decoder { bytes, pos: 0 };
+    let value = decoder.value(0)?;
+    if decoder.pos != bytes.len() {
+        bail!("unexpected data after CBOR item");
+    }
+    Ok(value)
+}
+
+fn encode_value(value: &Value, encoding: Encoding, out: &mut Vec<u8>) -> anyhow::Result<()> {
+    match value {
+        Value::Null => out.push(0xf6),
+        Value::Bool(b) => out.push(if *b { 0xf5 } else { 0xf4 }),
+        Value::Number(n) => encode_number(n, encoding, out)?,
+        Value::String(s) => encode_text(s, out),
+        Value::Array(items) => {
+            head(ARRAY, items.len() as u64, out);
+            for item in items {
+                encode_value(item, encoding, out)?;
+            }
+        }
+        Value::Object(map) => {
+            let mut entries = map
+                .iter()
+                .map(|(key, value)| {
+                    let mut key_bytes = Vec::new();
+                    encode_text(key, &mut key_bytes);
+                    (key_bytes, value)
+                })
+                .collect::<Vec<_>>();
+            match encoding {
+                Encoding::Core => entries.sort_by(|a, b| a.0.cmp(&b.0)),
+                Encoding::DagCbor => {
+                    entries.sort_by(|a, b| a.0.len().cmp(&b.0.len()).then_with(|| a.0.cmp(&b.0)));
+                }
+            }
+
+            head(MAP, entries.len() as u64, out);
+            for (key, value) in entries {
+                out.extend_from_slice(&key);
+                encode_value(value, encoding, out)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn encode_text(s: &str, out: &mut Vec<u8>) {
+    head(TEXT, s.len() as u64, out);
+    out.extend_from_slice(s.as_bytes());
+}
+
+#[allow(clippy::cast_possible_truncation)]
+fn encode_number(n: &Number, encoding: Encoding, out: &mut Vec<u8>) -> anyhow::Result<()> {
+    if let Some(u) = n.as_u64() {
+        head(UNSIGNED, u, out);
+    } else if let Some(i) = n.as_i64() {
+        // `i` is negative, so `-1 - i` is not
+        head(NEGATIVE, (-1 - i).unsigned_abs(), out);
+    } else if encoding == Encoding::DagCbor {
+        bail!("floating point numbers are not supported: {n}");
+    } else {
+        let f = n.as_f64().unwrap_or_default();
+        let single = f as f32;
+        if f64::from(single).to_bits() == f.to_bits() {
+            out.push(0xfa);
+            out.extend_from_slice(&single.to_be_bytes());
+        } else {
+            out.push(0xfb);
+            out.extend_from_slice(&f.to_be_bytes());
+        }
+    }
+    Ok(())
+}
+
+// Write the initial byte and argument of a data item.
+fn head(major: u8, arg: u64, out: &mut Vec<u8>) {
+    let major = major << 5;
+    if let Ok(arg) = u8::try_from(arg) {
+        if arg < 0x18 {
+            out.push(major | arg);
+        } else {
+            out.extend_from_slice(&[major | 0x18, arg]);
+        }
+    } else if let Ok(arg) = u16::try_from(arg) {
+        out.push(major | 0x19);
+        out.extend_from_slice(&arg.to_be_bytes());
+    } else if let Ok(arg) = u32::try_from(arg) {
+        out.push(major | 0x1a);
+        out.extend_from_slice(&arg.to_be_bytes());
+    } else {
+        out.push(major | 0x1b);
+        out.extend_from_slice(&arg.to_be_bytes());
+    }
+}
+
+struct Decoder<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl Decoder<'_> {
+    fn value(&mut self, depth: usize) -> anyhow::Result<Value> {
+        if depth > MAX_DEPTH {
+            bail!("CBOR nesting too deep");
+        }
+        let initial = self.take(1)?[0];
+        let major = initial >> 5;
+        let info = initial & 0x1f;
+
+        if major == SIMPLE {
+            return self.simple(info);
+        }
+        let arg = self.argument(info)?;
+        match major {
+            UNSIGNED => Ok(Value::from(arg)),
+            NEGATIVE => {
+                let n = i64::try_from(arg).map_err(|_| anyhow!("CBOR integer out of range"))?;
+                Ok(Value::from(-1 - n))
+            }
+            TEXT => Ok(Value::String(self.text(arg)?)),
+            ARRAY => {
+                let mut items = Vec::new();
+                for _ in 0..arg {
+                    items.push(self.value(depth + 1)?);
+                }
+                Ok(Value::Array(items))
+            }
+            MAP => {
+                let mut map = Map::new();
+                for _ in 0..arg {
+                    let initial = self.take(1)?[0];
+                    if initial >> 5 != TEXT {
+                        bail!("CBOR map keys must be text strings");
+                    }
+                    let len = self.argument(initial & 0x1f)?;
+                    let key = self.text(len)?;
+                    let value = self.value(depth + 1)?;
+                    if map.insert(key.clone(), value).is_some() {
+                        bail!("duplicate CBOR map key: {key}");
+                    }
+                }
+                Ok(Value::Object(map))
+            }
+            BYTES => bail!("CBOR byte strings are not supported"),
+            TAG => bail!("CBOR tags are not supported"),
+            _ => unreachable!("major type is three bits"),
+        }
+    }
+
+    // Simple values and floats (major type 7).
+    fn simple(&mut self, info: u8) -> anyhow::Result<Value> {
+        let float = match info {
+            20 => return Ok(Value::Bool(false)),
+            21 => return Ok(Value::Bool(true)),
+            22 => return Ok(Value::Null),
+            25 => half(u16::from_be_bytes(self.array()?)),
+            26 => f64::from(f32::from_be_bytes(self.array()?)),
+            27 => f64::from_be_bytes(self.array()?),
+            _ => bail!("unsupported CBOR simple value {info}"),
+        };
+        Number::from_f64(float)
+            .map(Value::Number)
+            .ok_or_else(|| anyhow!("CBOR float {float} has no JSON equivalent"))
+    }
+
+    // The argument of a data item: a value, length or count.
+    fn argument(&mut self, info: u8) -> anyhow::Result<u64> {
+        match info {
+            0..24 => Ok(u64::from(info)),
+            24 => Ok(u64::from(self.take(1)?[0])),
+            25 => Ok(u64::from(u16::from_be_bytes(self.array()?))),
+            26 => Ok(u64::from(u32::from_be_bytes(self.array()?))),
+            27 => Ok(u64::from_be_bytes(self.array()?)),
+            31 => bail!("indefinite-length CBOR items are not supported"),
+            _ => bail!("invalid CBOR additional information {info}"),
+        }
+    }
+
+    fn text(&mut self, len: u64) -> anyhow::Result<String> {
+        let len = usize::try_from(len).map_err(|_| anyhow!("CBOR text string too long"))?;
+        let bytes = self.take(len)?;
+        Ok(std::str::from_utf8(bytes).map_err(|_| anyhow!("invalid UTF-8 in CBOR text"))?.into())
+    }
+
+    fn array<const N: usize>(&mut self) -> anyhow::Result<[u8; N]> {
+        let mut array = [0; N];
+        array.copy_from_slice(self.take(N)?);
+        Ok(array)
+    }
+
+    fn take(&mut self, len: usize) -> anyhow::Result<&[u8]> {
+        let end = self.pos.checked_add(len).filter(|end| *end <= self.bytes.len());
+        let Some(end) = end else {
+            bail!("unexpected end of CBOR data");
+        };
+        let bytes = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+}
+
+// Convert a half-precision float (RFC 8949 Appendix D).
+fn half(bits: u16) -> f64 {
+    let exp = i32::from((bits >> 10) & 0x1f);
+    let mant = f64::from(bits & 0x3ff);
+    let value = match exp {
+        0 => mant * 2f64.powi(-24),
+        31 if mant == 0.0 => f64::INFINITY,
+        31 => f64::NAN,
+        _ => (mant + 1024.0) * 2f64.powi(exp - 25),
+    };
+    if bits & 0x8000 == 0 { value } else { -value }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn map_keys_sorted() {
+        let value = json!({"bb": -1, "a": "x", "c": [true, null, 500]});
+        let expected = [
+            0xa3, 0x61, b'a', 0x61, b'x', 0x61, b'c', 0x83, 0xf5, 0xf6, 0x19, 0x01, 0xf4, 0x62,
+            b'b', b'b', 0x20,
+        ];
+        assert_eq!(encode(&value, Encoding::DagCbor).expect("should encode"), expected);
+        assert_eq!(encode(&value, Encoding::Core).expect("should encode"), expected);
+        assert_eq!(decode(&expected).expect("should decode"), value);
+    }
+
+    #[test]
+    fn floats() {
+        let bytes = encode(&json!({"a": 1.5}), Encoding::Core).expect("should encode");
+        assert_eq!(bytes, [0xa1, 0x61, b'a', 0xfa, 0x3f, 0xc0, 0x00, 0x00]);
+        encode(&json!({"a": 1.5}), Encoding::DagCbor).expect_err("should not encode floats");
+    }
+}
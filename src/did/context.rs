@@ -0,0 +1,130 @@
+//! # JSON-LD Contexts
+//!
+//! The `@context` of a DID document is computed from its content: the DID v1
+//! context followed by the contexts defining the terms of each verification
+//! method type and service type used in the document.
+//!
+//! Contexts known to this module are added when a method or service that
+//! needs them is present and removed when none is. Any other context, such as
+//! an embedded term definition, is left in place.
+
+use super::{Document, MethodType};
+use crate::core::Kind;
+
+/// The DID v1 context. The first context of every JSON-LD DID document.
+pub const DID_CONTEXT: &str = "https://www.w3.org/ns/did/v1";
+
+const MULTIKEY: &str = "https://w3id.org/security/multikey/v1";
+const ED25519_2020: &str = "https://w3id.org/security/suites/ed25519-2020/v1";
+const ED25519_2018: &str = "https://w3id.org/security/suites/ed25519-2018/v1";
+const X25519_2020: &str = "https://w3id.org/security/suites/x25519-2020/v1";
+const JWS_2020: &str = "https://w3id.org/security/suites/jws-2020/v1";
+const JWK: &str = "https://w3id.org/security/jwk/v1";
+const SECP256K1_2019: &str = "https://w3id.org/security/suites/secp256k1-2019/v1";
+const LINKED_DOMAINS: &str = "https://identity.foundation/.well-known/did-configuration/v1";
+
+/// The context for `LinkedVerifiablePresentation` services.
+pub const LINKED_VP_CONTEXT: &str = "https://identity.foundation/linked-vp/contexts/v1";
+
+// Contexts managed by `Document::update_context`.
+const MANAGED: [&str; 9] = [
+    MULTIKEY,
+    ED25519_2020,
+    ED25519_2018,
+    X25519_2020,
+    JWS_2020,
+    JWK,
+    SECP256K1_2019,
+    LINKED_DOMAINS,
+    LINKED_VP_CONTEXT,
+];
+
+impl MethodType {
+    /// The JSON-LD context defining the verification method type and its
+    /// properties, if there is one.
+    ///
+    /// The blockchain account types used by `did:pkh` have no published
+    /// context: `did:pkh` documents define their terms inline.
+    #[must_use]
+    pub const fn context(&self) -> Option<&'static str> {
+        match self {
+            Self::Multikey => Some(MULTIKEY),
+            Self::Ed25519VerificationKey2020 => Some(ED25519_2020),
+            Self::Ed25519VerificationKey2018 => Some(ED25519_2018),
+            Self::X25519KeyAgreementKey2020 => Some(X25519_2020),
+            Self::JsonWebKey2020 => Some(JWS_2020),
+            Self::JsonWebKey => Some(JWK),
+            Self::EcdsaSecp256k1VerificationKey2019 => Some(SECP256K1_2019),
+            Self::EcdsaSecp256k1RecoveryMethod2020
+            | Self::Ed25519PublicKeyBlake2bDigestSize20Base58CheckEncoded2021
+            | Self::P256PublicKeyBlake2bDigestSize20Base58CheckEncoded2021 => None,
+        }
+    }
+}
+
+/// The JSON-LD context defining a service type, if there is one.
+#[must_use]
+pub fn service_context(service_type: &str) -> Option<&'static str> {
+    match service_type {
+        "LinkedDomains" => Some(LINKED_DOMAINS),
+        "LinkedVerifiablePresentation" => Some(LINKED_VP_CONTEXT),
+        _ => None,
+    }
+}
+
+impl Document {
+    /// Update the document's `@context` to match its content.
+    ///
+    /// The DID v1 context is moved to (or added as) the first context. The
+    /// contexts needed by the document's verification methods (including
+    /// those embedded in verification relationships) and services are added,
+    /// and known contexts that are no longer needed are removed. Other
+    /// contexts are kept, in order.
+    pub fn update_context(&mut self) {
+        let embedded = [
+            &self.authentication,
+            &self.assertion_method,
+            &self.key_agreement,
+            &self.capability_invocation,
+            &self.capability_delegation,
+        ]
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|kind| match kind {
+            Kind::Object(vm) => Some(vm),
+            Kind::String(_) => None,
+        });
+        let method_contexts = self
+            .verification_method
+            .iter()
+            .flatten()
+            .chain(embedded)
+            .filter_map(|vm| vm.type_.context());
+        let service_contexts =
+            self.service.iter().flatten().filter_map(|s| service_context(&s.type_));
+
+        let mut required = Vec::new();
+        for context in method_contexts.chain(service_contexts) {
+            if !required.contains(&context) {
+                required.push(context);
+            }
+        }
+
+        let did_context = Kind::String(DID_CONTEXT.to_string());
+        self.context.retain(|c| match c {
+            Kind::String(s) => {
+                s != DID_CONTEXT
+                    && (!MANAGED.contains(&s.as_str()) || required.contains(&s.as_str()))
+            }
+            Kind::Object(_) => true,
+        });
+        self.context.insert(0, did_context);
+        for context in required {
+            let context = Kind::String(context.to_string());
+            if !self.context.contains(&context) {
+                self.context.push(context);
+            }
+        }
+    }
+}
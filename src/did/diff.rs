@@ -0,0 +1,255 @@
+//! # Document Diff
+//!
+//! A semantic comparison of two DID documents, such as the `state` documents
+//! of consecutive `did:webvh` log entries.
+//!
+//! Verification methods and services are matched by ID, with relative IDs
+//! resolved against the document's ID. The order of arrays is ignored, as
+//! are document metadata and the computed `@context`. For an exact,
+//! order-preserving description of the changes see [`Document::patch_to`].
+
+use serde::Serialize;
+use serde_json::Value;
+
+use super::{Document, KeyPurpose, Service, VerificationMethod};
+use crate::core::{Kind, OneMany};
+
+// The verification relationships compared by `Document::diff`.
+const RELATIONSHIPS: [KeyPurpose; 5] = [
+    KeyPurpose::Authentication,
+    KeyPurpose::AssertionMethod,
+    KeyPurpose::KeyAgreement,
+    KeyPurpose::CapabilityInvocation,
+    KeyPurpose::CapabilityDelegation,
+];
+
+/// The differences between two DID documents.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DocumentDiff {
+    /// The previous and next document IDs, if the ID changed (for example,
+    /// when a portable `did:webvh` DID is moved).
+    pub id: Option<(String, String)>,
+
+    /// Changes to the document's `alsoKnownAs` identifiers.
+    pub also_known_as: SetChanges,
+
+    /// Changes to the document's controllers.
+    pub controllers: SetChanges,
+
+    /// Changes to the document's verification methods, including those
+    /// embedded in verification relationships.
+    pub verification_methods: Changes<VerificationMethod>,
+
+    /// The verification methods (by absolute ID) added to and removed from
+    /// each verification relationship. Relationships that did not change are
+    /// omitted.
+    pub relationships: Vec<(KeyPurpose, SetChanges)>,
+
+    /// Changes to the document's services.
+    pub services: Changes<Service>,
+}
+
+impl DocumentDiff {
+    /// Whether the documents are semantically the same.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.id.is_none()
+            && self.also_known_as.is_empty()
+            && self.controllers.is_empty()
+            && self.verification_methods.is_empty()
+            && self.relationships.is_empty()
+            && self.services.is_empty()
+    }
+}
+
+/// Values added to and removed from an unordered set of strings.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SetChanges {
+    /// Values in the next document but not the previous one.
+    pub added: Vec<String>,
+
+    /// Values in the previous document but not the next one.
+    pub removed: Vec<String>,
+}
+
+impl SetChanges {
+    /// Whether the set is unchanged.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+
+    fn new(previous: &[String], next: &[String]) -> Self {
+        Self {
+            added: next.iter().filter(|s| !previous.contains(s)).cloned().collect(),
+            removed: previous.iter().filter(|s| !next.contains(s)).cloned().collect(),
+        }
+    }
+}
+
+/// Items, identified by ID, added to, removed from or changed in a document.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Changes<T> {
+    /// Items in the next document with an ID not in the previous one.
+    pub added: Vec<T>,
+
+    /// Items in the previous document with an ID not in the next one.
+    pub removed: Vec<T>,
+
+    /// Items with the same ID in both documents but different content, as
+    /// `(previous, next)` pairs.
+    pub changed: Vec<(T, T)>,
+}
+
+impl<T> Default for Changes<T> {
+    fn default() -> Self {
+        Self {
+            added: Vec::new(),
+            removed: Vec::new(),
+            changed: Vec::new(),
+        }
+    }
+}
+
+impl<T> Changes<T> {
+    /// Whether no items were added, removed or changed.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+impl<T: Clone + Serialize> Changes<T> {
+    // Match items by absolute ID and compare matched items as JSON, with
+    // their IDs made absolute and ignoring array order.
+    fn new(previous: &[(String, &T)], next: &[(String, &T)]) -> Self {
+        fn find<'a, T>(items: &[(String, &'a T)], id: &str) -> Option<&'a T> {
+            items.iter().find(|(other, _)| other == id).map(|(_, item)| *item)
+        }
+        let as_json = |id: &str, item: &T| {
+            let mut value = serde_json::to_value(item).unwrap_or_default();
+            if let Value::Object(map) = &mut value {
+                map.insert("id".to_string(), Value::String(id.to_string()));
+            }
+            value
+        };
+
+        let mut changes = Self::default();
+        for (id, item) in next {
+            match find(previous, id) {
+                None => changes.added.push((*item).clone()),
+                Some(prev) if !equivalent(&as_json(id, prev), &as_json(id, item)) => {
+                    changes.changed.push((prev.clone(), (*item).clone()));
+                }
+                Some(_) => {}
+            }
+        }
+        for (id, item) in previous {
+            if find(next, id).is_none() {
+                changes.removed.push((*item).clone());
+            }
+        }
+        changes
+    }
+}
+
+impl Document {
+    /// Compare this document with the `next` version of it.
+    ///
+    /// The comparison ignores the order of arrays, document metadata and the
+    /// `@context`, which is computed from the document's content.
+    #[must_use]
+    pub fn diff(&self, next: &Self) -> DocumentDiff {
+        let relationships = RELATIONSHIPS
+            .into_iter()
+            .filter_map(|purpose| {
+                let changes = SetChanges::new(
+                    &self.relationship_ids(&purpose),
+                    &next.relationship_ids(&purpose),
+                );
+                (!changes.is_empty()).then_some((purpose, changes))
+            })
+            .collect();
+
+        DocumentDiff {
+            id: (self.id != next.id).then(|| (self.id.clone(), next.id.clone())),
+            also_known_as: SetChanges::new(
+                self.also_known_as.as_deref().unwrap_or_default(),
+                next.also_known_as.as_deref().unwrap_or_default(),
+            ),
+            controllers: SetChanges::new(&self.controllers(), &next.controllers()),
+            verification_methods: Changes::new(&self.all_methods(), &next.all_methods()),
+            relationships,
+            services: Changes::new(&self.services(), &next.services()),
+        }
+    }
+
+    fn controllers(&self) -> Vec<String> {
+        match &self.controller {
+            Some(OneMany::One(controller)) => vec![controller.clone()],
+            Some(OneMany::Many(controllers)) => controllers.clone(),
+            None => vec![],
+        }
+    }
+
+    // The document's verification methods and those embedded in verification
+    // relationships, keyed by absolute ID.
+    fn all_methods(&self) -> Vec<(String, &VerificationMethod)> {
+        let embedded = RELATIONSHIPS
+            .iter()
+            .filter_map(|purpose| self.relationship(purpose).and_then(Option::as_ref))
+            .flatten()
+            .filter_map(|kind| match kind {
+                Kind::Object(vm) => Some(vm),
+                Kind::String(_) => None,
+            });
+        self.verification_method
+            .iter()
+            .flatten()
+            .chain(embedded)
+            .map(|vm| (self.absolute_id(&vm.id), vm))
+            .collect()
+    }
+
+    fn services(&self) -> Vec<(String, &Service)> {
+        self.service.iter().flatten().map(|s| (self.absolute_id(&s.id), s)).collect()
+    }
+
+    // The absolute IDs of the verification methods in a relationship.
+    fn relationship_ids(&self, purpose: &KeyPurpose) -> Vec<String> {
+        let relationship = self.relationship(purpose).and_then(Option::as_ref);
+        relationship
+            .into_iter()
+            .flatten()
+            .map(|kind| match kind {
+                Kind::String(id) => self.absolute_id(id),
+                Kind::Object(vm) => self.absolute_id(&vm.id),
+            })
+            .collect()
+    }
+}
+
+// Compare JSON values ignoring the order of arrays. A single value is
+// equivalent to an array containing only that value, as for properties such
+// as `serviceEndpoint` that can be either.
+fn equivalent(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Array(a), Value::Array(b)) => {
+            if a.len() != b.len() {
+                return false;
+            }
+            let mut unmatched = b.iter().collect::<Vec<_>>();
+            a.iter().all(|x| {
+                unmatched.iter().position(|y| equivalent(x, y)).is_some_and(|i| {
+                    unmatched.swap_remove(i);
+                    true
+                })
+            })
+        }
+        (Value::Array(a), b) | (b, Value::Array(a)) => a.len() == 1 && equivalent(&a[0], b),
+        (Value::Object(a), Value::Object(b)) => {
+            a.len() == b.len() && a.iter().all(|(k, x)| b.get(k).is_some_and(|y| equivalent(x, y)))
+        }
+        _ => a == b,
+    }
+}
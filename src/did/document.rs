@@ -8,15 +8,14 @@ use std::fmt::{self, Display, Formatter};
 use std::str::FromStr;
 
 use anyhow::{anyhow, bail};
-use base64ct::{Base64UrlUnpadded, Encoding};
 use chrono::{DateTime, Utc};
 use credibil_jose::PublicKeyJwk;
-use credibil_se::{derive_x25519_public, PublicKey, X25519_CODEC};
-use multibase::Base;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
-use super::BASE_CONTEXT;
+use super::PatchOperation;
+use super::multikey::{KeyCodec, Multikey};
+use crate::Error;
 use crate::core::{Kind, OneMany};
 
 /// The purpose key material will be used for.
@@ -75,7 +74,11 @@ impl FromStr for KeyPurpose {
 #[serde(rename_all = "camelCase")]
 pub struct Document {
     /// The context of the DID document.
-    #[serde(rename = "@context")]
+    ///
+    /// A representation-specific entry of the JSON-LD representation: it is
+    /// omitted when the document is produced as JSON or CBOR and may be
+    /// missing when consuming those representations.
+    #[serde(rename = "@context", default)]
     pub context: Vec<Kind<Value>>,
 
     /// The DID for a particular DID subject.
@@ -165,6 +168,12 @@ pub struct Document {
     /// <https://w3c.github.io/did-core/#dfn-diddocumentmetadata>
     #[serde(skip_serializing_if = "Option::is_none")]
     pub did_document_metadata: Option<DocumentMetadata>,
+
+    /// Properties not defined by this crate, such as extensions registered in
+    /// the DID Specification Registries. Preserved so they survive
+    /// consumption and production of the document.
+    #[serde(flatten)]
+    pub additional: HashMap<String, Value>,
 }
 
 impl Document {
@@ -179,6 +188,89 @@ impl Document {
     pub fn get_verification_method(&self, id: &str) -> Option<&VerificationMethod> {
         self.verification_method.as_ref()?.iter().find(|vm| vm.id == id)
     }
+
+    /// The verification methods that may be used for the given purpose.
+    ///
+    /// For a verification relationship, methods embedded in the relationship
+    /// are returned as they are and references are resolved to the methods
+    /// they identify. References that cannot be resolved are skipped. For
+    /// [`KeyPurpose::VerificationMethod`], the document's verification methods
+    /// are returned.
+    ///
+    /// IDs are compared after resolving relative IDs (such as `#key-1`)
+    /// against the document's ID.
+    #[must_use]
+    pub fn verification_methods(&self, purpose: &KeyPurpose) -> Vec<&VerificationMethod> {
+        let Some(relationship) = self.relationship(purpose) else {
+            return self.verification_method.iter().flatten().collect();
+        };
+        relationship
+            .iter()
+            .flatten()
+            .filter_map(|kind| match kind {
+                Kind::Object(vm) => Some(vm),
+                Kind::String(id) => self.find_verification_method(id),
+            })
+            .collect()
+    }
+
+    /// Whether the verification method with the given ID may be used for the
+    /// given purpose: it is embedded in, or referenced by, the verification
+    /// relationship. For [`KeyPurpose::VerificationMethod`], whether it is
+    /// one of the document's verification methods.
+    ///
+    /// The ID may be relative to the document's ID (such as `#key-1`).
+    #[must_use]
+    pub fn is_authorized(&self, vm_id: &str, purpose: &KeyPurpose) -> bool {
+        let vm_id = self.absolute_id(vm_id);
+        self.verification_methods(purpose).iter().any(|vm| self.absolute_id(&vm.id) == vm_id)
+    }
+
+    // The verification relationship for a purpose. `None` for
+    // `KeyPurpose::VerificationMethod`, which is not a relationship.
+    pub(super) const fn relationship(
+        &self, purpose: &KeyPurpose,
+    ) -> Option<&Option<Vec<Kind<VerificationMethod>>>> {
+        match purpose {
+            KeyPurpose::VerificationMethod => None,
+            KeyPurpose::Authentication => Some(&self.authentication),
+            KeyPurpose::AssertionMethod => Some(&self.assertion_method),
+            KeyPurpose::KeyAgreement => Some(&self.key_agreement),
+            KeyPurpose::CapabilityInvocation => Some(&self.capability_invocation),
+            KeyPurpose::CapabilityDelegation => Some(&self.capability_delegation),
+        }
+    }
+
+    // Find a verification method referenced from a verification relationship,
+    // either in the document's verification methods or embedded in another
+    // relationship.
+    pub(super) fn find_verification_method(&self, id: &str) -> Option<&VerificationMethod> {
+        let id = self.absolute_id(id);
+        let same = |vm: &&VerificationMethod| self.absolute_id(&vm.id) == id;
+        if let Some(vm) = self.verification_method.iter().flatten().find(same) {
+            return Some(vm);
+        }
+        [
+            &self.authentication,
+            &self.assertion_method,
+            &self.key_agreement,
+            &self.capability_invocation,
+            &self.capability_delegation,
+        ]
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|kind| match kind {
+            Kind::Object(vm) => Some(vm),
+            Kind::String(_) => None,
+        })
+        .find(same)
+    }
+
+    // Resolve an ID relative to the document's ID.
+    pub(super) fn absolute_id(&self, id: &str) -> String {
+        if id.starts_with('#') { format!("{}{id}", self.id) } else { id.to_string() }
+    }
 }
 
 /// Types of operation a `DocumentBuilder` can perform.
@@ -265,7 +357,7 @@ impl DocumentBuilder {
                     if cont == controller {
                         self.doc.controller = None;
                     } else {
-                        bail!("controller not found");
+                        return Err(Error::Builder("controller not found".to_string()).into());
                     }
                 }
                 OneMany::Many(mut cont) => {
@@ -273,12 +365,12 @@ impl DocumentBuilder {
                         cont.remove(pos);
                         self.doc.controller = Some(OneMany::Many(cont));
                     } else {
-                        bail!("controller not found");
+                        return Err(Error::Builder("controller not found".to_string()).into());
                     }
                 }
             },
             None => {
-                bail!("controller not found");
+                return Err(Error::Builder("controller not found".to_string()).into());
             }
         }
         Ok(self)
@@ -302,10 +394,10 @@ impl DocumentBuilder {
             if let Some(pos) = services.iter().position(|s| s.id == service_id) {
                 services.remove(pos);
             } else {
-                bail!("service not found");
+                return Err(Error::Builder("service not found".to_string()).into());
             }
         } else {
-            bail!("service not found");
+            return Err(Error::Builder("service not found".to_string()).into());
         }
         Ok(self)
     }
@@ -327,7 +419,7 @@ impl DocumentBuilder {
         if let Some(pos) = self.doc.context.iter().position(|c| c == context) {
             self.doc.context.remove(pos);
         } else {
-            bail!("context not found");
+            return Err(Error::Builder("context not found".to_string()).into());
         }
         Ok(self)
     }
@@ -359,9 +451,9 @@ impl DocumentBuilder {
                     self.doc.key_agreement.get_or_insert(vec![]).push(Kind::Object(vm.clone()));
                 }
                 Kind::String(_) => {
-                    bail!(
-                        "key agreement must be handled by the encryption method creation algorithm"
-                    );
+                    let detail =
+                        "key agreement must be handled by the encryption method creation algorithm";
+                    return Err(Error::Builder(detail.to_string()).into());
                 }
             },
             KeyPurpose::CapabilityInvocation => {
@@ -375,7 +467,8 @@ impl DocumentBuilder {
                     self.doc.verification_method.get_or_insert(vec![]).push(vm.clone());
                 }
                 Kind::String(_) => {
-                    bail!("verification method must be a standalone verification method");
+                    let detail = "verification method must be a standalone verification method";
+                    return Err(Error::Builder(detail.to_string()).into());
                 }
             },
         }
@@ -392,7 +485,9 @@ impl DocumentBuilder {
     /// # Note
     ///
     /// 1. The verification key format will be multibase encoded, derived from
-    ///    the JWK passed in. This is hard-coded for this function.
+    ///    the JWK passed in. This is hard-coded for this function. `Ed25519`
+    ///    keys use the `Ed25519VerificationKey2020` type and other curves use
+    ///    `Multikey`.
     ///
     /// 2. The key identifier will be `{did}#key-0`.
     ///
@@ -400,16 +495,22 @@ impl DocumentBuilder {
     ///
     /// # Errors
     /// Will fail if the JWK cannot be converted to a multibase string or if the
-    /// conversion from `Ed25519` to `X25519` fails.
+    /// conversion from `Ed25519` to `X25519` fails (including when a key
+    /// agreement is requested for a key that is not `Ed25519`).
     pub fn add_verifying_key(
         mut self, jwk: &PublicKeyJwk, key_agreement: bool,
     ) -> anyhow::Result<Self> {
-        let vk = jwk.to_multibase()?;
+        let vk = Multikey::from_jwk(jwk)?;
+        let method_type = if vk.codec() == KeyCodec::Ed25519 {
+            MethodType::Ed25519VerificationKey2020
+        } else {
+            MethodType::Multikey
+        };
         let vm = VerificationMethodBuilder::new(&PublicKeyFormat::PublicKeyMultibase {
-            public_key_multibase: vk,
+            public_key_multibase: vk.to_multibase(),
         })
         .key_id(&self.did(), VmKeyId::Index("key".to_string(), 0))?
-        .method_type(&MethodType::Ed25519VerificationKey2020)?
+        .method_type(&method_type)?
         .build();
         self.doc.verification_method.get_or_insert(vec![]).push(vm.clone());
         if key_agreement {
@@ -477,7 +578,7 @@ impl DocumentBuilder {
             }
         }
         if !found {
-            bail!("verification method not found");
+            return Err(Error::Builder("verification method not found".to_string()).into());
         }
         Ok(self)
     }
@@ -505,7 +606,7 @@ impl DocumentBuilder {
         let vm = self
             .doc
             .get_verification_method(vm_id)
-            .ok_or_else(|| anyhow::anyhow!("verification method not found"))?;
+            .ok_or_else(|| Error::Builder("verification method not found".to_string()))?;
         let ka = vm.derive_key_agreement()?;
 
         self.doc.key_agreement.get_or_insert(vec![]).push(Kind::Object(ka));
@@ -525,6 +626,20 @@ impl DocumentBuilder {
         self.doc.id.clone()
     }
 
+    /// Apply a JSON Patch to the document under construction, for example one
+    /// generated with [`Document::patch_to`] between earlier versions.
+    ///
+    /// # Errors
+    /// Will fail with an [`Error::Builder`] if the patch cannot be applied or
+    /// does not produce a valid document.
+    pub fn patch(mut self, patch: &[PatchOperation]) -> anyhow::Result<Self> {
+        self.doc = self
+            .doc
+            .apply_patch(patch)
+            .map_err(|e| Error::Builder(format!("cannot apply patch: {e:#}")))?;
+        Ok(self)
+    }
+
     /// Set metadata for the document.
     #[must_use]
     pub fn metadata(mut self, md: DocumentMetadata) -> Self {
@@ -534,6 +649,9 @@ impl DocumentBuilder {
 
     /// Update metadata with created or updated timestamp and build the DID
     /// Document.
+    ///
+    /// The document's `@context` is updated to match the verification methods
+    /// and services it contains (see [`Document::update_context`]).
     #[must_use]
     pub fn build(mut self) -> Document {
         let mut md = self.doc.did_document_metadata.clone().unwrap_or_default();
@@ -542,14 +660,25 @@ impl DocumentBuilder {
             DocumentBuilderOperation::Update => md.updated = Some(chrono::Utc::now()),
         }
         self.doc.did_document_metadata = Some(md);
-        for ctx in &BASE_CONTEXT {
-            let c = Kind::String((*ctx).to_string());
-            if !self.doc.context.contains(&c) {
-                self.doc.context.push(c);
-            }
-        }
+        self.doc.update_context();
         self.doc
     }
+
+    /// Build the DID Document as for [`DocumentBuilder::build`], refusing to
+    /// emit a document that does not conform to DID Core (see
+    /// [`Document::validate`]).
+    ///
+    /// # Errors
+    /// Will fail with an [`Error::Conformance`] listing the problems found if
+    /// the document does not conform.
+    pub fn build_conformant(self) -> anyhow::Result<Document> {
+        let doc = self.build();
+        let findings = doc.validate();
+        if !findings.is_empty() {
+            return Err(Error::Conformance(findings).into());
+        }
+        Ok(doc)
+    }
 }
 
 /// Services are used to express ways of communicating with the DID subject or
@@ -580,6 +709,11 @@ pub struct Service {
     /// One or more endpoints for the service.
     #[allow(clippy::struct_field_names)]
     pub service_endpoint: OneMany<Kind<Value>>,
+
+    /// Other properties of the service, such as `routingKeys` or `accept`
+    /// for `DIDComm` messaging services.
+    #[serde(flatten)]
+    pub additional: HashMap<String, Value>,
 }
 
 /// Service builder
@@ -683,6 +817,7 @@ impl ServiceBuilder<WithType, WithEndpoint> {
             id: self.id,
             type_: self.service_type.0,
             service_endpoint: ep,
+            additional: HashMap::new(),
         }
     }
 }
@@ -742,28 +877,13 @@ impl VerificationMethod {
     /// returned, including testing the assumption that the signing key is
     /// `Ed25519` in the first place.
     pub fn derive_key_agreement(&self) -> anyhow::Result<Self> {
-        if self.type_ != MethodType::Ed25519VerificationKey2020 {
+        if !matches!(self.type_, MethodType::Ed25519VerificationKey2020 | MethodType::Multikey) {
             bail!("verification method is not an Ed25519 public key");
         }
-
-        let jwk = match &self.key {
-            PublicKeyFormat::PublicKeyJwk { public_key_jwk } => public_key_jwk.clone(),
-            PublicKeyFormat::PublicKeyMultibase { public_key_multibase } => {
-                PublicKeyJwk::from_multibase(public_key_multibase)?
-            }
-        };
-        let key_bytes = Base64UrlUnpadded::decode_vec(&jwk.x)?;
-        let pub_key = PublicKey::from_slice(&key_bytes)?;
-        let x25519_key = derive_x25519_public(&pub_key)?;
-
-        // base58B encode the raw key
-        let mut multi_bytes = vec![];
-        multi_bytes.extend_from_slice(&X25519_CODEC);
-        multi_bytes.extend_from_slice(&x25519_key.to_bytes());
-        let multikey = multibase::encode(Base::Base58Btc, &multi_bytes);
+        let multikey = self.key.multikey()?.derive_x25519()?;
 
         let vm = VerificationMethodBuilder::new(&PublicKeyFormat::PublicKeyMultibase {
-            public_key_multibase: multikey,
+            public_key_multibase: multikey.to_multibase(),
         })
         .key_id(&self.did(), VmKeyId::Did)?
         .method_type(&MethodType::X25519KeyAgreementKey2020)?
@@ -810,17 +930,25 @@ impl VerificationMethodBuilder {
             VmKeyId::Verification => {
                 let mb = match &self.vm_key {
                     PublicKeyFormat::PublicKeyJwk { public_key_jwk } => {
-                        public_key_jwk.to_multibase()?
+                        Multikey::from_jwk(public_key_jwk)?.to_multibase()
                     }
                     PublicKeyFormat::PublicKeyMultibase { public_key_multibase } => {
                         public_key_multibase.clone()
                     }
+                    PublicKeyFormat::BlockchainAccountId { .. } => {
+                        let detail =
+                            "blockchain account ID cannot be used as a verification key ID";
+                        return Err(Error::Builder(detail.to_string()).into());
+                    }
                 };
                 self.kid = format!("{did}#{mb}");
             }
             VmKeyId::Index(prefix, index) => {
                 self.kid = format!("{did}#{prefix}{index}");
             }
+            VmKeyId::Fragment(fragment) => {
+                self.kid = format!("{did}#{fragment}");
+            }
         }
         Ok(self)
     }
@@ -837,11 +965,12 @@ impl VerificationMethodBuilder {
             PublicKeyFormat::PublicKeyJwk { .. } => {
                 if !matches!(
                     mtype,
-                    MethodType::JsonWebKey2020 | MethodType::EcdsaSecp256k1VerificationKey2019
+                    MethodType::JsonWebKey2020
+                        | MethodType::JsonWebKey
+                        | MethodType::EcdsaSecp256k1VerificationKey2019
                 ) {
-                    bail!(
-                        "JWK key format only supports JsonWebKey2020 and EcdsaSecp256k1VerificationKey2019"
-                    );
+                    let detail = "JWK key format only supports JsonWebKey2020, JsonWebKey and EcdsaSecp256k1VerificationKey2019";
+                    return Err(Error::Builder(detail.to_string()).into());
                 }
             }
             PublicKeyFormat::PublicKeyMultibase { .. } => {
@@ -851,9 +980,20 @@ impl VerificationMethodBuilder {
                         | MethodType::Ed25519VerificationKey2020
                         | MethodType::X25519KeyAgreementKey2020
                 ) {
-                    bail!(
-                        "Multibase key format only supports Multikey, Ed25519VerificationKey2020 and X25519KeyAgreementKey2020"
-                    );
+                    let detail = "Multibase key format only supports Multikey, Ed25519VerificationKey2020 and X25519KeyAgreementKey2020";
+                    return Err(Error::Builder(detail.to_string()).into());
+                }
+            }
+            PublicKeyFormat::BlockchainAccountId { .. } => {
+                if !matches!(
+                    mtype,
+                    MethodType::EcdsaSecp256k1RecoveryMethod2020
+                        | MethodType::Ed25519VerificationKey2018
+                        | MethodType::Ed25519PublicKeyBlake2bDigestSize20Base58CheckEncoded2021
+                        | MethodType::P256PublicKeyBlake2bDigestSize20Base58CheckEncoded2021
+                ) {
+                    let detail = "Blockchain account ID format only supports EcdsaSecp256k1RecoveryMethod2020, Ed25519VerificationKey2018 and Tezos key hash types";
+                    return Err(Error::Builder(detail.to_string()).into());
                 }
             }
         }
@@ -896,6 +1036,9 @@ pub enum VmKeyId {
     /// With prefix `key-` and index `0`, the key ID will be
     /// `did:<method>:<method-specific-identifier>#key-0`.
     Index(String, u32),
+
+    /// Append the provided fragment to the document identifier (DID URL).
+    Fragment(String),
 }
 
 /// The format of the public key material.
@@ -914,6 +1057,12 @@ pub enum PublicKeyFormat {
         /// The public key encoded as a JWK.
         public_key_jwk: PublicKeyJwk,
     },
+
+    /// The key is identified by a blockchain account rather than included.
+    BlockchainAccountId {
+        /// The CAIP-10 account ID of the key.
+        blockchain_account_id: String,
+    },
 }
 
 impl Default for PublicKeyFormat {
@@ -934,7 +1083,10 @@ impl PublicKeyFormat {
         match self {
             Self::PublicKeyJwk { public_key_jwk } => Ok(public_key_jwk.clone()),
             Self::PublicKeyMultibase { public_key_multibase } => {
-                PublicKeyJwk::from_multibase(public_key_multibase)
+                Multikey::from_multibase(public_key_multibase)?.to_jwk()
+            }
+            Self::BlockchainAccountId { .. } => {
+                bail!("blockchain account ID does not contain a public key")
             }
         }
     }
@@ -946,8 +1098,30 @@ impl PublicKeyFormat {
     /// multibase string.
     pub fn multibase(&self) -> anyhow::Result<String> {
         match self {
-            Self::PublicKeyJwk { public_key_jwk } => public_key_jwk.to_multibase(),
+            Self::PublicKeyJwk { public_key_jwk } => {
+                Ok(Multikey::from_jwk(public_key_jwk)?.to_multibase())
+            }
             Self::PublicKeyMultibase { public_key_multibase } => Ok(public_key_multibase.clone()),
+            Self::BlockchainAccountId { .. } => {
+                bail!("blockchain account ID does not contain a public key")
+            }
+        }
+    }
+
+    /// Return the key as a decoded [`Multikey`].
+    ///
+    /// # Errors
+    /// Will return an error if the key cannot be converted to a supported
+    /// multikey type.
+    pub fn multikey(&self) -> anyhow::Result<Multikey> {
+        match self {
+            Self::PublicKeyJwk { public_key_jwk } => Multikey::from_jwk(public_key_jwk),
+            Self::PublicKeyMultibase { public_key_multibase } => {
+                Multikey::from_multibase(public_key_multibase)
+            }
+            Self::BlockchainAccountId { .. } => {
+                bail!("blockchain account ID does not contain a public key")
+            }
         }
     }
 }
@@ -970,8 +1144,26 @@ pub enum MethodType {
     /// JSON Web Key (JWK), version 2020.
     JsonWebKey2020,
 
+    /// JSON Web Key (JWK) as defined by Controlled Identifiers v1.0.
+    JsonWebKey,
+
     /// Secp256k1 Verification Key, version 2019.
     EcdsaSecp256k1VerificationKey2019,
+
+    /// Secp256k1 key recoverable from a signature, identified by a blockchain
+    /// account.
+    EcdsaSecp256k1RecoveryMethod2020,
+
+    /// `ED25519` Verification key, version 2018.
+    Ed25519VerificationKey2018,
+
+    /// `Ed25519` key identified by a Tezos (`tz1`) account.
+    #[serde(rename = "Ed25519PublicKeyBLAKE2BDigestSize20Base58CheckEncoded2021")]
+    Ed25519PublicKeyBlake2bDigestSize20Base58CheckEncoded2021,
+
+    /// `P-256` key identified by a Tezos (`tz3`) account.
+    #[serde(rename = "P256PublicKeyBLAKE2BDigestSize20Base58CheckEncoded2021")]
+    P256PublicKeyBlake2bDigestSize20Base58CheckEncoded2021,
 }
 
 impl Display for MethodType {
@@ -981,9 +1173,18 @@ impl Display for MethodType {
             Self::Ed25519VerificationKey2020 => write!(f, "Ed25519VerificationKey2020"),
             Self::X25519KeyAgreementKey2020 => write!(f, "X25519KeyAgreementKey2020"),
             Self::JsonWebKey2020 => write!(f, "JsonWebKey2020"),
+            Self::JsonWebKey => write!(f, "JsonWebKey"),
             Self::EcdsaSecp256k1VerificationKey2019 => {
                 write!(f, "EcdsaSecp256k1VerificationKey2019")
             }
+            Self::EcdsaSecp256k1RecoveryMethod2020 => write!(f, "EcdsaSecp256k1RecoveryMethod2020"),
+            Self::Ed25519VerificationKey2018 => write!(f, "Ed25519VerificationKey2018"),
+            Self::Ed25519PublicKeyBlake2bDigestSize20Base58CheckEncoded2021 => {
+                write!(f, "Ed25519PublicKeyBLAKE2BDigestSize20Base58CheckEncoded2021")
+            }
+            Self::P256PublicKeyBlake2bDigestSize20Base58CheckEncoded2021 => {
+                write!(f, "P256PublicKeyBLAKE2BDigestSize20Base58CheckEncoded2021")
+            }
         }
     }
 }
@@ -0,0 +1,137 @@
+//! # Hashlinks
+//!
+//! Integrity protection for DID URLs using the `hl` query parameter. The
+//! parameter is a [Hashlink] of the DID document (or, for a DID URL path
+//! identifying a file, the file's content): a multibase-encoded multihash of
+//! the resource.
+//!
+//! Hashlinks are generated using SHA2-256 and `base58btc`. SHA2-256 and
+//! SHA2-512 hashlinks in any multibase encoding are accepted when verifying.
+//!
+//! A DID document is hashed using the JSON Canonicalization Scheme, without
+//! its document metadata.
+//!
+//! [Hashlink]: https://datatracker.ietf.org/doc/html/draft-sporny-hashlink
+
+use multibase::Base;
+use sha2::{Digest, Sha256, Sha512};
+
+use super::{Document, ErrorCode, QueryParams, ResolutionOptions, Url};
+use crate::Error;
+
+const SHA2_256: [u8; 2] = [0x12, 0x20];
+const SHA2_512: [u8; 2] = [0x13, 0x40];
+
+/// Generate the hashlink of a resource's content.
+#[must_use]
+pub fn hashlink(content: &[u8]) -> String {
+    let mut multihash = SHA2_256.to_vec();
+    multihash.extend_from_slice(&Sha256::digest(content));
+    multibase::encode(Base::Base58Btc, multihash)
+}
+
+/// Verify a resource's content matches a hashlink.
+///
+/// Only the hash part of the hashlink is used: any metadata (following a
+/// `:`) is ignored.
+///
+/// # Errors
+///
+/// Will fail if the hashlink is not valid or uses an unsupported hash
+/// algorithm, or with an [`Error::Hashlink`] if the content does not match.
+pub fn verify_hashlink(hl: &str, content: &[u8]) -> anyhow::Result<()> {
+    let encoded = hl.split(':').next().unwrap_or_default();
+    let invalid = |detail: &str| Error::resolution(ErrorCode::InvalidDidUrl, detail);
+    let (_, multihash) = multibase::decode(encoded).map_err(|_| invalid("invalid hashlink"))?;
+
+    let matches = match multihash.split_at_checked(2) {
+        Some((prefix, digest)) if prefix == SHA2_256 => {
+            digest == Sha256::digest(content).as_slice()
+        }
+        Some((prefix, digest)) if prefix == SHA2_512 => {
+            digest == Sha512::digest(content).as_slice()
+        }
+        _ => return Err(invalid("unsupported hashlink algorithm").into()),
+    };
+    if !matches {
+        return Err(Error::Hashlink(format!("resource does not match hashlink {hl}")).into());
+    }
+    Ok(())
+}
+
+impl Document {
+    /// Generate the hashlink of the DID document.
+    ///
+    /// # Errors
+    ///
+    /// Will fail if the document cannot be serialized.
+    pub fn hashlink(&self) -> anyhow::Result<String> {
+        Ok(hashlink(&document_bytes(self)?))
+    }
+
+    /// Generate a DID URL for the document that carries its hashlink as the
+    /// `hl` query parameter. Resolving the URL will fail unless the resolved
+    /// document matches.
+    ///
+    /// For documents with a version ID in their metadata (such as
+    /// `did:webvh`), the version is also added to the URL so the document
+    /// continues to resolve after the DID is updated.
+    ///
+    /// # Errors
+    ///
+    /// Will fail if the document ID is not a valid DID or the document cannot
+    /// be serialized.
+    pub fn hashlink_url(&self) -> anyhow::Result<String> {
+        let version_id = self
+            .did_document_metadata
+            .as_ref()
+            .and_then(|md| md.additional.as_ref())
+            .and_then(|a| a.get("versionId"))
+            .and_then(|v| v.as_str())
+            .map(ToString::to_string);
+        let url = Url {
+            query: Some(QueryParams {
+                version_id,
+                hashlink: Some(self.hashlink()?),
+                ..QueryParams::default()
+            }),
+            ..self.id.parse::<Url>()?
+        };
+        Ok(url.to_string())
+    }
+}
+
+// Verify a DID document matches the expected hash, if any, given by the DID
+// URL's hashlink or the `expectedHash` resolution option.
+pub fn verify_document(
+    url: &Url, options: Option<&ResolutionOptions>, doc: &Document,
+) -> anyhow::Result<()> {
+    let Some(hl) = expected_hash(url, options)? else {
+        return Ok(());
+    };
+    verify_hashlink(&hl, &document_bytes(doc)?)
+}
+
+// Verify a file's content matches the expected hash, if any, given by the DID
+// URL's hashlink or the `expectedHash` resolution option.
+pub fn verify_content(
+    url: &Url, options: Option<&ResolutionOptions>, content: &[u8],
+) -> anyhow::Result<()> {
+    let Some(hl) = expected_hash(url, options)? else {
+        return Ok(());
+    };
+    verify_hashlink(&hl, content)
+}
+
+fn expected_hash(url: &Url, options: Option<&ResolutionOptions>) -> anyhow::Result<Option<String>> {
+    Ok(ResolutionOptions::merge(options, url.query.as_ref())?.expected_hash)
+}
+
+// The canonical serialization of a DID document, without its metadata.
+fn document_bytes(doc: &Document) -> anyhow::Result<Vec<u8>> {
+    let doc = Document {
+        did_document_metadata: None,
+        ..doc.clone()
+    };
+    Ok(serde_json_canonicalizer::to_string(&doc)?.into_bytes())
+}
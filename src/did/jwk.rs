@@ -0,0 +1,140 @@
+//! # DID JWK
+//!
+//! The `did:jwk` method encodes a public key JWK directly in the DID. The
+//! method-specific identifier is the base64url-encoded JSON serialization of
+//! the JWK, so, like `did:key`, the DID document is expanded from the DID
+//! itself.
+//!
+//! See:
+//!
+//! - <https://github.com/quartzjer/did-jwk/blob/main/spec.md>
+//! - <https://w3c.github.io/did-resolution>
+
+mod resolve;
+
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+use anyhow::bail;
+use base64ct::{Base64UrlUnpadded, Encoding};
+use credibil_jose::PublicKeyJwk;
+pub use resolve::*;
+use serde_json::Value;
+
+use crate::did::{Method, Url};
+
+/// The fragment identifying the single verification method of a `did:jwk`.
+pub const VERIFICATION_METHOD_ID: &str = "0";
+
+/// A validated `did:jwk` identifier.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DidJwk {
+    id: String,
+    jwk: PublicKeyJwk,
+}
+
+impl DidJwk {
+    /// Create a `did:jwk` from a public key JWK.
+    ///
+    /// The method-specific identifier is the base64url encoding of the
+    /// canonical (JCS) serialization of the JWK.
+    ///
+    /// # Errors
+    /// Will fail if the JWK contains private key material or cannot be
+    /// serialized.
+    pub fn from_jwk(jwk: &PublicKeyJwk) -> anyhow::Result<Self> {
+        check_public(&serde_json::to_value(jwk)?)?;
+        let json = serde_json_canonicalizer::to_string(jwk)?;
+        Ok(Self {
+            id: Base64UrlUnpadded::encode_string(json.as_bytes()),
+            jwk: jwk.clone(),
+        })
+    }
+
+    /// Create a `did:jwk` from a DID or DID URL.
+    ///
+    /// # Errors
+    /// Will fail if the URL is not a `did:jwk`, the method-specific identifier
+    /// is not a base64url-encoded public key JWK, or a fragment other than
+    /// `#0` is present.
+    pub fn from_url(url: &Url) -> anyhow::Result<Self> {
+        if url.method != Method::Jwk {
+            bail!("DID is not a valid did:jwk: {url}");
+        }
+        if url.path.is_some() {
+            bail!("DID is not a valid did:jwk - paths are not supported");
+        }
+        if let Some(fragment) = &url.fragment {
+            if fragment != VERIFICATION_METHOD_ID {
+                bail!("DID URL fragment does not identify the did:jwk verification method");
+            }
+        }
+        let Ok(decoded) = Base64UrlUnpadded::decode_vec(&url.id) else {
+            bail!("DID is not a valid did:jwk - method-specific id must be base64url encoded");
+        };
+        let value: Value = serde_json::from_slice(&decoded)?;
+        check_public(&value)?;
+        let jwk: PublicKeyJwk = serde_json::from_value(value)?;
+
+        // Keep the identifier as given: it need not be canonical.
+        Ok(Self {
+            id: url.id.clone(),
+            jwk,
+        })
+    }
+
+    /// The DID, without a fragment.
+    #[must_use]
+    pub fn did(&self) -> String {
+        format!("did:jwk:{}", self.id)
+    }
+
+    /// The ID of the verification method for the public key.
+    #[must_use]
+    pub fn verification_method_id(&self) -> String {
+        format!("{}#{VERIFICATION_METHOD_ID}", self.did())
+    }
+
+    /// The public key JWK.
+    #[must_use]
+    pub const fn public_key_jwk(&self) -> &PublicKeyJwk {
+        &self.jwk
+    }
+
+    /// The intended use of the key (`sig` or `enc`), if specified.
+    #[must_use]
+    pub fn key_use(&self) -> Option<String> {
+        let value = serde_json::to_value(&self.jwk).ok()?;
+        value.get("use").and_then(Value::as_str).map(ToString::to_string)
+    }
+}
+
+impl TryFrom<&Url> for DidJwk {
+    type Error = anyhow::Error;
+
+    fn try_from(url: &Url) -> anyhow::Result<Self> {
+        Self::from_url(url)
+    }
+}
+
+impl FromStr for DidJwk {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        Self::from_url(&Url::from_str(s)?)
+    }
+}
+
+impl Display for DidJwk {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.did())
+    }
+}
+
+// A `did:jwk` must not contain a private key.
+fn check_public(jwk: &Value) -> anyhow::Result<()> {
+    if jwk.get("d").is_some() {
+        bail!("did:jwk must not contain private key material");
+    }
+    Ok(())
+}
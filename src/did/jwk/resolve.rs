@@ -0,0 +1,83 @@
+//! Resolution of a DID into a DID document using the `did:jwk` method.
+
+use super::DidJwk;
+use crate::core::Kind;
+use crate::did::{
+    Document, DocumentBuilder, KeyPurpose, MethodType, PublicKeyFormat, Resource, Url,
+    VerificationMethod, VerificationMethodBuilder, VmKeyId, document_resource,
+};
+
+/// Resolve a `did:jwk` URL into a [`Resource`].
+///
+/// A URL without a fragment resolves to the full document and `#0` resolves
+/// to the verification method.
+///
+/// # Errors
+/// If the URL is not a valid `did:jwk` URL or the requested resource is not in
+/// the expanded document, an error is returned.
+pub fn resolve(url: &Url) -> anyhow::Result<Resource> {
+    let doc = DidJwk::from_url(url)?.document()?;
+    document_resource(url, None, &doc)
+}
+
+/// Expand a `did:jwk` URL into a DID document.
+///
+/// See [`DidJwk::document`].
+///
+/// # Errors
+/// If the URL is not a valid `did:jwk` URL or the key cannot be decoded, an
+/// error is returned.
+pub fn document(url: &Url) -> anyhow::Result<Document> {
+    DidJwk::from_url(url)?.document()
+}
+
+impl DidJwk {
+    /// Expand the `did:jwk` into a DID document.
+    ///
+    /// The JWK is used as a `JsonWebKey2020` verification method with the ID
+    /// `#0`. Verification relationships are set according to the key's `use`
+    /// parameter: `sig` keys are used for `authentication`, `assertionMethod`,
+    /// `capabilityInvocation` and `capabilityDelegation`, `enc` keys for
+    /// `keyAgreement` and keys without a `use` for all relationships.
+    ///
+    /// See <https://github.com/quartzjer/did-jwk/blob/main/spec.md#read>
+    ///
+    /// # Errors
+    /// If the document cannot be built from the key, an error is returned.
+    pub fn document(&self) -> anyhow::Result<Document> {
+        let did = self.did();
+
+        let vm = VerificationMethodBuilder::new(&PublicKeyFormat::PublicKeyJwk {
+            public_key_jwk: self.public_key_jwk().clone(),
+        })
+        .key_id(&did, VmKeyId::Index(String::new(), 0))?
+        .method_type(&MethodType::JsonWebKey2020)?
+        .build();
+        let vm_ref = Kind::<VerificationMethod>::String(vm.id.clone());
+
+        let key_use = self.key_use();
+        let mut builder = DocumentBuilder::new(&did)
+            .add_verification_method(&Kind::Object(vm), &KeyPurpose::VerificationMethod)?;
+        if key_use.as_deref() != Some("enc") {
+            builder = builder
+                .add_verification_method(&vm_ref, &KeyPurpose::Authentication)?
+                .add_verification_method(&vm_ref, &KeyPurpose::AssertionMethod)?
+                .add_verification_method(&vm_ref, &KeyPurpose::CapabilityInvocation)?
+                .add_verification_method(&vm_ref, &KeyPurpose::CapabilityDelegation)?;
+        }
+        let mut doc = builder.build();
+
+        // The builder only accepts embedded key agreement methods but here the
+        // key agreement key is the (only) verification method so is
+        // referenced.
+        if key_use.as_deref() != Some("sig") {
+            doc.key_agreement = Some(vec![vm_ref]);
+        }
+
+        // A `did:jwk` document is derived, never created or updated, so there
+        // is no meaningful metadata.
+        doc.did_document_metadata = None;
+
+        Ok(doc)
+    }
+}
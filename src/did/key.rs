@@ -11,14 +11,144 @@
 
 mod resolve;
 
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+use anyhow::bail;
 use credibil_jose::PublicKeyJwk;
-pub use resolve::*;
+pub(crate) use resolve::expand;
+pub use resolve::{document, resolve};
+
+use crate::did::{KeyCodec, Method, Multikey, Url};
+
+/// A validated `did:key` identifier.
+///
+/// The method-specific identifier of a `did:key` is the multibase-encoded
+/// public key so the DID, its verification method and the public key are all
+/// derived from the same value.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DidKey {
+    multikey: Multikey,
+}
+
+impl DidKey {
+    /// Create a `did:key` from a public key.
+    #[must_use]
+    pub const fn new(multikey: Multikey) -> Self {
+        Self { multikey }
+    }
+
+    /// Create a `did:key` from a public key JWK.
+    ///
+    /// Supports `Ed25519`, `X25519`, `P-256`, `P-384` and `secp256k1` keys.
+    ///
+    /// # Errors
+    /// Will fail if the public key cannot be converted to multibase form.
+    pub fn from_jwk(jwk: &PublicKeyJwk) -> anyhow::Result<Self> {
+        Ok(Self::new(Multikey::from_jwk(jwk)?))
+    }
+
+    /// Create a `did:key` from a DID or DID URL.
+    ///
+    /// A fragment, if present, must identify a verification method of the
+    /// DID: either the key itself or, for `Ed25519` keys, the derived `X25519`
+    /// key agreement key.
+    ///
+    /// # Errors
+    /// Will fail if the URL is not a `did:key`, the key has an unsupported
+    /// multicodec prefix or is otherwise invalid, or the fragment does not
+    /// match the key.
+    pub fn from_url(url: &Url) -> anyhow::Result<Self> {
+        if url.method != Method::Key {
+            bail!("DID is not a valid did:key: {url}");
+        }
+        if !url.id.starts_with('z') {
+            bail!("DID is not a valid did:key - method-specific id must be base58btc encoded");
+        }
+        if url.path.is_some() {
+            bail!("DID is not a valid did:key - paths are not supported");
+        }
+        let did_key = Self::new(Multikey::from_multibase(&url.id)?);
+
+        if let Some(fragment) = &url.fragment {
+            let key_agreement = did_key.key_agreement()?.map(|ka| ka.to_multibase());
+            if *fragment != url.id && Some(fragment) != key_agreement.as_ref() {
+                bail!("DID URL fragment does not match the did:key public key");
+            }
+        }
+        Ok(did_key)
+    }
+
+    /// The DID, without a fragment.
+    #[must_use]
+    pub fn did(&self) -> String {
+        format!("did:key:{}", self.multikey)
+    }
+
+    /// The ID of the verification method for the public key.
+    #[must_use]
+    pub fn verification_method_id(&self) -> String {
+        format!("{}#{}", self.did(), self.multikey)
+    }
+
+    /// The public key as a JWK.
+    ///
+    /// # Errors
+    /// Will fail if the key cannot be converted to a JWK.
+    pub fn public_key_jwk(&self) -> anyhow::Result<PublicKeyJwk> {
+        self.multikey.to_jwk()
+    }
 
-/// Construct a `did:key` from a public key.
+    /// The public key.
+    #[must_use]
+    pub const fn multikey(&self) -> &Multikey {
+        &self.multikey
+    }
+
+    /// The `X25519` key agreement key derived from an `Ed25519` public key.
+    ///
+    /// Returns `None` for other key types.
+    ///
+    /// # Errors
+    /// Will fail if the `Ed25519` key cannot be converted to `X25519`.
+    pub fn key_agreement(&self) -> anyhow::Result<Option<Multikey>> {
+        if self.multikey.codec() != KeyCodec::Ed25519 {
+            return Ok(None);
+        }
+        Ok(Some(self.multikey.derive_x25519()?))
+    }
+}
+
+impl TryFrom<&Url> for DidKey {
+    type Error = anyhow::Error;
+
+    fn try_from(url: &Url) -> anyhow::Result<Self> {
+        Self::from_url(url)
+    }
+}
+
+impl FromStr for DidKey {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        Self::from_url(&Url::from_str(s)?)
+    }
+}
+
+impl Display for DidKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.did())
+    }
+}
+
+/// Construct a `did:key` verification method ID from a public key.
+///
+/// Note that the value returned is the DID URL of the verification method
+/// (`did:key:{multi}#{multi}`) rather than the DID.
 ///
 /// # Errors
 /// Will fail if the public key cannot be converted to multibase form.
+#[deprecated(note = "use `DidKey::from_jwk` and `DidKey::did` or `DidKey::verification_method_id`")]
 pub fn did_from_jwk(jwk: &PublicKeyJwk) -> anyhow::Result<String> {
-    let multi = jwk.to_multibase()?;
-    Ok(format!("did:key:{multi}#{multi}"))
+    Ok(DidKey::from_jwk(jwk)?.verification_method_id())
 }
@@ -1,30 +1,93 @@
-//! Resolution of a DID into a public key using the `did:jwk` method.
+//! Resolution of a DID into a DID document using the `did:key` method.
 
-use anyhow::bail;
+use super::DidKey;
+use crate::core::Kind;
+use crate::did::{
+    Document, DocumentBuilder, KeyCodec, KeyPurpose, MethodType, Multikey, PublicKeyFormat,
+    Resource, Url, VerificationMethod, VerificationMethodBuilder, VmKeyId, document_resource,
+};
 
-use crate::did::{Method, MethodType, PublicKeyFormat, Resource, Url, VerificationMethod};
-
-/// Convert a `did:key` URL into a [`VerificationMethod`] object.
+/// Resolve a `did:key` URL into a [`Resource`].
+///
+/// The DID document is expanded from the method-specific identifier and the
+/// resource requested by the URL is then taken from the document. A URL
+/// without a fragment resolves to the full document.
 ///
 /// # Errors
-/// If the URL is not a valid `did:key` URL, an error is returned.
+/// If the URL is not a valid `did:key` URL or the requested resource is not in
+/// the expanded document, an error is returned.
 pub fn resolve(url: &Url) -> anyhow::Result<Resource> {
-    if url.method != Method::Key {
-        bail!("DID is not a valid did:key: {url}");
+    let doc = DidKey::from_url(url)?.document()?;
+    document_resource(url, None, &doc)
+}
+
+/// Expand a `did:key` URL into a DID document.
+///
+/// See [`DidKey::document`].
+///
+/// # Errors
+/// If the URL is not a valid `did:key` URL or the key cannot be decoded, an
+/// error is returned.
+pub fn document(url: &Url) -> anyhow::Result<Document> {
+    DidKey::from_url(url)?.document()
+}
+
+impl DidKey {
+    /// Expand the `did:key` into a DID document.
+    ///
+    /// Implements the `did:key` document creation algorithm: the
+    /// method-specific identifier is the multibase-encoded public key and is
+    /// used to create a single verification method that is referenced by the
+    /// `authentication`, `assertionMethod`, `capabilityInvocation` and
+    /// `capabilityDelegation` relationships. For `Ed25519` keys, an `X25519`
+    /// key agreement method is derived from the signing key. `P-256`, `P-384`
+    /// and `secp256k1` keys are used as-is and have no key agreement method.
+    ///
+    /// See <https://w3c-ccg.github.io/did-method-key/#document-creation-algorithm>
+    ///
+    /// # Errors
+    /// If the document cannot be built from the key, an error is returned.
+    pub fn document(&self) -> anyhow::Result<Document> {
+        expand(&self.did(), self.multikey())
     }
-    // For did:key, the fragment is the key already multibase encoded. There is
-    // no need to use a builder.
-    let Some(fragment) = &url.fragment else {
-        bail!("DID is not a valid did:key - there is no fragment");
-    };
-    let vm = VerificationMethod {
-        context: None,
-        id: url.resource_id(),
-        type_: MethodType::Multikey,
-        controller: url.did(),
-        key: PublicKeyFormat::PublicKeyMultibase {
-            public_key_multibase: fragment.to_string(),
-        },
-    };
-    Ok(Resource::VerificationMethod(vm))
+}
+
+/// Expand a single key into a DID document using the `did:key` document
+/// creation algorithm. Also used by `did:peer` numalgo 0, which differs from
+/// `did:key` only in the DID.
+///
+/// # Errors
+/// If the document cannot be built from the key, an error is returned.
+pub fn expand(did: &str, multikey: &Multikey) -> anyhow::Result<Document> {
+    let vm = VerificationMethodBuilder::new(&PublicKeyFormat::PublicKeyMultibase {
+        public_key_multibase: multikey.to_multibase(),
+    })
+    .key_id(did, VmKeyId::Verification)?
+    .method_type(&MethodType::Multikey)?
+    .build();
+    let vm_ref = Kind::<VerificationMethod>::String(vm.id.clone());
+
+    let mut builder = DocumentBuilder::new(did)
+        .add_verification_method(&Kind::Object(vm), &KeyPurpose::VerificationMethod)?
+        .add_verification_method(&vm_ref, &KeyPurpose::Authentication)?
+        .add_verification_method(&vm_ref, &KeyPurpose::AssertionMethod)?
+        .add_verification_method(&vm_ref, &KeyPurpose::CapabilityInvocation)?
+        .add_verification_method(&vm_ref, &KeyPurpose::CapabilityDelegation)?;
+    if multikey.codec() == KeyCodec::Ed25519 {
+        // the key agreement method is identified by its own multibase key
+        let ka = VerificationMethodBuilder::new(&PublicKeyFormat::PublicKeyMultibase {
+            public_key_multibase: multikey.derive_x25519()?.to_multibase(),
+        })
+        .key_id(did, VmKeyId::Verification)?
+        .method_type(&MethodType::X25519KeyAgreementKey2020)?
+        .build();
+        builder = builder.add_verification_method(&Kind::Object(ka), &KeyPurpose::KeyAgreement)?;
+    }
+    let mut doc = builder.build();
+
+    // The document is derived, never created or updated, so there is no
+    // meaningful metadata.
+    doc.did_document_metadata = None;
+
+    Ok(doc)
 }
@@ -0,0 +1,396 @@
+//! # Multikey
+//!
+//! Conversion between multibase-encoded public keys (as used by `did:key` and
+//! the `Multikey` verification method type) and JWKs.
+//!
+//! A multikey is the base58-btc multibase encoding of a multicodec
+//! (unsigned varint) key type prefix followed by the raw public key bytes.
+//! Elliptic curve keys are always in compressed SEC1 form.
+//!
+//! See:
+//!
+//! - <https://www.w3.org/TR/cid-1.0/#Multikey>
+//! - <https://github.com/multiformats/multicodec/blob/master/table.csv>
+
+use std::fmt::{self, Display, Formatter};
+
+use anyhow::{anyhow, bail};
+use base64ct::{Base64UrlUnpadded, Encoding};
+use credibil_jose::PublicKeyJwk;
+use credibil_se::{PublicKey, derive_x25519_public};
+use multibase::Base;
+use p256::elliptic_curve::sec1::ToEncodedPoint;
+use serde_json::{Value, json};
+
+/// Public key types that can be expressed as a multikey.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyCodec {
+    /// `ed25519-pub` (0xed). `did:key` identifiers start with `z6Mk`.
+    Ed25519,
+
+    /// `x25519-pub` (0xec). `did:key` identifiers start with `z6LS`.
+    X25519,
+
+    /// `secp256k1-pub` (0xe7). `did:key` identifiers start with `zQ3s`.
+    Secp256k1,
+
+    /// `p256-pub` (0x1200). `did:key` identifiers start with `zDn`.
+    P256,
+
+    /// `p384-pub` (0x1201). `did:key` identifiers start with `z82`.
+    P384,
+}
+
+impl KeyCodec {
+    /// The unsigned varint encoding of the multicodec value.
+    #[must_use]
+    pub const fn prefix(self) -> [u8; 2] {
+        match self {
+            Self::Ed25519 => [0xed, 0x01],
+            Self::X25519 => [0xec, 0x01],
+            Self::Secp256k1 => [0xe7, 0x01],
+            Self::P256 => [0x80, 0x24],
+            Self::P384 => [0x81, 0x24],
+        }
+    }
+
+    /// Find the codec for a multicodec-prefixed key.
+    ///
+    /// # Errors
+    /// Will fail if the prefix is not one supported by this crate.
+    pub fn from_prefix(bytes: &[u8]) -> anyhow::Result<Self> {
+        let Some(prefix) = bytes.get(0..2) else {
+            bail!("multikey is too short to contain a multicodec prefix");
+        };
+        [Self::Ed25519, Self::X25519, Self::Secp256k1, Self::P256, Self::P384]
+            .into_iter()
+            .find(|codec| codec.prefix() == prefix)
+            .ok_or_else(|| {
+                anyhow!("unsupported multicodec prefix: 0x{:02x}{:02x}", prefix[0], prefix[1])
+            })
+    }
+
+    /// The length in bytes of the raw (compressed) public key.
+    #[must_use]
+    pub const fn key_len(self) -> usize {
+        match self {
+            Self::Ed25519 | Self::X25519 => 32,
+            Self::Secp256k1 | Self::P256 => 33,
+            Self::P384 => 49,
+        }
+    }
+
+    /// The JWK `kty` (key type) parameter for the codec.
+    #[must_use]
+    pub const fn key_type(self) -> &'static str {
+        match self {
+            Self::Ed25519 | Self::X25519 => "OKP",
+            Self::Secp256k1 | Self::P256 | Self::P384 => "EC",
+        }
+    }
+
+    /// The JWK `crv` (curve) parameter for the codec.
+    #[must_use]
+    pub const fn curve(self) -> &'static str {
+        match self {
+            Self::Ed25519 => "Ed25519",
+            Self::X25519 => "X25519",
+            Self::Secp256k1 => "secp256k1",
+            Self::P256 => "P-256",
+            Self::P384 => "P-384",
+        }
+    }
+
+    /// Find the codec for a JWK `crv` parameter.
+    ///
+    /// # Errors
+    /// Will fail if the curve is not one supported by this crate.
+    pub fn from_curve(crv: &str) -> anyhow::Result<Self> {
+        match crv {
+            "Ed25519" => Ok(Self::Ed25519),
+            "X25519" => Ok(Self::X25519),
+            "secp256k1" => Ok(Self::Secp256k1),
+            "P-256" => Ok(Self::P256),
+            "P-384" => Ok(Self::P384),
+            _ => Err(anyhow!("unsupported curve: {crv}")),
+        }
+    }
+}
+
+impl Display for KeyCodec {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.curve())
+    }
+}
+
+/// A public key with its multicodec key type.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Multikey {
+    codec: KeyCodec,
+    key: Vec<u8>,
+}
+
+impl Multikey {
+    /// Create a multikey from raw public key bytes.
+    ///
+    /// Elliptic curve keys must be in compressed SEC1 form.
+    ///
+    /// # Errors
+    /// Will fail if the key is the wrong length for the codec or is not a
+    /// valid point on the curve.
+    pub fn new(codec: KeyCodec, key: &[u8]) -> anyhow::Result<Self> {
+        if key.len() != codec.key_len() {
+            bail!("{codec} key must be {} bytes, got {}", codec.key_len(), key.len());
+        }
+        // Check the point is on the curve.
+        match codec {
+            KeyCodec::Ed25519 | KeyCodec::X25519 => {}
+            KeyCodec::Secp256k1 => {
+                k256::PublicKey::from_sec1_bytes(key)
+                    .map_err(|_| anyhow!("invalid {codec} key"))?;
+            }
+            KeyCodec::P256 => {
+                p256::PublicKey::from_sec1_bytes(key)
+                    .map_err(|_| anyhow!("invalid {codec} key"))?;
+            }
+            KeyCodec::P384 => {
+                p384::PublicKey::from_sec1_bytes(key)
+                    .map_err(|_| anyhow!("invalid {codec} key"))?;
+            }
+        }
+        Ok(Self {
+            codec,
+            key: key.to_vec(),
+        })
+    }
+
+    /// Decode a multibase-encoded multikey.
+    ///
+    /// # Errors
+    /// Will fail if the value is not base58-btc encoded, has an unsupported
+    /// multicodec prefix or the key is invalid for its type.
+    pub fn from_multibase(multibase: &str) -> anyhow::Result<Self> {
+        let (base, bytes) = multibase::decode(multibase)?;
+        if base != Base::Base58Btc {
+            bail!("multikey must be base58-btc encoded");
+        }
+        let codec = KeyCodec::from_prefix(&bytes)?;
+        Self::new(codec, &bytes[2..])
+    }
+
+    /// Encode the key as a base58-btc multibase string.
+    #[must_use]
+    pub fn to_multibase(&self) -> String {
+        let bytes = [self.codec.prefix().as_slice(), &self.key].concat();
+        multibase::encode(Base::Base58Btc, bytes)
+    }
+
+    /// Convert a JWK into a multikey, compressing elliptic curve points.
+    ///
+    /// # Errors
+    /// Will fail if the JWK is for an unsupported curve, its key type does not
+    /// match the curve, or its coordinates are malformed or not a point on the
+    /// curve.
+    pub fn from_jwk(jwk: &PublicKeyJwk) -> anyhow::Result<Self> {
+        let value = serde_json::to_value(jwk)?;
+        let param = |name: &str| value.get(name).and_then(Value::as_str);
+
+        let Some(crv) = param("crv") else {
+            bail!("JWK has no curve");
+        };
+        let codec = KeyCodec::from_curve(crv)?;
+        let kty = param("kty").unwrap_or_default();
+        if kty != codec.key_type() {
+            bail!("{crv} JWK must have key type {}, not {kty}", codec.key_type());
+        }
+        let Some(x) = param("x") else {
+            bail!("JWK has no x coordinate");
+        };
+        let x =
+            Base64UrlUnpadded::decode_vec(x).map_err(|e| anyhow!("invalid x coordinate: {e}"))?;
+
+        // validate the full point is on the curve before compressing it
+        let point = || {
+            let Some(y) = param("y") else {
+                bail!("{codec} JWK has no y coordinate");
+            };
+            let y = Base64UrlUnpadded::decode_vec(y)
+                .map_err(|e| anyhow!("invalid y coordinate: {e}"))?;
+            Ok([&[0x04], x.as_slice(), &y].concat())
+        };
+        let invalid = |_| anyhow!("{codec} JWK coordinates are not a valid point");
+        let key = match codec {
+            KeyCodec::Ed25519 | KeyCodec::X25519 => x.clone(),
+            KeyCodec::Secp256k1 => k256::PublicKey::from_sec1_bytes(&point()?)
+                .map_err(invalid)?
+                .to_encoded_point(true)
+                .as_bytes()
+                .to_vec(),
+            KeyCodec::P256 => p256::PublicKey::from_sec1_bytes(&point()?)
+                .map_err(invalid)?
+                .to_encoded_point(true)
+                .as_bytes()
+                .to_vec(),
+            KeyCodec::P384 => p384::PublicKey::from_sec1_bytes(&point()?)
+                .map_err(invalid)?
+                .to_encoded_point(true)
+                .as_bytes()
+                .to_vec(),
+        };
+        Self::new(codec, &key)
+    }
+
+    /// Convert the multikey into a JWK, decompressing elliptic curve points.
+    ///
+    /// # Errors
+    /// Will fail if an elliptic curve point cannot be decompressed.
+    pub fn to_jwk(&self) -> anyhow::Result<PublicKeyJwk> {
+        let invalid = |_| anyhow!("invalid {} key", self.codec);
+        let (x, y) = match self.codec {
+            KeyCodec::Ed25519 | KeyCodec::X25519 => (self.key.clone(), None),
+            KeyCodec::Secp256k1 => {
+                let point = k256::PublicKey::from_sec1_bytes(&self.key).map_err(invalid)?;
+                let point = point.to_encoded_point(false);
+                (point.x().map(|x| x.to_vec()).unwrap_or_default(), point.y().map(|y| y.to_vec()))
+            }
+            KeyCodec::P256 => {
+                let point = p256::PublicKey::from_sec1_bytes(&self.key).map_err(invalid)?;
+                let point = point.to_encoded_point(false);
+                (point.x().map(|x| x.to_vec()).unwrap_or_default(), point.y().map(|y| y.to_vec()))
+            }
+            KeyCodec::P384 => {
+                let point = p384::PublicKey::from_sec1_bytes(&self.key).map_err(invalid)?;
+                let point = point.to_encoded_point(false);
+                (point.x().map(|x| x.to_vec()).unwrap_or_default(), point.y().map(|y| y.to_vec()))
+            }
+        };
+
+        let mut jwk = json!({
+            "kty": self.codec.key_type(),
+            "crv": self.codec.curve(),
+            "x": Base64UrlUnpadded::encode_string(&x),
+        });
+        if let Some(y) = y {
+            jwk["y"] = Value::String(Base64UrlUnpadded::encode_string(&y));
+        }
+        Ok(serde_json::from_value(jwk)?)
+    }
+
+    /// Derive the `X25519` key agreement key from an `Ed25519` signing key.
+    ///
+    /// # Errors
+    /// Will fail if the key is not an `Ed25519` key or the conversion fails.
+    pub fn derive_x25519(&self) -> anyhow::Result<Self> {
+        if self.codec != KeyCodec::Ed25519 {
+            bail!("{} key cannot be converted to X25519", self.codec);
+        }
+        let pub_key = PublicKey::from_slice(&self.key)?;
+        let x25519_key = derive_x25519_public(&pub_key)?;
+        Self::new(KeyCodec::X25519, &x25519_key.to_bytes())
+    }
+
+    /// The key type of the multikey.
+    #[must_use]
+    pub const fn codec(&self) -> KeyCodec {
+        self.codec
+    }
+
+    /// The raw public key bytes (compressed for elliptic curve keys).
+    #[must_use]
+    pub fn key(&self) -> &[u8] {
+        &self.key
+    }
+}
+
+impl Display for Multikey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_multibase())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ed25519() {
+        let multi = "z6MkhaXgBZDvotDkL5257faiztiGiC2QtKLGpbnnEGta2doK";
+        let key = Multikey::from_multibase(multi).expect("should decode");
+        assert_eq!(key.codec(), KeyCodec::Ed25519);
+        let jwk = key.to_jwk().expect("should convert to JWK");
+        let from_jwk = Multikey::from_jwk(&jwk).expect("should convert from JWK");
+        assert_eq!(from_jwk.to_multibase(), multi);
+    }
+
+    #[test]
+    fn p256() {
+        let multi = "zDnaerDaTF5BXEavCrfRZEk316dpbLsfPDZ3WJ5hRTPFU2169";
+        let key = Multikey::from_multibase(multi).expect("should decode");
+        assert_eq!(key.codec(), KeyCodec::P256);
+        let jwk = serde_json::to_value(key.to_jwk().expect("should convert to JWK"))
+            .expect("should serialize");
+        assert_eq!(jwk["kty"], "EC");
+        assert_eq!(jwk["crv"], "P-256");
+        assert_eq!(jwk["x"], "fyNYMN0976ci7xqiSdag3buk-ZCwgXU4kz9XNkBlNUI");
+        assert_eq!(jwk["y"], "hW2ojTNfH7Jbi8--CJUo3OCbH3y5n91g-IMA9MLMbTU");
+        let jwk = serde_json::from_value(jwk).expect("should deserialize");
+        let from_jwk = Multikey::from_jwk(&jwk).expect("should convert from JWK");
+        assert_eq!(from_jwk.to_multibase(), multi);
+    }
+
+    #[test]
+    fn p384() {
+        let multi = "z82Lm1MpAkeJcix9K8TMiLd5NMAhnwkjjCBeWHXyu3U4oT2MVJJKXkcVBgjGhnLBn2Kaau9";
+        let key = Multikey::from_multibase(multi).expect("should decode");
+        assert_eq!(key.codec(), KeyCodec::P384);
+        let jwk = key.to_jwk().expect("should convert to JWK");
+        let from_jwk = Multikey::from_jwk(&jwk).expect("should convert from JWK");
+        assert_eq!(from_jwk.to_multibase(), multi);
+    }
+
+    #[test]
+    fn secp256k1() {
+        let multi = "zQ3shokFTS3brHcDQrn82RUDfCZESWL1ZdCEJwekUDPQiYBme";
+        let key = Multikey::from_multibase(multi).expect("should decode");
+        assert_eq!(key.codec(), KeyCodec::Secp256k1);
+        let jwk = key.to_jwk().expect("should convert to JWK");
+        let from_jwk = Multikey::from_jwk(&jwk).expect("should convert from JWK");
+        assert_eq!(from_jwk.to_multibase(), multi);
+    }
+
+    #[test]
+    fn invalid_jwk() {
+        let multi = "zDnaerDaTF5BXEavCrfRZEk316dpbLsfPDZ3WJ5hRTPFU2169";
+        let key = Multikey::from_multibase(multi).expect("should decode");
+        let jwk = serde_json::to_value(key.to_jwk().expect("should convert to JWK"))
+            .expect("should serialize");
+        let from_value = |jwk: Value| {
+            Multikey::from_jwk(&serde_json::from_value(jwk).expect("should deserialize"))
+        };
+
+        // A different y coordinate with the same parity.
+        let mut wrong_y = jwk.clone();
+        wrong_y["y"] = json!("hW2ojTNfH7Jbi8--CJUo3OCbH3y5n91g-IMA9MLMbTc");
+        assert!(from_value(wrong_y).is_err());
+        // Truncated y coordinate.
+        let mut short_y = jwk.clone();
+        short_y["y"] = json!("NQ");
+        assert!(from_value(short_y).is_err());
+        // Key type does not match the curve.
+        let mut wrong_kty = jwk;
+        wrong_kty["kty"] = json!("OKP");
+        assert!(from_value(wrong_kty).is_err());
+    }
+
+    #[test]
+    fn invalid() {
+        // Unknown multicodec prefix (sha2-256).
+        let multi = multibase::encode(Base::Base58Btc, [0x12, 0x20, 0x01, 0x02]);
+        assert!(Multikey::from_multibase(&multi).is_err());
+        // Truncated P-256 key.
+        let multi = multibase::encode(Base::Base58Btc, [0x80, 0x24, 0x02, 0x01]);
+        assert!(Multikey::from_multibase(&multi).is_err());
+        // Not base58-btc.
+        assert!(Multikey::from_multibase("uAQID").is_err());
+    }
+}
@@ -0,0 +1,281 @@
+//! # JSON Patch
+//!
+//! Generation and application of [RFC 6902](https://www.rfc-editor.org/rfc/rfc6902)
+//! JSON Patches between DID documents.
+//!
+//! A patch describes the exact changes needed to turn one document into
+//! another. Document metadata is not part of the DID document and is never
+//! included in, or changed by, a patch.
+
+use anyhow::{Context, anyhow, bail};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::Document;
+
+/// A JSON Patch operation. Paths are JSON pointers
+/// ([RFC 6901](https://www.rfc-editor.org/rfc/rfc6901)).
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum PatchOperation {
+    /// Add a value to an object or insert it into an array, replacing any
+    /// existing object member.
+    Add {
+        /// The location to add the value at.
+        path: String,
+
+        /// The value to add.
+        value: Value,
+    },
+
+    /// Remove the value at a location.
+    Remove {
+        /// The location of the value to remove.
+        path: String,
+    },
+
+    /// Replace the value at a location.
+    Replace {
+        /// The location of the value to replace.
+        path: String,
+
+        /// The replacement value.
+        value: Value,
+    },
+
+    /// Remove the value at one location and add it at another.
+    Move {
+        /// The location of the value to move.
+        from: String,
+
+        /// The location to move the value to.
+        path: String,
+    },
+
+    /// Copy the value at one location to another.
+    Copy {
+        /// The location of the value to copy.
+        from: String,
+
+        /// The location to copy the value to.
+        path: String,
+    },
+
+    /// Check the value at a location is equal to the given value.
+    Test {
+        /// The location of the value to check.
+        path: String,
+
+        /// The expected value.
+        value: Value,
+    },
+}
+
+impl Document {
+    /// Generate a JSON Patch that turns this document into `next`.
+    ///
+    /// Document metadata is ignored. Applying the patch to this document
+    /// (see [`Document::apply_patch`]) produces `next`, without its metadata.
+    ///
+    /// # Errors
+    /// Will fail if either document cannot be serialized.
+    pub fn patch_to(&self, next: &Self) -> anyhow::Result<Vec<PatchOperation>> {
+        let mut ops = Vec::new();
+        diff_values("", &self.to_patchable()?, &next.to_patchable()?, &mut ops);
+        Ok(ops)
+    }
+
+    /// Apply a JSON Patch to a copy of this document. The document's
+    /// metadata is kept.
+    ///
+    /// # Errors
+    /// Will fail if an operation cannot be applied (including a failed
+    /// `test` operation) or if the patched value is not a valid document.
+    pub fn apply_patch(&self, patch: &[PatchOperation]) -> anyhow::Result<Self> {
+        let mut value = self.to_patchable()?;
+        apply_patch(&mut value, patch)?;
+        let mut doc: Self =
+            serde_json::from_value(value).context("patched value is not a document")?;
+        doc.did_document_metadata.clone_from(&self.did_document_metadata);
+        Ok(doc)
+    }
+
+    // The document as JSON, without metadata.
+    fn to_patchable(&self) -> anyhow::Result<Value> {
+        let doc = Self {
+            did_document_metadata: None,
+            ..self.clone()
+        };
+        serde_json::to_value(doc).context("serializing document")
+    }
+}
+
+/// Apply a JSON Patch to a JSON value.
+///
+/// Operations are applied in order. If any operation fails the value is left
+/// unchanged.
+///
+/// # Errors
+/// Will fail if a path is not a valid JSON pointer, a location does not
+/// exist, or a `test` operation fails.
+pub fn apply_patch(value: &mut Value, patch: &[PatchOperation]) -> anyhow::Result<()> {
+    let mut patched = value.clone();
+    for (i, op) in patch.iter().enumerate() {
+        apply(&mut patched, op).with_context(|| format!("patch operation {i}"))?;
+    }
+    *value = patched;
+    Ok(())
+}
+
+fn apply(value: &mut Value, op: &PatchOperation) -> anyhow::Result<()> {
+    match op {
+        PatchOperation::Add { path, value: new } => add(value, path, new.clone()),
+        PatchOperation::Remove { path } => remove(value, path).map(|_| ()),
+        PatchOperation::Replace { path, value: new } => {
+            *pointer_mut(value, &tokens(path)?)? = new.clone();
+            Ok(())
+        }
+        PatchOperation::Move { from, path } => {
+            if path.starts_with(from.as_str()) && path[from.len()..].starts_with('/') {
+                bail!("cannot move {from} into one of its children");
+            }
+            let moved = remove(value, from)?;
+            add(value, path, moved)
+        }
+        PatchOperation::Copy { from, path } => {
+            let copied = pointer_mut(value, &tokens(from)?)?.clone();
+            add(value, path, copied)
+        }
+        PatchOperation::Test {
+            path,
+            value: expected,
+        } => {
+            if pointer_mut(value, &tokens(path)?)? != expected {
+                bail!("test failed at {path}");
+            }
+            Ok(())
+        }
+    }
+}
+
+fn add(value: &mut Value, path: &str, new: Value) -> anyhow::Result<()> {
+    let mut tokens = tokens(path)?;
+    let Some(last) = tokens.pop() else {
+        *value = new;
+        return Ok(());
+    };
+    match pointer_mut(value, &tokens)? {
+        Value::Object(map) => {
+            map.insert(last, new);
+        }
+        Value::Array(array) => {
+            let index = if last == "-" { array.len() } else { index(&last)? };
+            if index > array.len() {
+                bail!("index {index} is out of bounds at {path}");
+            }
+            array.insert(index, new);
+        }
+        _ => bail!("cannot add to a scalar at {path}"),
+    }
+    Ok(())
+}
+
+fn remove(value: &mut Value, path: &str) -> anyhow::Result<Value> {
+    let mut tokens = tokens(path)?;
+    let Some(last) = tokens.pop() else {
+        bail!("cannot remove the whole document");
+    };
+    let removed = match pointer_mut(value, &tokens)? {
+        Value::Object(map) => map.remove(&last),
+        Value::Array(array) => {
+            let index = index(&last)?;
+            (index < array.len()).then(|| array.remove(index))
+        }
+        _ => None,
+    };
+    removed.ok_or_else(|| anyhow!("no value at {path}"))
+}
+
+// The value a parsed JSON pointer refers to.
+fn pointer_mut<'a>(value: &'a mut Value, tokens: &[String]) -> anyhow::Result<&'a mut Value> {
+    let mut current = value;
+    for token in tokens {
+        current = match current {
+            Value::Object(map) => map.get_mut(token),
+            Value::Array(array) => array.get_mut(index(token)?),
+            _ => None,
+        }
+        .ok_or_else(|| anyhow!("no value at {token}"))?;
+    }
+    Ok(current)
+}
+
+// Split a JSON pointer into its unescaped reference tokens.
+fn tokens(pointer: &str) -> anyhow::Result<Vec<String>> {
+    if pointer.is_empty() {
+        return Ok(vec![]);
+    }
+    let Some(pointer) = pointer.strip_prefix('/') else {
+        bail!("{pointer} is not a JSON pointer");
+    };
+    Ok(pointer.split('/').map(|t| t.replace("~1", "/").replace("~0", "~")).collect())
+}
+
+// An array index: digits without leading zeros.
+fn index(token: &str) -> anyhow::Result<usize> {
+    if token.is_empty()
+        || (token.len() > 1 && token.starts_with('0'))
+        || !token.bytes().all(|b| b.is_ascii_digit())
+    {
+        bail!("{token} is not an array index");
+    }
+    token.parse().context("parsing array index")
+}
+
+fn escape(token: &str) -> String {
+    token.replace('~', "~0").replace('/', "~1")
+}
+
+// Append the operations needed to turn `previous` into `next`. Object
+// members are compared by name and array items by position.
+fn diff_values(path: &str, previous: &Value, next: &Value, ops: &mut Vec<PatchOperation>) {
+    match (previous, next) {
+        (Value::Object(prev), Value::Object(next)) => {
+            for (key, prev_value) in prev {
+                let path = format!("{path}/{}", escape(key));
+                match next.get(key) {
+                    Some(next_value) => diff_values(&path, prev_value, next_value, ops),
+                    None => ops.push(PatchOperation::Remove { path }),
+                }
+            }
+            for (key, next_value) in next.iter().filter(|(key, _)| !prev.contains_key(*key)) {
+                ops.push(PatchOperation::Add {
+                    path: format!("{path}/{}", escape(key)),
+                    value: next_value.clone(),
+                });
+            }
+        }
+        (Value::Array(prev), Value::Array(next)) => {
+            for (i, (prev_value, next_value)) in prev.iter().zip(next).enumerate() {
+                diff_values(&format!("{path}/{i}"), prev_value, next_value, ops);
+            }
+            // remove from the end so earlier indices stay valid
+            for i in (next.len()..prev.len()).rev() {
+                ops.push(PatchOperation::Remove {
+                    path: format!("{path}/{i}"),
+                });
+            }
+            for next_value in next.iter().skip(prev.len()) {
+                ops.push(PatchOperation::Add {
+                    path: format!("{path}/-"),
+                    value: next_value.clone(),
+                });
+            }
+        }
+        _ if previous == next => {}
+        _ => ops.push(PatchOperation::Replace {
+            path: path.to_string(),
+            value: next.clone(),
+        }),
+    }
+}
@@ -0,0 +1,205 @@
+//! # DID Peer
+//!
+//! The `did:peer` method is used for DIDs that are exchanged directly between
+//! peers (for example, `DIDComm` agents) rather than published. Peer DIDs are
+//! self-resolving: the DID document is expanded from the DID itself.
+//!
+//! Supported algorithms (numalgo):
+//!
+//! - `0`: a single inception key, expanded in the same way as `did:key`.
+//! - `2`: a set of keys, each with a purpose, and abbreviated services.
+//! - `4`: a hash of an encoded input document plus (in the long form) the
+//!   document itself.
+//!
+//! See:
+//!
+//! - <https://identity.foundation/peer-did-method-spec>
+//! - <https://w3c.github.io/did-resolution>
+
+mod resolve;
+
+use anyhow::bail;
+use base64ct::{Base64UrlUnpadded, Encoding};
+use multibase::Base;
+pub use resolve::*;
+use serde_json::{Map, Value};
+use sha2::Digest;
+
+use crate::Error;
+use crate::did::{Document, KeyPurpose, Multikey, Service};
+
+/// Multicodec prefix (unsigned varint) for JSON (0x0200).
+const JSON_CODEC: [u8; 2] = [0x80, 0x04];
+
+/// Multihash prefix for a 32-byte SHA2-256 digest.
+const SHA2_256: [u8; 2] = [0x12, 0x20];
+
+/// Service abbreviations used by numalgo 2 (full form, abbreviated form).
+const ABBREVIATIONS: [(&str, &str); 4] =
+    [("type", "t"), ("serviceEndpoint", "s"), ("routingKeys", "r"), ("accept", "a")];
+
+/// Construct a numalgo 0 `did:peer` from an inception key.
+#[must_use]
+pub fn did_from_key(key: &Multikey) -> String {
+    format!("did:peer:0{}", key.to_multibase())
+}
+
+/// Builder to create a numalgo 2 `did:peer` from a set of keys and services.
+///
+/// Keys are assigned the verification method IDs `#key-1`, `#key-2`, etc. in
+/// the order they are added. Services without an ID (or with the default ID
+/// for their position) are assigned `#service`, `#service-1`, etc.
+#[derive(Clone, Debug, Default)]
+pub struct Numalgo2Builder {
+    elements: Vec<String>,
+    services: usize,
+}
+
+impl Numalgo2Builder {
+    /// Create a new `Numalgo2Builder`.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a key for the specified purpose.
+    ///
+    /// # Errors
+    /// Will fail if the purpose is `VerificationMethod`: keys must be added
+    /// for a verification relationship.
+    pub fn key(mut self, key: &Multikey, purpose: &KeyPurpose) -> anyhow::Result<Self> {
+        let code = match purpose {
+            KeyPurpose::Authentication => 'V',
+            KeyPurpose::AssertionMethod => 'A',
+            KeyPurpose::KeyAgreement => 'E',
+            KeyPurpose::CapabilityInvocation => 'I',
+            KeyPurpose::CapabilityDelegation => 'D',
+            KeyPurpose::VerificationMethod => {
+                let detail = "did:peer keys must have a verification relationship";
+                return Err(Error::Builder(detail.to_string()).into());
+            }
+        };
+        self.elements.push(format!("{code}{}", key.to_multibase()));
+        Ok(self)
+    }
+
+    /// Add a service.
+    ///
+    /// # Errors
+    /// Will fail if the service cannot be serialized.
+    pub fn service(mut self, service: &Service) -> anyhow::Result<Self> {
+        let mut value = serde_json::to_value(service)?;
+        if let Some(obj) = value.as_object_mut() {
+            if service.id == default_service_id(self.services) {
+                obj.remove("id");
+            }
+        }
+        let json = serde_json::to_string(&abbreviate(&value, true))?;
+        self.elements.push(format!("S{}", Base64UrlUnpadded::encode_string(json.as_bytes())));
+        self.services += 1;
+        Ok(self)
+    }
+
+    /// Build the DID.
+    ///
+    /// # Errors
+    /// Will fail if no keys have been added.
+    pub fn build(self) -> anyhow::Result<String> {
+        if !self.elements.iter().any(|e| !e.starts_with('S')) {
+            let detail = "did:peer numalgo 2 requires at least one key";
+            return Err(Error::Builder(detail.to_string()).into());
+        }
+        Ok(format!("did:peer:2.{}", self.elements.join(".")))
+    }
+}
+
+/// Construct the long form of a numalgo 4 `did:peer` from an input document.
+///
+/// The input document should use relative references (for example, `#key-1`)
+/// for verification method and service IDs. Any `id` and metadata on the
+/// document are ignored.
+///
+/// # Errors
+/// Will fail if the document cannot be serialized.
+pub fn did_from_document(doc: &Document) -> anyhow::Result<String> {
+    let mut value = serde_json::to_value(doc)?;
+    if let Some(obj) = value.as_object_mut() {
+        obj.remove("id");
+        obj.remove("didDocumentMetadata");
+    }
+    let json = serde_json::to_vec(&value)?;
+    let encoded = multibase::encode(Base::Base58Btc, [JSON_CODEC.as_slice(), &json].concat());
+    Ok(format!("did:peer:4{}:{encoded}", hash(&encoded)))
+}
+
+/// The short form of a numalgo 4 `did:peer`.
+///
+/// # Errors
+/// Will fail if the DID is not a long-form numalgo 4 `did:peer` or its hash
+/// does not match the encoded document.
+pub fn short_form(long_form: &str) -> anyhow::Result<String> {
+    let Some(id) = long_form.strip_prefix("did:peer:4") else {
+        bail!("DID is not a numalgo 4 did:peer");
+    };
+    let (hash, _) = split_long_form(id)?;
+    Ok(format!("did:peer:4{hash}"))
+}
+
+// Split a long-form numalgo 4 identifier (without the `did:peer:4` prefix)
+// into the hash and the decoded input document, checking the hash.
+fn split_long_form(id: &str) -> anyhow::Result<(&str, Value)> {
+    let Some((expected, encoded)) = id.split_once(':') else {
+        bail!("did:peer numalgo 4 is not in long form");
+    };
+    if hash(encoded) != expected {
+        bail!("did:peer numalgo 4 hash does not match the encoded document");
+    }
+    let (base, bytes) = multibase::decode(encoded)?;
+    if base != Base::Base58Btc || !bytes.starts_with(&JSON_CODEC) {
+        bail!("did:peer numalgo 4 document must be base58btc-encoded JSON");
+    }
+    Ok((expected, serde_json::from_slice(&bytes[JSON_CODEC.len()..])?))
+}
+
+// The multibase-encoded SHA2-256 multihash of an encoded document.
+fn hash(encoded: &str) -> String {
+    let digest = sha2::Sha256::digest(encoded.as_bytes());
+    multibase::encode(Base::Base58Btc, [SHA2_256.as_slice(), &digest[..]].concat())
+}
+
+// The relative ID given to a numalgo 2 service without one.
+fn default_service_id(index: usize) -> String {
+    if index == 0 { "#service".to_string() } else { format!("#service-{index}") }
+}
+
+// Abbreviate (or expand) the keys and well-known values of a numalgo 2
+// service.
+fn abbreviate(value: &Value, shorten: bool) -> Value {
+    let rename = |key: &str| {
+        ABBREVIATIONS
+            .iter()
+            .find_map(|(full, abbr)| {
+                let (from, to) = if shorten { (full, abbr) } else { (abbr, full) };
+                (*from == key).then(|| (*to).to_string())
+            })
+            .unwrap_or_else(|| key.to_string())
+    };
+
+    match value {
+        Value::Object(obj) => {
+            let mut out = Map::new();
+            for (key, val) in obj {
+                let key = rename(key);
+                let val = match (key.as_str(), val.as_str()) {
+                    ("t", Some("DIDCommMessaging")) => Value::String("dm".to_string()),
+                    ("type", Some("dm")) => Value::String("DIDCommMessaging".to_string()),
+                    _ => abbreviate(val, shorten),
+                };
+                out.insert(key, val);
+            }
+            Value::Object(out)
+        }
+        Value::Array(arr) => Value::Array(arr.iter().map(|v| abbreviate(v, shorten)).collect()),
+        _ => value.clone(),
+    }
+}
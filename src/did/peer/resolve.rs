@@ -0,0 +1,241 @@
+//! Resolution of a DID into a DID document using the `did:peer` method.
+
+use anyhow::{anyhow, bail};
+use base64ct::{Base64UrlUnpadded, Encoding};
+use serde_json::Value;
+
+use super::{abbreviate, default_service_id, split_long_form};
+use crate::core::Kind;
+use crate::did::key::expand;
+use crate::did::{
+    Document, DocumentBuilder, KeyPurpose, Method, MethodType, Multikey, PublicKeyFormat, Resource,
+    Service, ServiceBuilder, Url, VerificationMethodBuilder, VmKeyId, document_resource,
+};
+
+/// Verification relationships that may appear in a numalgo 4 input document.
+const RELATIONSHIPS: [&str; 5] = [
+    "authentication",
+    "assertionMethod",
+    "keyAgreement",
+    "capabilityInvocation",
+    "capabilityDelegation",
+];
+
+/// Resolve a `did:peer` URL into a [`Resource`].
+///
+/// A URL without a fragment resolves to the full document.
+///
+/// # Errors
+/// If the URL is not a valid `did:peer` URL or the requested resource is not
+/// in the expanded document, an error is returned.
+pub fn resolve(url: &Url) -> anyhow::Result<Resource> {
+    let doc = document(url)?;
+    document_resource(url, None, &doc)
+}
+
+/// Expand a `did:peer` URL into a DID document.
+///
+/// Short-form numalgo 4 DIDs cannot be expanded on their own: use
+/// [`short_form_document`] with the long form received from the peer.
+///
+/// # Errors
+/// If the URL is not a valid `did:peer` URL, uses an unsupported numalgo, or
+/// the DID cannot be decoded, an error is returned.
+pub fn document(url: &Url) -> anyhow::Result<Document> {
+    if url.method != Method::Peer {
+        bail!("DID is not a valid did:peer: {url}");
+    }
+    if url.path.is_some() {
+        bail!("DID is not a valid did:peer - paths are not supported");
+    }
+
+    let did = url.did();
+    let mut chars = url.id.chars();
+    let numalgo = chars.next();
+    let id = chars.as_str();
+
+    match numalgo {
+        Some('0') => expand(&did, &Multikey::from_multibase(id)?),
+        Some('2') => numalgo_2(&did, id),
+        Some('4') => {
+            if !id.contains(':') {
+                bail!("short-form did:peer numalgo 4 requires the long-form DID to resolve");
+            }
+            let (hash, input) = split_long_form(id)?;
+            contextualize(input, &did, &format!("did:peer:4{hash}"))
+        }
+        _ => bail!("unsupported did:peer numalgo"),
+    }
+}
+
+/// Expand a short-form numalgo 4 `did:peer` into a DID document using the
+/// long form previously received from the peer.
+///
+/// # Errors
+/// If the URL is not a short-form numalgo 4 `did:peer` or the long form is
+/// not the long form of the same DID, an error is returned.
+pub fn short_form_document(url: &Url, long_form: &str) -> anyhow::Result<Document> {
+    if url.method != Method::Peer || !url.id.starts_with('4') || url.id.contains(':') {
+        bail!("DID is not a short-form numalgo 4 did:peer: {url}");
+    }
+    let Some(long_id) = long_form.strip_prefix("did:peer:4") else {
+        bail!("long form is not a numalgo 4 did:peer");
+    };
+    let (hash, input) = split_long_form(long_id)?;
+    if hash != &url.id[1..] {
+        bail!("long form does not match the short-form DID");
+    }
+    contextualize(input, &url.did(), long_form)
+}
+
+// Expand the keys and services of a numalgo 2 `did:peer` into a document.
+fn numalgo_2(did: &str, id: &str) -> anyhow::Result<Document> {
+    let Some(elements) = id.strip_prefix('.') else {
+        bail!("did:peer numalgo 2 elements must be separated by '.'");
+    };
+
+    let mut builder = DocumentBuilder::new(did);
+    let mut keys = 0;
+    let mut services = 0;
+    let mut key_agreement = vec![];
+
+    for element in elements.split('.') {
+        let mut chars = element.chars();
+        let code = chars.next();
+        let value = chars.as_str();
+
+        let purpose = match code {
+            Some('S') => {
+                builder = builder.add_service(&decode_service(did, value, services)?);
+                services += 1;
+                continue;
+            }
+            Some('V') => KeyPurpose::Authentication,
+            Some('A') => KeyPurpose::AssertionMethod,
+            Some('E') => KeyPurpose::KeyAgreement,
+            Some('I') => KeyPurpose::CapabilityInvocation,
+            Some('D') => KeyPurpose::CapabilityDelegation,
+            _ => bail!("unsupported did:peer numalgo 2 purpose code: {element}"),
+        };
+
+        keys += 1;
+        let multikey = Multikey::from_multibase(value)?;
+        let vm = VerificationMethodBuilder::new(&PublicKeyFormat::PublicKeyMultibase {
+            public_key_multibase: multikey.to_multibase(),
+        })
+        .key_id(did, VmKeyId::Index("key-".to_string(), keys))?
+        .method_type(&MethodType::Multikey)?
+        .build();
+
+        // Every key is listed as a verification method and referenced from
+        // its relationship.
+        let reference = Kind::String(vm.id.clone());
+        builder =
+            builder.add_verification_method(&Kind::Object(vm), &KeyPurpose::VerificationMethod)?;
+        if purpose == KeyPurpose::KeyAgreement {
+            // the builder only embeds key agreement methods
+            key_agreement.push(reference);
+        } else {
+            builder = builder.add_verification_method(&reference, &purpose)?;
+        }
+    }
+    if keys == 0 {
+        bail!("did:peer numalgo 2 requires at least one key");
+    }
+
+    let mut doc = builder.build();
+    if !key_agreement.is_empty() {
+        doc.key_agreement = Some(key_agreement);
+    }
+    doc.did_document_metadata = None;
+    Ok(doc)
+}
+
+// Decode an abbreviated numalgo 2 service.
+fn decode_service(did: &str, encoded: &str, index: usize) -> anyhow::Result<Service> {
+    let bytes = Base64UrlUnpadded::decode_vec(encoded.trim_end_matches('='))
+        .map_err(|e| anyhow!("invalid did:peer service encoding: {e}"))?;
+    let value = abbreviate(&serde_json::from_slice(&bytes)?, false);
+
+    let id = value
+        .get("id")
+        .and_then(Value::as_str)
+        .map_or_else(|| default_service_id(index), ToString::to_string);
+    let Some(service_type) = value.get("type").and_then(Value::as_str) else {
+        bail!("did:peer service has no type");
+    };
+
+    let builder = ServiceBuilder::new(&absolute(did, &id)).service_type(&service_type);
+    let service = match value.get("serviceEndpoint") {
+        Some(Value::String(endpoint)) => builder.endpoint_str(endpoint).build(),
+        Some(Value::Array(endpoints)) => {
+            let Some((first, rest)) = endpoints.split_first() else {
+                bail!("did:peer service has no endpoint");
+            };
+            let mut builder = builder.endpoint_json(first);
+            for endpoint in rest {
+                builder = builder.add_endpoint_json(endpoint);
+            }
+            builder.build()
+        }
+        Some(endpoint) => builder.endpoint_json(endpoint).build(),
+        None => bail!("did:peer service has no endpoint"),
+    };
+    Ok(service)
+}
+
+// Turn a numalgo 4 input document into the resolved document for `did`:
+// relative IDs are made absolute and verification methods without a
+// (non-empty) controller are controlled by the DID.
+fn contextualize(mut input: Value, did: &str, also_known_as: &str) -> anyhow::Result<Document> {
+    let Some(obj) = input.as_object_mut() else {
+        bail!("did:peer numalgo 4 input document must be a JSON object");
+    };
+    obj.insert("id".to_string(), Value::String(did.to_string()));
+    obj.entry("@context").or_insert_with(|| Value::Array(vec![]));
+
+    for vm in obj.get_mut("verificationMethod").and_then(Value::as_array_mut).into_iter().flatten()
+    {
+        contextualize_method(vm, did);
+    }
+    for relationship in RELATIONSHIPS {
+        let methods = obj.get_mut(relationship).and_then(Value::as_array_mut);
+        for vm in methods.into_iter().flatten() {
+            if let Value::String(id) = vm {
+                *id = absolute(did, id);
+            } else {
+                contextualize_method(vm, did);
+            }
+        }
+    }
+    for service in obj.get_mut("service").and_then(Value::as_array_mut).into_iter().flatten() {
+        if let Some(Value::String(id)) = service.get_mut("id") {
+            *id = absolute(did, id);
+        }
+    }
+
+    let doc: Document = serde_json::from_value(input)?;
+    let mut doc = DocumentBuilder::from(&doc).also_known_as(also_known_as).build();
+    doc.did_document_metadata = None;
+    Ok(doc)
+}
+
+fn contextualize_method(vm: &mut Value, did: &str) {
+    let Some(vm) = vm.as_object_mut() else {
+        return;
+    };
+    if let Some(Value::String(id)) = vm.get_mut("id") {
+        *id = absolute(did, id);
+    }
+    match vm.get("controller") {
+        Some(Value::String(controller)) if !controller.is_empty() => {}
+        _ => {
+            vm.insert("controller".to_string(), Value::String(did.to_string()));
+        }
+    }
+}
+
+// Resolve a relative reference (`#fragment`) against the DID.
+fn absolute(did: &str, id: &str) -> String {
+    if id.starts_with('#') { format!("{did}{id}") } else { id.to_string() }
+}
@@ -0,0 +1,143 @@
+//! # DID PKH
+//!
+//! The `did:pkh` method identifies a DID subject by a blockchain account. The
+//! method-specific identifier is a [CAIP-10] account ID and, like `did:key`,
+//! the DID document is expanded from the DID itself.
+//!
+//! Supported namespaces are `eip155` (Ethereum and other EVM chains),
+//! `solana`, `tezos` and `bip122` (Bitcoin).
+//!
+//! See:
+//!
+//! - <https://github.com/w3c-ccg/did-pkh/blob/main/did-pkh-method-draft.md>
+//! - <https://github.com/ChainAgnostic/CAIPs/blob/main/CAIPs/caip-10.md>
+//!
+//! [CAIP-10]: https://github.com/ChainAgnostic/CAIPs/blob/main/CAIPs/caip-10.md
+
+mod resolve;
+
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+use anyhow::bail;
+pub use resolve::*;
+
+use crate::did::MethodType;
+
+/// A CAIP-10 blockchain account ID of the form
+/// `<namespace>:<reference>:<address>`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AccountId {
+    /// The blockchain namespace, for example `eip155`.
+    pub namespace: String,
+
+    /// The chain reference within the namespace, for example `1` for Ethereum
+    /// mainnet.
+    pub reference: String,
+
+    /// The account address on the chain.
+    pub address: String,
+}
+
+impl AccountId {
+    /// The `did:pkh` DID for the account.
+    #[must_use]
+    pub fn did(&self) -> String {
+        format!("did:pkh:{self}")
+    }
+
+    /// The verification method type used for keys controlling the account.
+    ///
+    /// # Errors
+    /// Will fail if the namespace is not supported or (for Tezos) the address
+    /// type is not recognized.
+    pub fn method_type(&self) -> anyhow::Result<MethodType> {
+        let method_type = match self.namespace.as_str() {
+            "eip155" | "bip122" => MethodType::EcdsaSecp256k1RecoveryMethod2020,
+            "solana" => MethodType::Ed25519VerificationKey2018,
+            "tezos" => match self.address.get(..3) {
+                Some("tz1") => {
+                    MethodType::Ed25519PublicKeyBlake2bDigestSize20Base58CheckEncoded2021
+                }
+                Some("tz2") => MethodType::EcdsaSecp256k1RecoveryMethod2020,
+                Some("tz3") => MethodType::P256PublicKeyBlake2bDigestSize20Base58CheckEncoded2021,
+                _ => bail!("unsupported tezos address: {}", self.address),
+            },
+            _ => bail!("unsupported did:pkh namespace: {}", self.namespace),
+        };
+        Ok(method_type)
+    }
+}
+
+impl FromStr for AccountId {
+    type Err = anyhow::Error;
+
+    /// Parse and validate a CAIP-10 account ID for a supported namespace.
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let mut parts = s.splitn(3, ':');
+        let (Some(namespace), Some(reference), Some(address)) =
+            (parts.next(), parts.next(), parts.next())
+        else {
+            bail!("account ID must be of the form <namespace>:<reference>:<address>");
+        };
+
+        // Generic CAIP-2 and CAIP-10 syntax.
+        let is_valid = |value: &str, min: usize, max: usize, extra: &str| {
+            (min..=max).contains(&value.len())
+                && value.chars().all(|c| c.is_ascii_alphanumeric() || extra.contains(c))
+        };
+        if !is_valid(namespace, 3, 8, "-") || namespace.chars().any(|c| c.is_ascii_uppercase()) {
+            bail!("invalid CAIP-2 namespace: {namespace}");
+        }
+        if !is_valid(reference, 1, 32, "-_") {
+            bail!("invalid CAIP-2 reference: {reference}");
+        }
+        if !is_valid(address, 1, 128, "-.%") {
+            bail!("invalid CAIP-10 address: {address}");
+        }
+
+        // Namespace-specific address formats.
+        let is_base58 =
+            |value: &str| value.chars().all(|c| c.is_ascii_alphanumeric() && !"0OIl".contains(c));
+        match namespace {
+            "eip155" => {
+                let hex = address.strip_prefix("0x").unwrap_or_default();
+                if hex.len() != 40 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+                    bail!("invalid eip155 address: {address}");
+                }
+            }
+            "solana" => {
+                if !(32..=44).contains(&address.len()) || !is_base58(address) {
+                    bail!("invalid solana address: {address}");
+                }
+            }
+            "tezos" => {
+                let prefix = address.get(..3).unwrap_or_default();
+                if address.len() != 36
+                    || !["tz1", "tz2", "tz3"].contains(&prefix)
+                    || !is_base58(address)
+                {
+                    bail!("invalid tezos address: {address}");
+                }
+            }
+            "bip122" => {
+                if !(26..=62).contains(&address.len()) {
+                    bail!("invalid bip122 address: {address}");
+                }
+            }
+            _ => bail!("unsupported did:pkh namespace: {namespace}"),
+        }
+
+        Ok(Self {
+            namespace: namespace.to_string(),
+            reference: reference.to_string(),
+            address: address.to_string(),
+        })
+    }
+}
+
+impl Display for AccountId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.namespace, self.reference, self.address)
+    }
+}
@@ -0,0 +1,91 @@
+//! Resolution of a DID into a DID document using the `did:pkh` method.
+
+use std::str::FromStr;
+
+use anyhow::bail;
+use serde_json::{Map, Value};
+
+use super::AccountId;
+use crate::core::Kind;
+use crate::did::{
+    Document, DocumentBuilder, KeyPurpose, Method, MethodType, PublicKeyFormat, Resource, Url,
+    VerificationMethod, VerificationMethodBuilder, VmKeyId, document_resource,
+};
+
+/// The fragment identifying the verification method of a `did:pkh`.
+pub const VERIFICATION_METHOD_ID: &str = "blockchainAccountId";
+
+/// Resolve a `did:pkh` URL into a [`Resource`].
+///
+/// A URL without a fragment resolves to the full document and
+/// `#blockchainAccountId` resolves to the verification method.
+///
+/// # Errors
+/// If the URL is not a valid `did:pkh` URL or the requested resource is not in
+/// the expanded document, an error is returned.
+pub fn resolve(url: &Url) -> anyhow::Result<Resource> {
+    let doc = document(url)?;
+    document_resource(url, None, &doc)
+}
+
+/// Expand a `did:pkh` URL into a DID document.
+///
+/// The document contains a single verification method identified by the
+/// account ID (`blockchainAccountId`) rather than a public key, of a type
+/// determined by the namespace, that is used for `authentication` and
+/// `assertionMethod`.
+///
+/// # Errors
+/// If the URL is not a valid `did:pkh` URL or the account ID is not valid for
+/// a supported namespace, an error is returned.
+pub fn document(url: &Url) -> anyhow::Result<Document> {
+    if url.method != Method::Pkh {
+        bail!("DID is not a valid did:pkh: {url}");
+    }
+    if url.path.is_some() {
+        bail!("DID is not a valid did:pkh - paths are not supported");
+    }
+    let account = AccountId::from_str(&url.id)?;
+    let method_type = account.method_type()?;
+    let did = url.did();
+
+    let vm = VerificationMethodBuilder::new(&PublicKeyFormat::BlockchainAccountId {
+        blockchain_account_id: account.to_string(),
+    })
+    .key_id(&did, VmKeyId::Fragment(VERIFICATION_METHOD_ID.to_string()))?
+    .method_type(&method_type)?
+    .build();
+    let vm_ref = Kind::<VerificationMethod>::String(vm.id.clone());
+
+    // The terms used are not defined by the base contexts.
+    let mut context = Map::new();
+    context.insert(
+        "blockchainAccountId".to_string(),
+        Value::String("https://w3id.org/security#blockchainAccountId".to_string()),
+    );
+    context.insert(method_type.to_string(), Value::String(type_iri(&method_type)));
+
+    let mut doc = DocumentBuilder::new(&did)
+        .add_context(&Kind::Object(Value::Object(context)))
+        .add_verification_method(&Kind::Object(vm), &KeyPurpose::VerificationMethod)?
+        .add_verification_method(&vm_ref, &KeyPurpose::Authentication)?
+        .add_verification_method(&vm_ref, &KeyPurpose::AssertionMethod)?
+        .build();
+
+    // A `did:pkh` document is derived, never created or updated, so there is
+    // no meaningful metadata.
+    doc.did_document_metadata = None;
+
+    Ok(doc)
+}
+
+// The JSON-LD term definition for a blockchain account verification method
+// type.
+fn type_iri(method_type: &MethodType) -> String {
+    match method_type {
+        MethodType::EcdsaSecp256k1RecoveryMethod2020 => {
+            "https://identity.foundation/EcdsaSecp256k1RecoverySignature2020#EcdsaSecp256k1RecoveryMethod2020".to_string()
+        }
+        _ => format!("https://w3id.org/security#{method_type}"),
+    }
+}
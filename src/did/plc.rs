@@ -0,0 +1,278 @@
+//! # DID PLC
+//!
+//! The `did:plc` method is a DID method for self-authenticating identifiers
+//! whose state is kept by a directory server as a log of signed operations.
+//! It is used for Bluesky (AT Protocol) identities.
+//!
+//! The DID is derived from the hash of the signed genesis operation. Each
+//! subsequent operation references the previous operation by CID and is
+//! signed by one of the previous operation's rotation keys. Rotation keys are
+//! listed in priority order: within 72 hours, an operation signed by a
+//! higher-priority key can fork the log, nullifying the operations signed by
+//! lower-priority keys. A tombstone operation permanently deactivates the DID.
+//!
+//! See:
+//!
+//! - <https://web.plc.directory/spec/v0.1/did-plc>
+//! - <https://w3c.github.io/did-resolution>
+
+mod resolve;
+
+use std::collections::BTreeMap;
+
+use anyhow::bail;
+use base64ct::{Base64UrlUnpadded, Encoding};
+use chrono::{DateTime, Utc};
+use multibase::Base;
+pub use resolve::*;
+use serde::{Deserialize, Serialize};
+use sha2::Digest;
+
+use super::cbor;
+use crate::Error;
+
+/// The default PLC directory.
+pub const DEFAULT_DIRECTORY: &str = "https://plc.directory";
+
+/// CID prefix for a version 1 CID of a `dag-cbor` block with a SHA2-256
+/// multihash.
+const CID_PREFIX: [u8; 4] = [0x01, 0x71, 0x12, 0x20];
+
+/// Length of the method-specific identifier (truncated base32 hash).
+const ID_LEN: usize = 24;
+
+/// An entry in the audit log returned by a PLC directory.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct LogEntry {
+    /// The DID the operation applies to.
+    pub did: String,
+
+    /// The signed operation.
+    pub operation: Operation,
+
+    /// The CID of the operation.
+    pub cid: String,
+
+    /// Whether the operation has been nullified by a later operation signed
+    /// with a higher-priority rotation key.
+    pub nullified: bool,
+
+    /// The time the directory received the operation.
+    pub created_at: DateTime<Utc>,
+}
+
+/// A signed `did:plc` operation.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(tag = "type")]
+#[allow(clippy::large_enum_variant)]
+pub enum Operation {
+    /// Create or update the DID's state.
+    #[serde(rename = "plc_operation")]
+    Plc(PlcOperation),
+
+    /// Deactivate the DID.
+    #[serde(rename = "plc_tombstone")]
+    Tombstone(Tombstone),
+
+    /// Legacy genesis operation.
+    #[serde(rename = "create")]
+    LegacyCreate(LegacyCreate),
+}
+
+/// A regular `did:plc` operation: the complete state of the DID.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct PlcOperation {
+    /// `did:key` rotation keys, in priority order, authorized to sign the
+    /// next operation. Only `secp256k1` and `P-256` keys are supported.
+    pub rotation_keys: Vec<String>,
+
+    /// `did:key` verification methods, keyed by name (for example,
+    /// `atproto`).
+    pub verification_methods: BTreeMap<String, String>,
+
+    /// Other identifiers for the DID subject (for example, `at://` handles).
+    pub also_known_as: Vec<String>,
+
+    /// Services, keyed by name (for example, `atproto_pds`).
+    pub services: BTreeMap<String, PlcService>,
+
+    /// The CID of the previous operation or `None` for the genesis operation.
+    pub prev: Option<String>,
+
+    /// The base64url-encoded signature over the unsigned operation.
+    pub sig: String,
+}
+
+/// A service in a `did:plc` operation.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct PlcService {
+    /// The service type.
+    #[serde(rename = "type")]
+    pub type_: String,
+
+    /// The service endpoint.
+    pub endpoint: String,
+}
+
+/// A `did:plc` tombstone operation.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct Tombstone {
+    /// The CID of the previous operation.
+    pub prev: String,
+
+    /// The base64url-encoded signature over the unsigned operation.
+    pub sig: String,
+}
+
+/// A legacy `did:plc` genesis operation.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct LegacyCreate {
+    /// `did:key` signing key.
+    pub signing_key: String,
+
+    /// `did:key` recovery key.
+    pub recovery_key: String,
+
+    /// The handle of the DID subject.
+    pub handle: String,
+
+    /// The personal data server endpoint.
+    pub service: String,
+
+    /// Always `None`.
+    pub prev: Option<String>,
+
+    /// The base64url-encoded signature over the unsigned operation.
+    pub sig: String,
+}
+
+impl Operation {
+    /// The CID of the previous operation or `None` for a genesis operation.
+    #[must_use]
+    pub fn prev(&self) -> Option<&str> {
+        match self {
+            Self::Plc(op) => op.prev.as_deref(),
+            Self::Tombstone(op) => Some(&op.prev),
+            Self::LegacyCreate(op) => op.prev.as_deref(),
+        }
+    }
+
+    /// The base64url-encoded signature.
+    #[must_use]
+    pub fn sig(&self) -> &str {
+        match self {
+            Self::Plc(op) => &op.sig,
+            Self::Tombstone(op) => &op.sig,
+            Self::LegacyCreate(op) => &op.sig,
+        }
+    }
+
+    /// Set the signature over the operation's [`signing_input`].
+    ///
+    /// The signature must be a 64-byte ECDSA signature (`r || s`) in low-S
+    /// form.
+    ///
+    /// [`signing_input`]: Self::signing_input
+    pub fn set_signature(&mut self, signature: &[u8]) {
+        let sig = Base64UrlUnpadded::encode_string(signature);
+        match self {
+            Self::Plc(op) => op.sig = sig,
+            Self::Tombstone(op) => op.sig = sig,
+            Self::LegacyCreate(op) => op.sig = sig,
+        }
+    }
+
+    /// The rotation keys authorized to sign the operation that follows this
+    /// one, in priority order. A tombstone has no rotation keys.
+    #[must_use]
+    pub fn rotation_keys(&self) -> Option<Vec<String>> {
+        self.state().map(|state| state.rotation_keys)
+    }
+
+    /// The state of the DID after the operation has been applied, with legacy
+    /// genesis operations converted to the current format. A tombstone has no
+    /// state.
+    #[must_use]
+    pub fn state(&self) -> Option<PlcOperation> {
+        match self {
+            Self::Plc(op) => Some(op.clone()),
+            Self::Tombstone(_) => None,
+            Self::LegacyCreate(op) => Some(PlcOperation {
+                rotation_keys: vec![op.recovery_key.clone(), op.signing_key.clone()],
+                verification_methods: BTreeMap::from([(
+                    "atproto".to_string(),
+                    op.signing_key.clone(),
+                )]),
+                also_known_as: vec![format!("at://{}", op.handle)],
+                services: BTreeMap::from([(
+                    "atproto_pds".to_string(),
+                    PlcService {
+                        type_: "AtprotoPersonalDataServer".to_string(),
+                        endpoint: op.service.clone(),
+                    },
+                )]),
+                prev: None,
+                sig: op.sig.clone(),
+            }),
+        }
+    }
+
+    /// The bytes signed by a rotation key: the `DAG-CBOR` encoding of the
+    /// operation without its signature.
+    ///
+    /// # Errors
+    /// Will fail if the operation cannot be encoded.
+    pub fn signing_input(&self) -> anyhow::Result<Vec<u8>> {
+        let mut value = serde_json::to_value(self)?;
+        if let Some(obj) = value.as_object_mut() {
+            obj.remove("sig");
+        }
+        cbor::encode(&value, cbor::Encoding::DagCbor)
+    }
+
+    /// The CID of the signed operation, as referenced by the `prev` field of
+    /// the operation that follows it.
+    ///
+    /// # Errors
+    /// Will fail if the operation cannot be encoded.
+    pub fn cid(&self) -> anyhow::Result<String> {
+        let digest = sha2::Sha256::digest(self.signed_bytes()?);
+        Ok(multibase::encode(Base::Base32Lower, [CID_PREFIX.as_slice(), &digest[..]].concat()))
+    }
+
+    /// The DID derived from a signed genesis operation.
+    ///
+    /// # Errors
+    /// Will fail if the operation is not a genesis operation or cannot be
+    /// encoded.
+    pub fn did(&self) -> anyhow::Result<String> {
+        if self.prev().is_some() {
+            bail!("only a genesis operation can be used to derive a did:plc");
+        }
+        let digest = sha2::Sha256::digest(self.signed_bytes()?);
+        let encoded = multibase::encode(Base::Base32Lower, digest);
+        Ok(format!("did:plc:{}", &encoded[1..=ID_LEN]))
+    }
+
+    fn signed_bytes(&self) -> anyhow::Result<Vec<u8>> {
+        cbor::encode(&serde_json::to_value(self)?, cbor::Encoding::DagCbor)
+    }
+}
+
+/// Check a `did:plc` method-specific identifier is 24 characters of
+/// lower-case base32.
+///
+/// # Errors
+/// Will fail with an [`Error::Parse`] if the identifier is not well-formed.
+pub fn validate_id(id: &str) -> anyhow::Result<()> {
+    if id.len() != ID_LEN
+        || !id.bytes().all(|b| b.is_ascii_lowercase() || (b'2'..=b'7').contains(&b))
+    {
+        let detail = "DID is not a valid did:plc - identifier must be 24 base32 characters";
+        return Err(Error::Parse(detail.to_string()).into());
+    }
+    Ok(())
+}
@@ -0,0 +1,332 @@
+//! # DID PLC Resolver
+//!
+//! Resolution of a DID for the `did:plc` method by verifying the audit log
+//! of operations returned by a PLC directory.
+
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail};
+use base64ct::{Base64UrlUnpadded, Encoding};
+use chrono::{DateTime, Duration, Utc};
+use k256::ecdsa::signature::Verifier;
+
+use super::{DEFAULT_DIRECTORY, LogEntry, Operation, PlcOperation, validate_id};
+use crate::core::Kind;
+use crate::did::key::DidKey;
+use crate::did::{
+    Document, DocumentBuilder, DocumentMetadataBuilder, ErrorCode, KeyCodec, KeyPurpose, Method,
+    MethodType, PublicKeyFormat, QueryParams, ResolutionOptions, ServiceBuilder, Url,
+    VerificationMethodBuilder, VmKeyId,
+};
+use crate::{Error, Identity, IdentityResolver, LogErrorKind};
+
+/// The period within which an operation signed by a higher-priority rotation
+/// key can nullify operations signed by a lower-priority key.
+const RECOVERY_WINDOW: Duration = Duration::hours(72);
+
+impl Url {
+    /// Convert a `did:plc` URL to an HTTP URL pointing to the DID's audit log
+    /// in the default PLC directory.
+    #[must_use]
+    pub fn to_plc_http(&self) -> String {
+        self.to_plc_http_in(DEFAULT_DIRECTORY)
+    }
+
+    /// Convert a `did:plc` URL to an HTTP URL pointing to the DID's audit log
+    /// in the given PLC directory, such as a self-hosted directory or a
+    /// mirror.
+    #[must_use]
+    pub fn to_plc_http_in(&self, directory: &str) -> String {
+        format!("{}/{}/log/audit", directory.trim_end_matches('/'), self.did())
+    }
+}
+
+/// Resolve a `did:plc` DID URL to a DID document using the default PLC
+/// directory.
+///
+/// The resolver is used to fetch the audit log for the DID from the
+/// directory (see [`Url::to_plc_http`]) and should return it as an
+/// [`Identity::PlcLog`]. The log is then verified using [`resolve_log`].
+///
+/// Version selection options are combined with the URL's query parameters
+/// (see [`ResolutionOptions::merge`]) and all options are passed on to the
+/// resolver.
+///
+/// # Errors
+///
+/// Will fail if the DID URL is invalid, the options conflict with the query
+/// parameters, the resolver returns an error or the log cannot be verified.
+pub async fn resolve(
+    url: &Url, options: Option<&ResolutionOptions>, resolver: &impl IdentityResolver,
+) -> anyhow::Result<Document> {
+    resolve_in(DEFAULT_DIRECTORY, url, options, resolver).await
+}
+
+/// Resolve a `did:plc` DID URL to a DID document as for [`resolve`], fetching
+/// the audit log from the given PLC directory (see [`Url::to_plc_http_in`]).
+///
+/// # Errors
+///
+/// Will fail if the DID URL is invalid, the options conflict with the query
+/// parameters, the resolver returns an error or the log cannot be verified.
+pub async fn resolve_in(
+    directory: &str, url: &Url, options: Option<&ResolutionOptions>,
+    resolver: &impl IdentityResolver,
+) -> anyhow::Result<Document> {
+    if url.method != Method::Plc {
+        return Err(Error::Parse(format!("DID is not a valid did:plc: {url}")).into());
+    }
+    validate_id(&url.id)?;
+
+    let options = ResolutionOptions::merge(options, url.query.as_ref())?;
+
+    let identity = resolver.resolve_with_options(&url.to_plc_http_in(directory), &options).await?;
+    let Identity::PlcLog(log) = identity else {
+        bail!("resolver did not return a did:plc audit log");
+    };
+    resolve_log(&url.did(), &log, Some(&options.query_params()))
+}
+
+/// Verification of a `did:plc` audit log and resolution into a DID document.
+///
+/// Every operation in the log is verified, in order:
+///
+/// - the genesis operation must hash to the DID;
+/// - each operation must be signed by one of the rotation keys of the
+///   operation it references;
+/// - an operation that references an earlier operation than the latest
+///   nullifies the operations that follow it, which is only allowed within 72
+///   hours and by a higher-priority rotation key;
+/// - no operation may follow a tombstone.
+///
+/// The `nullified` flag of each entry must agree with the verified log. The
+/// latest (or requested) state is returned as a DID document. A tombstoned
+/// DID resolves to an empty document with the `deactivated` metadata flag
+/// set.
+///
+/// The `versionId` query parameter selects an operation by CID and the
+/// `versionTime` parameter selects the latest operation created at or before
+/// the given time.
+///
+/// # Errors
+///
+/// Will fail with an [`Error::LogVerification`] carrying the CID of the
+/// offending operation if the log entries are invalid.
+#[allow(clippy::too_many_lines)]
+pub fn resolve_log(
+    did: &str, log: &[LogEntry], parameters: Option<&QueryParams>,
+) -> anyhow::Result<Document> {
+    if log.is_empty() {
+        return Err(Error::LogVerification {
+            kind: LogErrorKind::Empty,
+            version_id: None,
+            detail: "log entries are empty".to_string(),
+        }
+        .into());
+    }
+
+    // The operations currently in effect, from genesis to the latest
+    // operation, with the index of the rotation key that signed each.
+    let mut chain: Vec<(&LogEntry, usize)> = vec![];
+    let mut nullified: Vec<&str> = vec![];
+    let mut prev_time = DateTime::<Utc>::MIN_UTC;
+
+    for (i, entry) in log.iter().enumerate() {
+        // 1. The entry must be for this DID and its CID must match the
+        // operation.
+        if entry.did != did {
+            let detail = format!("log entry {i} is for a different DID: {}", entry.did);
+            return Err(Error::log(LogErrorKind::Did, &entry.cid, detail).into());
+        }
+        let cid = entry.operation.cid()?;
+        if cid != entry.cid {
+            let detail = format!("log entry {i} CID does not match the operation");
+            return Err(Error::log(LogErrorKind::Hash, &entry.cid, detail).into());
+        }
+
+        // 2. Entries are ordered by creation time.
+        if entry.created_at < prev_time {
+            let detail = format!(
+                "log entry times are not monotonically increasing: {prev_time} -> {}",
+                entry.created_at
+            );
+            return Err(Error::log(LogErrorKind::VersionTime, cid, detail).into());
+        }
+        prev_time = entry.created_at;
+
+        let Some(prev) = entry.operation.prev() else {
+            // 3. The genesis operation is signed by one of its own rotation
+            // keys and its hash is the DID.
+            if i != 0 {
+                let detail = format!(
+                    "operation {cid} is a genesis operation but is not the first log entry"
+                );
+                return Err(Error::log(LogErrorKind::Chain, cid, detail).into());
+            }
+            if entry.operation.did()? != did {
+                let detail = "genesis operation does not match the DID";
+                return Err(Error::log(LogErrorKind::Did, cid, detail).into());
+            }
+            let Some(rotation_keys) = entry.operation.rotation_keys() else {
+                let detail = "genesis operation cannot be a tombstone";
+                return Err(Error::log(LogErrorKind::Chain, cid, detail).into());
+            };
+            let key_index = verify_signature(&entry.operation, &rotation_keys)
+                .map_err(|e| Error::log(LogErrorKind::Proof, &cid, e.to_string()))?;
+            chain.push((entry, key_index));
+            continue;
+        };
+        if i == 0 {
+            let detail = "first log entry must be a genesis operation";
+            return Err(Error::log(LogErrorKind::Chain, cid, detail).into());
+        }
+
+        // 4. Later operations are signed by one of the rotation keys of the
+        // (non-nullified) operation they reference.
+        let Some(pos) = chain.iter().position(|(e, _)| e.cid == prev) else {
+            let detail =
+                format!("operation {cid} references an unknown or nullified operation {prev}");
+            return Err(Error::log(LogErrorKind::Chain, cid, detail).into());
+        };
+        let Some(rotation_keys) = chain[pos].0.operation.rotation_keys() else {
+            let detail = format!("operation {cid} follows a tombstone");
+            return Err(Error::log(LogErrorKind::Chain, cid, detail).into());
+        };
+        let key_index = verify_signature(&entry.operation, &rotation_keys)
+            .map_err(|e| Error::log(LogErrorKind::Proof, &cid, e.to_string()))?;
+
+        // 5. Referencing an earlier operation forks the log, nullifying the
+        // operations after it.
+        if let Some((first, first_index)) = chain.get(pos + 1) {
+            if key_index >= *first_index {
+                let detail = format!(
+                    "operation {cid} cannot nullify operations signed by an equal or higher-priority rotation key"
+                );
+                return Err(Error::log(LogErrorKind::Nullification, cid, detail).into());
+            }
+            if entry.created_at - first.created_at > RECOVERY_WINDOW {
+                let detail =
+                    format!("operation {cid} cannot nullify operations older than 72 hours");
+                return Err(Error::log(LogErrorKind::Nullification, cid, detail).into());
+            }
+            nullified.extend(chain.drain(pos + 1..).map(|(e, _)| e.cid.as_str()));
+        }
+        chain.push((entry, key_index));
+    }
+
+    // 6. The directory's view of nullified operations must match.
+    for entry in log {
+        if entry.nullified != nullified.contains(&entry.cid.as_str()) {
+            let detail =
+                format!("nullified flag for operation {} does not match the log", entry.cid);
+            return Err(Error::log(LogErrorKind::Nullification, &entry.cid, detail).into());
+        }
+    }
+
+    // 7. Select the requested version (or the latest).
+    let mut index = chain.len() - 1;
+    if let Some(params) = parameters {
+        if let Some(version_id) = &params.version_id {
+            let Some(pos) = chain.iter().position(|(e, _)| e.cid == *version_id) else {
+                let detail = format!("version {version_id} not found in log");
+                return Err(Error::resolution(ErrorCode::NotFound, detail).into());
+            };
+            index = pos;
+        }
+        if let Some(version_time) = &params.version_time {
+            let version_time = version_time.parse::<DateTime<Utc>>()?;
+            let Some(pos) = chain.iter().rposition(|(e, _)| e.created_at <= version_time) else {
+                let detail = format!("DID did not exist at {version_time}");
+                return Err(Error::resolution(ErrorCode::NotFound, detail).into());
+            };
+            index = index.min(pos);
+        }
+    }
+
+    let entry = chain[index].0;
+    let mut mdb =
+        DocumentMetadataBuilder::new().created(&chain[0].0.created_at).version_id(&entry.cid);
+    if index > 0 {
+        mdb = mdb.updated(&entry.created_at);
+    }
+    if let Some((next, _)) = chain.get(index + 1) {
+        mdb = mdb.next_update(&next.created_at).next_version_id(&next.cid);
+    }
+
+    let mut doc = if let Some(state) = entry.operation.state() {
+        document(did, &state)?
+    } else {
+        mdb = mdb.deactivated(true);
+        DocumentBuilder::new(did).build()
+    };
+    doc.did_document_metadata = Some(mdb.build());
+    Ok(doc)
+}
+
+// Verify the operation is signed by one of the rotation keys, returning the
+// index (priority) of the key that signed it.
+fn verify_signature(operation: &Operation, rotation_keys: &[String]) -> anyhow::Result<usize> {
+    let sig = Base64UrlUnpadded::decode_vec(operation.sig())
+        .map_err(|e| anyhow!("invalid did:plc operation signature encoding: {e}"))?;
+    let msg = operation.signing_input()?;
+
+    for (index, key) in rotation_keys.iter().enumerate() {
+        let multikey = DidKey::from_str(key)?.multikey().clone();
+        let verified = match multikey.codec() {
+            KeyCodec::Secp256k1 => {
+                let Ok(sig) = k256::ecdsa::Signature::from_slice(&sig) else {
+                    bail!("invalid did:plc operation signature");
+                };
+                if sig.normalize_s().is_some() {
+                    bail!("did:plc operation signatures must be in low-S form");
+                }
+                let vk = k256::ecdsa::VerifyingKey::from_sec1_bytes(multikey.key())
+                    .map_err(|e| anyhow!("invalid secp256k1 rotation key: {e}"))?;
+                vk.verify(&msg, &sig).is_ok()
+            }
+            KeyCodec::P256 => {
+                let Ok(sig) = p256::ecdsa::Signature::from_slice(&sig) else {
+                    bail!("invalid did:plc operation signature");
+                };
+                if sig.normalize_s().is_some() {
+                    bail!("did:plc operation signatures must be in low-S form");
+                }
+                let vk = p256::ecdsa::VerifyingKey::from_sec1_bytes(multikey.key())
+                    .map_err(|e| anyhow!("invalid P-256 rotation key: {e}"))?;
+                vk.verify(&msg, &sig).is_ok()
+            }
+            codec => bail!("unsupported did:plc rotation key type: {codec}"),
+        };
+        if verified {
+            return Ok(index);
+        }
+    }
+    bail!("operation is not signed by a valid rotation key")
+}
+
+// Build a DID document from the state of the DID.
+fn document(did: &str, state: &PlcOperation) -> anyhow::Result<Document> {
+    let mut builder = DocumentBuilder::new(did);
+    for aka in &state.also_known_as {
+        builder = builder.also_known_as(aka);
+    }
+    for (name, key) in &state.verification_methods {
+        let multikey = DidKey::from_str(key)?.multikey().clone();
+        let vm = VerificationMethodBuilder::new(&PublicKeyFormat::PublicKeyMultibase {
+            public_key_multibase: multikey.to_multibase(),
+        })
+        .key_id(did, VmKeyId::Fragment(name.clone()))?
+        .method_type(&MethodType::Multikey)?
+        .build();
+        builder =
+            builder.add_verification_method(&Kind::Object(vm), &KeyPurpose::VerificationMethod)?;
+    }
+    for (name, service) in &state.services {
+        let service = ServiceBuilder::new(&format!("{did}#{name}"))
+            .service_type(&service.type_)
+            .endpoint_str(&service.endpoint)
+            .build();
+        builder = builder.add_service(&service);
+    }
+    Ok(builder.build())
+}
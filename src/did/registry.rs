@@ -0,0 +1,284 @@
+//! # DID Method Registry
+//!
+//! DID methods are pluggable: each method implements [`DidMethod`] and a
+//! [`Registry`] dispatches parsing, resolution and dereferencing of DID URLs
+//! to the method named in the URL.
+//!
+//! The methods built into this crate are registered by default (see
+//! [`Method`]). Additional methods can be registered to extend — or replace —
+//! the built-in methods.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use anyhow::bail;
+
+use super::hashlink;
+use super::resolution::{ResolutionOptions, ResolutionResult, resolve_method};
+use super::resolve::document_resource;
+use super::{
+    Document, ErrorCode, Method, Resource, Url, jwk, key, peer, pkh, plc, web, webvh, x509,
+};
+use crate::{Error, Identity, IdentityResolver};
+
+/// A boxed future returned by [`DidMethod`] operations.
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// A DID method that can be registered with a [`Registry`].
+///
+/// The trait is generic over the identity resolver so that methods can be
+/// used as trait objects.
+pub trait DidMethod<R: IdentityResolver>: Send + Sync {
+    /// The method name. For example, `key` for `did:key`.
+    fn name(&self) -> &str;
+
+    /// Parse a DID URL for this method.
+    ///
+    /// The default implementation parses the generic DID URL syntax and
+    /// checks the URL names this method. Implementers should override this
+    /// to also validate the method-specific ID.
+    ///
+    /// # Errors
+    /// Will fail with an [`Error::Parse`] if the DID URL is not a valid URL
+    /// for this method.
+    fn parse(&self, did_url: &str) -> anyhow::Result<Url> {
+        let url = Url::from_str(did_url)?;
+        if url.method != self.name() {
+            let detail = format!("DID URL is not a did:{} URL: {did_url}", self.name());
+            return Err(Error::Parse(detail).into());
+        }
+        Ok(url)
+    }
+
+    /// Resolve a DID URL to a DID document.
+    ///
+    /// Hosted methods should use the resolver to fetch identity material,
+    /// passing on the resolution options (see
+    /// [`IdentityResolver::resolve_with_options`]). Self-describing methods
+    /// can ignore the resolver.
+    fn resolve<'a>(
+        &'a self, url: &'a Url, options: Option<&'a ResolutionOptions>, resolver: &'a R,
+    ) -> BoxFuture<'a, anyhow::Result<Document>>;
+
+    /// Dereference a DID URL to a resource.
+    ///
+    /// The default implementation resolves the DID document and finds the
+    /// resource in the document using [`document_resource`].
+    fn dereference<'a>(
+        &'a self, url: &'a Url, options: Option<&'a ResolutionOptions>, resolver: &'a R,
+    ) -> BoxFuture<'a, anyhow::Result<Resource>> {
+        Box::pin(async move {
+            let doc = self.resolve(url, options, resolver).await?;
+            document_resource(url, options, &doc)
+        })
+    }
+}
+
+impl<R: IdentityResolver> DidMethod<R> for Method {
+    fn name(&self) -> &str {
+        self.as_str()
+    }
+
+    fn parse(&self, did_url: &str) -> anyhow::Result<Url> {
+        let url = Url::from_str(did_url)?;
+        if url.method != *self {
+            let detail = format!("DID URL is not a did:{self} URL: {did_url}");
+            return Err(Error::Parse(detail).into());
+        }
+        let valid = match self {
+            Self::Key => key::DidKey::from_url(&url).map(|_| ()),
+            Self::Jwk => jwk::DidJwk::from_url(&url).map(|_| ()),
+            Self::Pkh => pkh::AccountId::from_str(&url.id).map(|_| ()),
+            Self::Plc => plc::validate_id(&url.id),
+            Self::X509 => x509::DidX509::from_url(&url).map(|_| ()),
+            Self::Peer | Self::Web | Self::WebVh => Ok(()),
+        };
+        valid.map_err(|e| Error::Parse(e.to_string()))?;
+        Ok(url)
+    }
+
+    fn resolve<'a>(
+        &'a self, url: &'a Url, options: Option<&'a ResolutionOptions>, resolver: &'a R,
+    ) -> BoxFuture<'a, anyhow::Result<Document>> {
+        Box::pin(async move {
+            self.check_options(url, options)?;
+            match self {
+                Self::Key => key::document(url),
+                Self::Jwk => jwk::document(url),
+                Self::Peer => peer::document(url),
+                Self::Pkh => pkh::document(url),
+                Self::Plc => plc::resolve(url, options, resolver).await,
+                Self::Web => web::resolve(url, options, resolver).await,
+                Self::WebVh => webvh::resolve(url, options, resolver).await,
+                Self::X509 => {
+                    let options = options.cloned().unwrap_or_default();
+                    let identity = resolver.resolve_with_options(&url.did(), &options).await?;
+                    let Identity::X509Chain(chain) = identity else {
+                        bail!("resolver did not return a certificate chain");
+                    };
+                    x509::document(url, chain.certificates())
+                }
+            }
+        })
+    }
+
+    fn dereference<'a>(
+        &'a self, url: &'a Url, options: Option<&'a ResolutionOptions>, resolver: &'a R,
+    ) -> BoxFuture<'a, anyhow::Result<Resource>> {
+        Box::pin(async move {
+            if *self == Self::WebVh {
+                self.check_options(url, options)?;
+                return webvh::dereference(url, options, resolver).await;
+            }
+            let doc = DidMethod::<R>::resolve(self, url, options, resolver).await?;
+            document_resource(url, options, &doc)
+        })
+    }
+}
+
+impl Method {
+    // Check the options can be honoured by the method. Only methods with a
+    // version history support version selection.
+    fn check_options(self, url: &Url, options: Option<&ResolutionOptions>) -> anyhow::Result<()> {
+        let options = ResolutionOptions::merge(options, url.query.as_ref())?;
+        match self {
+            Self::Key | Self::Jwk | Self::Peer | Self::Pkh | Self::X509
+                if options.selects_version() =>
+            {
+                let detail = format!("did:{self} does not support version selection");
+                Err(Error::resolution(ErrorCode::InvalidOptions, detail).into())
+            }
+            Self::Plc if options.version_number.is_some() => {
+                let detail = "did:plc does not support version numbers";
+                Err(Error::resolution(ErrorCode::InvalidOptions, detail).into())
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// A registry of DID methods, keyed by method name.
+///
+/// [`Registry::default`] registers the methods built into this crate.
+pub struct Registry<R: IdentityResolver> {
+    methods: HashMap<String, Arc<dyn DidMethod<R>>>,
+}
+
+impl<R: IdentityResolver> Default for Registry<R> {
+    fn default() -> Self {
+        let mut registry = Self::new();
+        for method in Method::ALL {
+            registry = registry.register(method);
+        }
+        registry
+    }
+}
+
+impl<R: IdentityResolver> Clone for Registry<R> {
+    fn clone(&self) -> Self {
+        Self {
+            methods: self.methods.clone(),
+        }
+    }
+}
+
+impl<R: IdentityResolver> Registry<R> {
+    /// Create an empty registry.
+    ///
+    /// Use [`Registry::default`] for a registry with the methods built into
+    /// this crate.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            methods: HashMap::new(),
+        }
+    }
+
+    /// Register a DID method, replacing any method with the same name.
+    #[must_use]
+    pub fn register(mut self, method: impl DidMethod<R> + 'static) -> Self {
+        self.methods.insert(method.name().to_string(), Arc::new(method));
+        self
+    }
+
+    /// The registered method with the given name, if any.
+    #[must_use]
+    pub fn method(&self, name: &str) -> Option<&dyn DidMethod<R>> {
+        self.methods.get(name).map(AsRef::as_ref)
+    }
+
+    /// Parse a DID URL using the method named in the URL.
+    ///
+    /// # Errors
+    /// Will fail if the URL cannot be parsed, the method is not registered or
+    /// the method rejects the URL.
+    pub fn parse(&self, did_url: &str) -> anyhow::Result<Url> {
+        let url = Url::from_str(did_url)?;
+        self.get(&url.method)?.parse(did_url)
+    }
+
+    /// Resolve a DID URL to a DID document using the method named in the URL.
+    ///
+    /// # Errors
+    /// Will fail if the method is not registered, resolution fails or the
+    /// document does not match the URL's hashlink (`hl`) or the
+    /// `expectedHash` option.
+    pub async fn resolve(
+        &self, url: &Url, options: Option<&ResolutionOptions>, resolver: &R,
+    ) -> anyhow::Result<Document> {
+        let doc = self.get(&url.method)?.resolve(url, options, resolver).await?;
+        hashlink::verify_document(url, options, &doc)?;
+        Ok(doc)
+    }
+
+    /// Resolve a DID into a [`ResolutionResult`] using the method named in the
+    /// DID.
+    ///
+    /// See [`super::resolve_did`].
+    pub async fn resolve_did(
+        &self, did: &str, options: Option<&ResolutionOptions>, resolver: &R,
+    ) -> ResolutionResult {
+        let url = match Url::from_str(did) {
+            Ok(url) => url,
+            Err(e) => return ResolutionResult::error(ErrorCode::InvalidDid, e.to_string()),
+        };
+        match self.get(&url.method) {
+            Ok(method) => resolve_method(method, did, options, resolver).await,
+            Err(e) => ResolutionResult::error(ErrorCode::MethodNotSupported, e.to_string()),
+        }
+    }
+
+    /// Dereference a DID URL into a resource using the method named in the
+    /// URL.
+    ///
+    /// # Errors
+    /// Will fail if the URL cannot be parsed, the method is not registered or
+    /// dereferencing fails.
+    pub async fn dereference(
+        &self, did_url: &str, options: Option<&ResolutionOptions>, resolver: &R,
+    ) -> anyhow::Result<Resource> {
+        let url = Url::from_str(did_url)?;
+        self.deref_url(&url, options, resolver).await
+    }
+
+    /// Dereference a structured DID URL into a resource using the method
+    /// named in the URL.
+    ///
+    /// # Errors
+    /// Will fail if the method is not registered or dereferencing fails.
+    pub async fn deref_url(
+        &self, url: &Url, options: Option<&ResolutionOptions>, resolver: &R,
+    ) -> anyhow::Result<Resource> {
+        self.get(&url.method)?.dereference(url, options, resolver).await
+    }
+
+    fn get(&self, name: &str) -> anyhow::Result<&dyn DidMethod<R>> {
+        self.method(name).ok_or_else(|| {
+            let detail = format!("method not supported: {name}");
+            Error::resolution(ErrorCode::MethodNotSupported, detail).into()
+        })
+    }
+}
@@ -0,0 +1,198 @@
+//! # DID Document Representations
+//!
+//! Production and consumption of the concrete representations of a DID
+//! document, as described in
+//! [DID Core §6](https://www.w3.org/TR/did-core/#representations):
+//!
+//! - `application/did+json`: the document as JSON, without `@context`.
+//! - `application/did+ld+json`: the document as JSON-LD. The `@context` entry
+//!   is required and its first value must be the DID v1 context.
+//! - `application/did+cbor`: the JSON representation encoded as CBOR
+//!   ([RFC 8949](https://www.rfc-editor.org/rfc/rfc8949)), for constrained
+//!   devices.
+//!
+//! Document metadata is not part of the DID document and is never produced.
+//! Properties not defined by this crate are preserved in
+//! [`Document::additional`] (and [`super::Service::additional`]) when
+//! consuming, and produced as they were.
+
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+use anyhow::{Context, bail};
+use serde_json::Value;
+
+use super::cbor::{self, Encoding};
+use super::{DID_CONTEXT, Document, ErrorCode};
+use crate::Error;
+use crate::core::Kind;
+
+/// The media type of the JSON representation of a DID document.
+pub const DID_JSON: &str = "application/did+json";
+
+/// The media type of the JSON-LD representation of a DID document.
+pub const DID_LD_JSON: &str = "application/did+ld+json";
+
+/// The media type of the CBOR representation of a DID document.
+pub const DID_CBOR: &str = "application/did+cbor";
+
+/// A concrete representation of a DID document.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Representation {
+    /// `application/did+json`
+    #[default]
+    Json,
+
+    /// `application/did+ld+json`
+    JsonLd,
+
+    /// `application/did+cbor`
+    Cbor,
+}
+
+impl Representation {
+    /// The media type of the representation.
+    #[must_use]
+    pub const fn media_type(self) -> &'static str {
+        match self {
+            Self::Json => DID_JSON,
+            Self::JsonLd => DID_LD_JSON,
+            Self::Cbor => DID_CBOR,
+        }
+    }
+
+    /// The representation with the given media type, ignoring any
+    /// parameters. The generic `application/json`, `application/ld+json` and
+    /// `application/cbor` media types are also recognized.
+    #[must_use]
+    pub fn from_media_type(media_type: &str) -> Option<Self> {
+        let media_type = media_type.split(';').next().unwrap_or_default().trim();
+        match media_type.to_ascii_lowercase().as_str() {
+            DID_JSON | "application/json" => Some(Self::Json),
+            DID_LD_JSON | "application/ld+json" => Some(Self::JsonLd),
+            DID_CBOR | "application/cbor" => Some(Self::Cbor),
+            _ => None,
+        }
+    }
+
+    /// Select the representation to return for an `accept` value, such as
+    /// the `accept` resolution option or HTTP header.
+    ///
+    /// The supported media range with the highest quality (`q`) wins, with
+    /// earlier ranges winning ties. Wildcard ranges select the default
+    /// (JSON) representation. Returns `None` when no range is supported.
+    #[must_use]
+    pub fn negotiate(accept: &str) -> Option<Self> {
+        let mut selected: Option<(Self, f32)> = None;
+        for range in accept.split(',') {
+            let mut parts = range.split(';');
+            let media_type = parts.next().unwrap_or_default().trim();
+            let quality = parts
+                .filter_map(|p| p.trim().strip_prefix("q="))
+                .find_map(|q| q.trim().parse::<f32>().ok())
+                .unwrap_or(1.0);
+            if quality <= 0.0 {
+                continue;
+            }
+            let representation = match media_type {
+                "*/*" | "application/*" => Self::default(),
+                _ => match Self::from_media_type(media_type) {
+                    Some(representation) => representation,
+                    None => continue,
+                },
+            };
+            if selected.is_none_or(|(_, q)| quality > q) {
+                selected = Some((representation, quality));
+            }
+        }
+        selected.map(|(representation, _)| representation)
+    }
+}
+
+impl Display for Representation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(self.media_type())
+    }
+}
+
+impl FromStr for Representation {
+    type Err = anyhow::Error;
+
+    /// Parse a media type into a [`Representation`].
+    ///
+    /// # Errors
+    ///
+    /// Fails with an [`Error::Resolution`] with the code
+    /// [`ErrorCode::RepresentationNotSupported`] if the media type is not a
+    /// supported representation.
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        Self::from_media_type(s).ok_or_else(|| {
+            let detail = format!("representation not supported: {s}");
+            Error::resolution(ErrorCode::RepresentationNotSupported, detail).into()
+        })
+    }
+}
+
+impl Document {
+    /// Produce the document in the given representation.
+    ///
+    /// For JSON-LD, the DID v1 context is added as the first context if the
+    /// document does not already start with it.
+    ///
+    /// # Errors
+    ///
+    /// Will fail if the document cannot be serialized.
+    pub fn produce(&self, representation: Representation) -> anyhow::Result<Vec<u8>> {
+        let mut doc = Self {
+            did_document_metadata: None,
+            ..self.clone()
+        };
+        if representation == Representation::JsonLd {
+            let did_context = Kind::String(DID_CONTEXT.to_string());
+            doc.context.retain(|c| *c != did_context);
+            doc.context.insert(0, did_context);
+        }
+
+        let mut value = serde_json::to_value(&doc)?;
+        if let (Representation::Json | Representation::Cbor, Some(map)) =
+            (representation, value.as_object_mut())
+        {
+            map.remove("@context");
+        }
+        match representation {
+            Representation::Json | Representation::JsonLd => Ok(serde_json::to_vec(&value)?),
+            Representation::Cbor => cbor::encode(&value, Encoding::Core),
+        }
+    }
+
+    /// Consume a document in the given representation.
+    ///
+    /// Any `@context` in a JSON or CBOR document is kept as it is.
+    ///
+    /// # Errors
+    ///
+    /// Will fail if the content is not a valid DID document in the
+    /// representation or, for JSON-LD, if the document's first context is
+    /// not the DID v1 context.
+    pub fn consume(content: &[u8], representation: Representation) -> anyhow::Result<Self> {
+        let value = match representation {
+            Representation::Json | Representation::JsonLd => {
+                serde_json::from_slice::<Value>(content)?
+            }
+            Representation::Cbor => cbor::decode(content)?,
+        };
+        if !value.is_object() {
+            bail!("{representation} DID document is not a map");
+        }
+        let doc: Self = serde_json::from_value(value)
+            .with_context(|| format!("invalid {representation} DID document"))?;
+
+        if representation == Representation::JsonLd {
+            match doc.context.first() {
+                Some(Kind::String(c)) if c == DID_CONTEXT => {}
+                _ => bail!("first @context must be {DID_CONTEXT}"),
+            }
+        }
+        Ok(doc)
+    }
+}
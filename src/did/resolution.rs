@@ -0,0 +1,496 @@
+//! # DID Resolution Result
+//!
+//! The result of resolving a DID, as defined by
+//! [W3C DID Resolution](https://w3c.github.io/did-resolution/#did-resolution-result).
+//!
+//! Resolution never fails outright: errors are reported in the resolution
+//! metadata using the standard error codes so callers can distinguish, for
+//! example, an invalid DID from a DID that could not be found. Methods and
+//! identity resolvers can report a specific error code by returning a
+//! [`ResolutionError`] (or an [`Error::Resolution`]).
+//!
+//! Callers can refine resolution using [`ResolutionOptions`].
+
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use super::{
+    DID_JSON, DidMethod, Document, DocumentMetadata, Method, QueryParams, Representation, Url,
+    hashlink,
+};
+use crate::{Error, IdentityResolver};
+
+const ERROR_TYPE_BASE: &str = "https://www.w3.org/ns/did#";
+
+/// Resolve a DID into a [`ResolutionResult`] using the methods built into
+/// this crate.
+///
+/// Unlike [`super::dereference`], resolution errors are returned in the
+/// result's `didResolutionMetadata` rather than as an error.
+///
+/// Use a [`super::Registry`] to resolve DIDs for other methods.
+pub async fn resolve_did<R: IdentityResolver>(
+    did: &str, options: Option<&ResolutionOptions>, resolver: &R,
+) -> ResolutionResult {
+    let url = match Url::from_str(did) {
+        Ok(url) => url,
+        Err(e) => return ResolutionResult::error(ErrorCode::InvalidDid, e.to_string()),
+    };
+    let Ok(method) = Method::from_str(&url.method) else {
+        let detail = format!("method not supported: {}", url.method);
+        return ResolutionResult::error(ErrorCode::MethodNotSupported, detail);
+    };
+    resolve_method(&method, did, options, resolver).await
+}
+
+// Resolve a DID using the given method.
+pub(super) async fn resolve_method<R: IdentityResolver>(
+    method: &dyn DidMethod<R>, did: &str, options: Option<&ResolutionOptions>, resolver: &R,
+) -> ResolutionResult {
+    let accept = options.and_then(|o| o.accept.as_deref());
+    let Some(representation) =
+        accept.map_or_else(|| Some(Representation::default()), Representation::negotiate)
+    else {
+        let detail = format!("representation not supported: {}", accept.unwrap_or_default());
+        return ResolutionResult::error(ErrorCode::RepresentationNotSupported, detail);
+    };
+    let url = match method.parse(did) {
+        Ok(url) => url,
+        Err(e) => return ResolutionResult::from(e.context(ErrorCode::InvalidDid)),
+    };
+    if url.path.is_some() || url.fragment.is_some() {
+        let detail = format!("not a DID: {did}");
+        return ResolutionResult::error(ErrorCode::InvalidDid, detail);
+    }
+    let doc = match method.resolve(&url, options, resolver).await {
+        Ok(doc) => doc,
+        Err(e) => return ResolutionResult::from(e),
+    };
+    if let Err(e) = hashlink::verify_document(&url, options, &doc) {
+        return ResolutionResult::from(e);
+    }
+    let mut result = ResolutionResult::from(doc);
+    result.did_resolution_metadata.content_type = Some(representation.media_type().to_string());
+    result
+}
+
+/// Options for DID resolution and dereferencing.
+///
+/// The version options select a version of the DID document in the same way
+/// as the equivalent DID URL query parameters (see [`QueryParams`]). Only
+/// methods with a version history (`did:webvh` and `did:plc`) support them.
+///
+/// All options are passed on to the identity resolver (see
+/// [`IdentityResolver::resolve_with_options`]), which is responsible for
+/// honouring `noCache`.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ResolutionOptions {
+    /// The media type of the preferred representation of the DID document,
+    /// as an HTTP `accept` value (see [`Representation::negotiate`]).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub accept: Option<String>,
+
+    /// The version of the DID document to resolve.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version_id: Option<String>,
+
+    /// Resolve the version of the DID document that was valid at this time.
+    /// An XML datetime value normalized to UTC without sub-second precision.
+    /// For example: 2020-12-20T19:17:47Z.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version_time: Option<String>,
+
+    /// The version number of the DID document to resolve, for methods with
+    /// numbered versions (such as `did:webvh`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version_number: Option<String>,
+
+    /// Bypass any cache and resolve the DID from its source.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub no_cache: bool,
+
+    /// The expected hash of the resolved content, as a hashlink. Equivalent
+    /// to the `hl` DID URL query parameter.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expected_hash: Option<String>,
+}
+
+impl ResolutionOptions {
+    /// Combine optional resolution options with the query parameters of a
+    /// DID URL.
+    ///
+    /// An option and the equivalent query parameter may both be set as long
+    /// as they agree.
+    ///
+    /// # Errors
+    /// Will fail with an [`Error::Resolution`] with the code
+    /// [`ErrorCode::InvalidOptions`] if an option conflicts with a query
+    /// parameter.
+    pub fn merge(options: Option<&Self>, query: Option<&QueryParams>) -> anyhow::Result<Self> {
+        let mut merged = options.cloned().unwrap_or_default();
+        let Some(query) = query else {
+            return Ok(merged);
+        };
+        for (name, option, param) in [
+            ("versionId", &mut merged.version_id, &query.version_id),
+            ("versionTime", &mut merged.version_time, &query.version_time),
+            ("versionNumber", &mut merged.version_number, &query.version_number),
+            ("hl", &mut merged.expected_hash, &query.hashlink),
+        ] {
+            match (&option, param) {
+                (Some(o), Some(p)) if o != p => {
+                    let detail = format!("{name} option {o} conflicts with query parameter {p}");
+                    return Err(Error::resolution(ErrorCode::InvalidOptions, detail).into());
+                }
+                (None, Some(p)) => *option = Some(p.clone()),
+                _ => {}
+            }
+        }
+        Ok(merged)
+    }
+
+    /// Whether the options select a version of the DID document.
+    #[must_use]
+    pub const fn selects_version(&self) -> bool {
+        self.version_id.is_some() || self.version_time.is_some() || self.version_number.is_some()
+    }
+
+    /// The version selection options as DID URL query parameters, for use
+    /// with log-based resolution (for example, `webvh::resolve_log`).
+    #[must_use]
+    pub fn query_params(&self) -> QueryParams {
+        QueryParams {
+            version_id: self.version_id.clone(),
+            version_time: self.version_time.clone(),
+            version_number: self.version_number.clone(),
+            hashlink: self.expected_hash.clone(),
+            ..QueryParams::default()
+        }
+    }
+}
+
+/// The result of resolving a DID.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ResolutionResult {
+    /// Metadata about the resolution process.
+    pub did_resolution_metadata: ResolutionMetadata,
+
+    /// The resolved DID document, if resolution was successful.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub did_document: Option<Document>,
+
+    /// Metadata about the DID document. Serialized as an empty object if
+    /// resolution was unsuccessful.
+    #[serde(serialize_with = "empty_if_none", deserialize_with = "none_if_empty", default)]
+    pub did_document_metadata: Option<DocumentMetadata>,
+}
+
+impl ResolutionResult {
+    /// Create an unsuccessful resolution result.
+    #[must_use]
+    pub fn error(code: ErrorCode, detail: impl Into<String>) -> Self {
+        Self {
+            did_resolution_metadata: ResolutionMetadata {
+                content_type: None,
+                error: Some(ProblemDetails::new(code, detail)),
+            },
+            did_document: None,
+            did_document_metadata: None,
+        }
+    }
+
+    /// The error code, if resolution was unsuccessful.
+    #[must_use]
+    pub fn error_code(&self) -> Option<ErrorCode> {
+        self.did_resolution_metadata.error.as_ref().and_then(ProblemDetails::code)
+    }
+
+    /// The resolved DID document produced in the representation given by the
+    /// resolution metadata's `contentType` (JSON if not set). `None` if
+    /// resolution was unsuccessful.
+    ///
+    /// # Errors
+    ///
+    /// Will fail if the content type is not a supported representation or
+    /// the document cannot be serialized.
+    pub fn did_document_stream(&self) -> anyhow::Result<Option<Vec<u8>>> {
+        let Some(doc) = &self.did_document else {
+            return Ok(None);
+        };
+        let representation = match &self.did_resolution_metadata.content_type {
+            Some(content_type) => content_type.parse()?,
+            None => Representation::default(),
+        };
+        Ok(Some(doc.produce(representation)?))
+    }
+
+    /// Whether the resolved DID has been deactivated.
+    #[must_use]
+    pub fn deactivated(&self) -> bool {
+        self.did_document_metadata.as_ref().and_then(|md| md.deactivated).unwrap_or_default()
+    }
+
+    /// The HTTP status code corresponding to the result, using the same
+    /// mapping as the Universal Resolver: `410 Gone` for a deactivated DID,
+    /// otherwise `200 OK` or the status for the error code (see
+    /// [`ErrorCode::http_status`]).
+    #[must_use]
+    pub fn http_status(&self) -> u16 {
+        if let Some(error) = &self.did_resolution_metadata.error {
+            return error.code().map_or(500, ErrorCode::http_status);
+        }
+        if self.deactivated() { 410 } else { 200 }
+    }
+}
+
+/// A successfully resolved DID document. The document's metadata is moved
+/// to the result's `didDocumentMetadata`.
+impl From<Document> for ResolutionResult {
+    fn from(mut doc: Document) -> Self {
+        let did_document_metadata = doc.did_document_metadata.take();
+        Self {
+            did_resolution_metadata: ResolutionMetadata {
+                content_type: Some(DID_JSON.to_string()),
+                error: None,
+            },
+            did_document: Some(doc),
+            did_document_metadata,
+        }
+    }
+}
+
+/// An unsuccessful resolution. The error code is taken from an [`ErrorCode`]
+/// added as context, or an [`Error`] or [`ResolutionError`] in the error (see
+/// [`Error::code`]), defaulting to [`ErrorCode::InternalError`].
+impl From<anyhow::Error> for ResolutionResult {
+    fn from(err: anyhow::Error) -> Self {
+        let context = err.downcast_ref::<ErrorCode>().copied();
+        if let Some(e) = err.downcast_ref::<Error>() {
+            return Self::error(context.unwrap_or_else(|| e.code()), e.to_string());
+        }
+        if let Some(re) = err.downcast_ref::<ResolutionError>() {
+            return Self::error(context.unwrap_or(re.code), re.detail.clone());
+        }
+        let code = context.unwrap_or(ErrorCode::InternalError);
+
+        // Report the underlying cause rather than the error code context.
+        Self::error(code, err.root_cause().to_string())
+    }
+}
+
+/// Metadata about the resolution process.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ResolutionMetadata {
+    /// The media type of the returned DID document representation.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+
+    /// Details of the error, if resolution was unsuccessful.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<ProblemDetails>,
+}
+
+/// An [RFC 9457](https://www.rfc-editor.org/rfc/rfc9457) problem details
+/// object describing a resolution error.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct ProblemDetails {
+    /// A URI identifying the error type. For standard errors, this is the
+    /// error code's type URI (see [`ErrorCode::type_uri`]).
+    #[serde(rename = "type")]
+    pub type_: String,
+
+    /// A short, human-readable summary of the error type.
+    pub title: String,
+
+    /// A human-readable explanation specific to this occurrence of the error.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+impl ProblemDetails {
+    /// Create problem details for a standard error code.
+    #[must_use]
+    pub fn new(code: ErrorCode, detail: impl Into<String>) -> Self {
+        let detail: String = detail.into();
+        Self {
+            type_: code.type_uri(),
+            title: code.title().to_string(),
+            detail: (!detail.is_empty()).then_some(detail),
+        }
+    }
+
+    /// The standard error code for the error type, if any.
+    #[must_use]
+    pub fn code(&self) -> Option<ErrorCode> {
+        ErrorCode::ALL.into_iter().find(|code| code.type_uri() == self.type_)
+    }
+}
+
+/// Standard DID resolution error codes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ErrorCode {
+    /// The DID is not syntactically valid, or not valid for its method.
+    InvalidDid,
+
+    /// The DID URL is not syntactically valid.
+    InvalidDidUrl,
+
+    /// The DID does not exist.
+    NotFound,
+
+    /// The requested representation of the DID document is not supported.
+    RepresentationNotSupported,
+
+    /// The DID method is not supported.
+    MethodNotSupported,
+
+    /// The resolution options are not valid.
+    InvalidOptions,
+
+    /// An unexpected error occurred during resolution.
+    InternalError,
+}
+
+impl ErrorCode {
+    const ALL: [Self; 7] = [
+        Self::InvalidDid,
+        Self::InvalidDidUrl,
+        Self::NotFound,
+        Self::RepresentationNotSupported,
+        Self::MethodNotSupported,
+        Self::InvalidOptions,
+        Self::InternalError,
+    ];
+
+    /// The error type URI used in [`ProblemDetails`]. For example,
+    /// `https://www.w3.org/ns/did#NOT_FOUND`.
+    #[must_use]
+    pub fn type_uri(self) -> String {
+        let name = match self {
+            Self::InvalidDid => "INVALID_DID",
+            Self::InvalidDidUrl => "INVALID_DID_URL",
+            Self::NotFound => "NOT_FOUND",
+            Self::RepresentationNotSupported => "REPRESENTATION_NOT_SUPPORTED",
+            Self::MethodNotSupported => "METHOD_NOT_SUPPORTED",
+            Self::InvalidOptions => "INVALID_OPTIONS",
+            Self::InternalError => "INTERNAL_ERROR",
+        };
+        format!("{ERROR_TYPE_BASE}{name}")
+    }
+
+    /// A short, human-readable summary of the error.
+    #[must_use]
+    pub const fn title(self) -> &'static str {
+        match self {
+            Self::InvalidDid => "Invalid DID",
+            Self::InvalidDidUrl => "Invalid DID URL",
+            Self::NotFound => "DID not found",
+            Self::RepresentationNotSupported => "Representation not supported",
+            Self::MethodNotSupported => "DID method not supported",
+            Self::InvalidOptions => "Invalid resolution options",
+            Self::InternalError => "Internal error",
+        }
+    }
+
+    /// The HTTP status code for the error, as used by the Universal
+    /// Resolver.
+    #[must_use]
+    pub const fn http_status(self) -> u16 {
+        match self {
+            Self::InvalidDid | Self::InvalidDidUrl | Self::InvalidOptions => 400,
+            Self::NotFound => 404,
+            Self::RepresentationNotSupported => 406,
+            Self::MethodNotSupported => 501,
+            Self::InternalError => 500,
+        }
+    }
+}
+
+/// The error code as it appears in the legacy `error` string property. For
+/// example, `notFound`.
+impl Display for ErrorCode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let code = match self {
+            Self::InvalidDid => "invalidDid",
+            Self::InvalidDidUrl => "invalidDidUrl",
+            Self::NotFound => "notFound",
+            Self::RepresentationNotSupported => "representationNotSupported",
+            Self::MethodNotSupported => "methodNotSupported",
+            Self::InvalidOptions => "invalidOptions",
+            Self::InternalError => "internalError",
+        };
+        f.write_str(code)
+    }
+}
+
+impl std::error::Error for ErrorCode {}
+
+/// An error with a standard DID resolution error code.
+///
+/// Return this error (or add an [`ErrorCode`] as context to another error)
+/// from a [`DidMethod`] or [`IdentityResolver`] to report the error code in
+/// the [`ResolutionResult`]. For example, a resolver should return
+/// [`ErrorCode::NotFound`] when a DID does not exist.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ResolutionError {
+    code: ErrorCode,
+    detail: String,
+}
+
+impl ResolutionError {
+    /// Create a new resolution error.
+    #[must_use]
+    pub fn new(code: ErrorCode, detail: impl Into<String>) -> Self {
+        Self {
+            code,
+            detail: detail.into(),
+        }
+    }
+
+    /// The error code.
+    #[must_use]
+    pub const fn code(&self) -> ErrorCode {
+        self.code
+    }
+
+    /// A human-readable explanation of the error.
+    #[must_use]
+    pub fn detail(&self) -> &str {
+        &self.detail
+    }
+}
+
+impl Display for ResolutionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.code, self.detail)
+    }
+}
+
+impl std::error::Error for ResolutionError {}
+
+// Serialize missing document metadata as an empty object.
+#[allow(clippy::ref_option)]
+fn empty_if_none<S: Serializer>(
+    md: &Option<DocumentMetadata>, serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match md {
+        Some(md) => md.serialize(serializer),
+        None => serde_json::Map::new().serialize(serializer),
+    }
+}
+
+// Deserialize an empty object as missing document metadata.
+fn none_if_empty<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<DocumentMetadata>, D::Error> {
+    let value = serde_json::Value::deserialize(deserializer)?;
+    if value.as_object().is_some_and(serde_json::Map::is_empty) || value.is_null() {
+        return Ok(None);
+    }
+    DocumentMetadata::deserialize(value).map(Some).map_err(serde::de::Error::custom)
+}
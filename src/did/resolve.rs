@@ -7,13 +7,16 @@
 
 use std::str::FromStr;
 
-use anyhow::bail;
+use percent_encoding::percent_decode_str;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
 use super::document::{Document, Service, VerificationMethod};
-use super::{key, Method, web, webvh};
+use super::hashlink;
 use super::url::Url;
-use crate::IdentityResolver;
+use super::{ErrorCode, Registry, ResolutionOptions};
+use crate::core::{Kind, OneMany};
+use crate::{Error, IdentityResolver};
 
 /// Dereference a DID URL into a resource.
 ///
@@ -24,13 +27,15 @@ use crate::IdentityResolver;
 /// Will return an error if the DID URL cannot be parsed or the provided
 /// resolver fails to resolve the source DID document.
 ///
-/// Note that only URLs implying DID methods supported by this crate will
-/// survive parsing.
+/// URLs for methods not built into this crate parse but fail to dereference
+/// with [`ErrorCode::MethodNotSupported`]: use a [`Registry`] for those.
+///
+/// [`Registry`]: super::Registry
 pub async fn dereference(
-    did_url: &str, resolver: &impl IdentityResolver,
+    did_url: &str, options: Option<&ResolutionOptions>, resolver: &impl IdentityResolver,
 ) -> anyhow::Result<Resource> {
     let url = Url::from_str(did_url)?;
-    deref_url(&url, resolver).await
+    deref_url(&url, options, resolver).await
 }
 
 /// Dereference a structured DID URL into a resource.
@@ -45,52 +50,212 @@ pub async fn dereference(
 ///
 /// For self-describing DIDs a full document is never resolved. The
 /// `DidResolver` can be a no-op because it's `resolve` method is not called.
-/// For example, the `did:key` method can return a public key from the DID URL
-/// fragment.
+/// For example, the `did:key`, `did:jwk` and `did:peer` methods can return a
+/// public key from the DID URL fragment.
+///
+/// Resolution options (if any) are combined with the URL's query parameters
+/// and passed on to the resolver. Version selection is only supported by
+/// methods with a version history.
 ///
 /// # Errors
 /// Will return an error if the provided resolver fails to resolve the source
-/// DID document.
+/// DID document or the options cannot be honoured.
 ///
 /// Will also return an error if the resource is not found in the document. This
 /// includes cases that don't make sense, like asking a `did:key` for a service
 /// endpoint.
-pub async fn deref_url(url: &Url, resolver: &impl IdentityResolver) -> anyhow::Result<Resource> {
-    match url.method {
-        Method::Key => key::resolve(url),
-        Method::Web => {
-            let doc = web::resolve(url, resolver).await?;
-            document_resource(url, &doc)
-        }
-        Method::WebVh => {
-            let doc = webvh::resolve(url, resolver).await?;
-            document_resource(url, &doc)
-        }
-    }
+///
+/// Only methods built into this crate are supported, using
+/// [`Registry::default`]: use a [`Registry`] to dereference URLs for other
+/// methods.
+///
+/// [`Registry`]: super::Registry
+/// [`Registry::default`]: super::Registry::default
+pub async fn deref_url(
+    url: &Url, options: Option<&ResolutionOptions>, resolver: &impl IdentityResolver,
+) -> anyhow::Result<Resource> {
+    Registry::default().deref_url(url, options, resolver).await
 }
 
 /// Get a resource from a DID document.
 ///
-/// Uses the `Url` to infer the type of resource to return.
+/// Uses the `Url` to infer the type of resource to return. If the `Url` has
+/// a hashlink (`hl`) or the options an `expected_hash`, the document must
+/// match it. A `service` parameter
+/// selects a service and returns a URL constructed from its endpoints and the
+/// `relativeRef` parameter (see [`service_endpoints`]). A fragment is found
+/// using [`fragment_resource`].
 ///
 /// # Errors
-/// Will return an error if the resource is not found in the document.
-pub fn document_resource(url: &Url, doc: &Document) -> anyhow::Result<Resource> {
+/// Will return an error if the resource is not found in the document, the
+/// options conflict with the URL's query parameters or the document does not
+/// match the expected hash.
+pub fn document_resource(
+    url: &Url, options: Option<&ResolutionOptions>, doc: &Document,
+) -> anyhow::Result<Resource> {
+    hashlink::verify_document(url, options, doc)?;
     if let Some(query) = &url.query {
         if let Some(service_id) = &query.service {
-            if let Some(service) = doc.get_service(service_id) {
-                return Ok(Resource::Service(service.clone()));
-            }
-            bail!("service {service_id} not found in document");
+            return service_resource(url, doc, service_id);
+        }
+        if query.relative_ref.is_some() {
+            let detail = format!("relativeRef requires the service parameter: {url}");
+            return Err(Error::resolution(ErrorCode::InvalidDidUrl, detail).into());
         }
     }
-    if url.fragment.is_none() {
+    let Some(fragment) = &url.fragment else {
         return Ok(Resource::Document(doc.clone()));
+    };
+    let Some(resource) = fragment_resource(doc, fragment) else {
+        let detail = format!("resource {url} not found in document");
+        return Err(Error::resolution(ErrorCode::NotFound, detail).into());
+    };
+    Ok(resource)
+}
+
+// Dereference the `service` (and `relativeRef`) parameters of a DID URL to
+// the first URL constructed from the selected service's endpoints. A fragment
+// on the DID URL is carried over to the constructed URL.
+fn service_resource(url: &Url, doc: &Document, service_id: &str) -> anyhow::Result<Resource> {
+    let Some(service) = select_service(doc, service_id) else {
+        let detail = format!("service {service_id} not found in document");
+        return Err(Error::resolution(ErrorCode::NotFound, detail).into());
+    };
+    let relative_ref = url.query.as_ref().and_then(|q| q.relative_ref.as_deref());
+    let Some(endpoint) = service_endpoints(service, relative_ref)?.into_iter().next() else {
+        let detail = format!("service {service_id} has no endpoint URL");
+        return Err(Error::resolution(ErrorCode::NotFound, detail).into());
+    };
+
+    let Some(fragment) = &url.fragment else {
+        return Ok(Resource::Url(endpoint));
+    };
+    let mut endpoint = url::Url::parse(&endpoint)?;
+    if endpoint.fragment().is_none() {
+        endpoint.set_fragment(Some(fragment));
     }
-    if let Some(vm) = doc.get_verification_method(&url.to_string()) {
-        return Ok(Resource::VerificationMethod(vm.clone()));
+    Ok(Resource::Url(endpoint.to_string()))
+}
+
+// Select a service by its ID, either in full or by the fragment of the ID
+// (`files` selects `did:example:123#files` and `#files`).
+fn select_service<'a>(doc: &'a Document, service_id: &str) -> Option<&'a Service> {
+    doc.service
+        .as_ref()?
+        .iter()
+        .find(|s| s.id == service_id || s.id.rsplit_once('#').is_some_and(|(_, f)| f == service_id))
+}
+
+/// Construct the URLs identified by a service's endpoints, following the
+/// [service endpoint construction] algorithm of DID Resolution.
+///
+/// Endpoints may be a URL string, a map or a set of strings and maps. For a
+/// map, the `uri` (or `url`) entry is used when present, otherwise every
+/// value that is a URL. Values that are not absolute URLs are ignored.
+///
+/// If a (percent-encoded) relative reference is provided, it is resolved
+/// against each endpoint URL as described in
+/// [RFC 3986](https://www.rfc-editor.org/rfc/rfc3986#section-5.2).
+///
+/// The URLs are returned in the order the endpoints are listed.
+///
+/// [service endpoint construction]: https://w3c.github.io/did-resolution/#service-endpoint-construction
+///
+/// # Errors
+///
+/// Will return an error if the relative reference is not valid.
+pub fn service_endpoints(
+    service: &Service, relative_ref: Option<&str>,
+) -> anyhow::Result<Vec<String>> {
+    let relative_ref = match relative_ref {
+        Some(r) => Some(percent_decode_str(r).decode_utf8().map_err(|e| {
+            Error::resolution(ErrorCode::InvalidDidUrl, format!("invalid relativeRef: {e}"))
+        })?),
+        None => None,
+    };
+
+    let endpoints = match &service.service_endpoint {
+        OneMany::One(endpoint) => std::slice::from_ref(endpoint),
+        OneMany::Many(endpoints) => endpoints.as_slice(),
+    };
+    let mut candidates = vec![];
+    for endpoint in endpoints {
+        match endpoint {
+            Kind::String(s) => candidates.push(s.as_str()),
+            Kind::Object(value) => endpoint_values(value, &mut candidates),
+        }
     }
-    bail!("verification method {url} not found in document")
+
+    let mut urls = vec![];
+    for candidate in candidates {
+        let Ok(base) = url::Url::parse(candidate) else {
+            continue;
+        };
+        let Some(relative_ref) = &relative_ref else {
+            urls.push(candidate.to_string());
+            continue;
+        };
+        let resolved = base.join(relative_ref).map_err(|e| {
+            Error::resolution(ErrorCode::InvalidDidUrl, format!("invalid relativeRef: {e}"))
+        })?;
+        urls.push(resolved.to_string());
+    }
+    Ok(urls)
+}
+
+// Candidate endpoint URLs from a service endpoint value.
+fn endpoint_values<'a>(value: &'a Value, candidates: &mut Vec<&'a str>) {
+    match value {
+        Value::String(s) => candidates.push(s),
+        Value::Array(values) => values.iter().for_each(|v| endpoint_values(v, candidates)),
+        Value::Object(map) => {
+            if let Some(uri) = map.get("uri").or_else(|| map.get("url")) {
+                endpoint_values(uri, candidates);
+            } else {
+                map.values().for_each(|v| endpoint_values(v, candidates));
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Find the resource identified by a DID URL fragment in a DID document.
+///
+/// Every place a resource can be defined is searched, in order: the
+/// document's verification methods, verification methods embedded in its
+/// verification relationships (`authentication`, `assertionMethod`,
+/// `keyAgreement`, `capabilityInvocation` and `capabilityDelegation`) and its
+/// services.
+///
+/// Relative IDs (such as `#key-1`) are resolved against the document's ID
+/// before comparison, so both `#key-1` and `did:example:123#key-1` identify
+/// the fragment `key-1` in the document for `did:example:123`.
+#[must_use]
+pub fn fragment_resource(doc: &Document, fragment: &str) -> Option<Resource> {
+    let id = format!("{}#{fragment}", doc.id);
+    let matches = |candidate: &str| {
+        candidate.strip_prefix('#').map_or(candidate == id, |relative| relative == fragment)
+    };
+
+    if let Some(vm) = doc.verification_method.iter().flatten().find(|vm| matches(&vm.id)) {
+        return Some(Resource::VerificationMethod(vm.clone()));
+    }
+    let relationships = [
+        &doc.authentication,
+        &doc.assertion_method,
+        &doc.key_agreement,
+        &doc.capability_invocation,
+        &doc.capability_delegation,
+    ];
+    let embedded = relationships.into_iter().flatten().flatten().find_map(|kind| match kind {
+        Kind::Object(vm) if matches(&vm.id) => Some(vm),
+        _ => None,
+    });
+    if let Some(vm) = embedded {
+        return Some(Resource::VerificationMethod(vm.clone()));
+    }
+    let service = doc.service.iter().flatten().find(|s| matches(&s.id))?;
+    Some(Resource::Service(service.clone()))
 }
 
 /// Resource represents the DID document resource returned as a result of DID
@@ -106,6 +271,13 @@ pub enum Resource {
 
     /// `Service` resource.
     Service(Service),
+
+    /// A URL constructed from a service endpoint, when the DID URL has a
+    /// `service` parameter.
+    Url(String),
+
+    /// The content of a file identified by the DID URL path (`did:webvh`).
+    Content(Vec<u8>),
 }
 
 impl Default for Resource {
@@ -9,10 +9,9 @@ use std::{
     str::FromStr,
 };
 
-use anyhow::bail;
 use nom::{
     Err as NomErr, IResult, Parser,
-    bytes::complete::{is_not, tag, take, take_until},
+    bytes::complete::{is_not, tag, take_while1},
     combinator::{opt, rest},
     error::{Error as NomError, ErrorKind},
     sequence::{preceded, terminated},
@@ -20,15 +19,19 @@ use nom::{
 use serde::{Deserialize, Serialize};
 
 use super::Method;
+use crate::Error;
 
 /// Structure of a DID URL.
 #[derive(Clone, Debug, Default)]
 pub struct Url {
-    /// DID method.
+    /// DID method name.
     ///
-    /// Specification calls for a string. In our case this must be a method
-    /// supported by this crate so we map to an enum.
-    pub method: Method,
+    /// Any syntactically valid method name is accepted so URLs for methods
+    /// not built into this crate can be parsed. Compare with a [`Method`] to
+    /// check for a built-in method.
+    ///
+    /// [`Method`]: super::Method
+    pub method: String,
 
     /// Method-specific ID.
     ///
@@ -66,39 +69,26 @@ impl Display for Url {
             write!(f, "/{}", path.join("/"))?;
         }
         if let Some(query) = &self.query {
-            write!(f, "?")?;
-            let mut first = true;
+            let mut params = vec![];
             if let Some(service) = &query.service {
-                write!(f, "service={service}")?;
-                first = false;
+                params.push(format!("service={service}"));
             }
             if let Some(relative_ref) = &query.relative_ref {
-                if !first {
-                    write!(f, "&")?;
-                }
-                write!(f, "relativeRef={relative_ref}")?;
-                first = false;
+                params.push(format!("relativeRef={relative_ref}"));
             }
             if let Some(version_id) = &query.version_id {
-                if !first {
-                    write!(f, "&")?;
-                }
-                write!(f, "versionId={version_id}")?;
-                first = false;
+                params.push(format!("versionId={version_id}"));
             }
             if let Some(version_time) = &query.version_time {
-                if !first {
-                    write!(f, "&")?;
-                }
-                write!(f, "versionTime={version_time}")?;
-                first = false;
+                params.push(format!("versionTime={version_time}"));
+            }
+            if let Some(version_number) = &query.version_number {
+                params.push(format!("versionNumber={version_number}"));
             }
             if let Some(hashlink) = &query.hashlink {
-                if !first {
-                    write!(f, "&")?;
-                }
-                write!(f, "hl={hashlink}")?;
+                params.push(format!("hl={hashlink}"));
             }
+            write!(f, "?{}", params.join("&"))?;
         }
         if let Some(fragment) = &self.fragment {
             write!(f, "#{fragment}")?;
@@ -151,13 +141,12 @@ impl Url {
     /// Parse a string into a DID URL if possible.
     /// 
     /// # Errors
-    /// If any internal parsing fails, an error is returned.
+    /// If any internal parsing fails, an [`Error::Parse`] is returned.
     pub fn parse(s: &str) -> anyhow::Result<Self> {
         match parse_url(s) {
-            Ok((_, url)) => Ok(url),
-            Err(err) => {
-                bail!("failed to parse DID URL: {err}");
-            }
+            Ok(("", url)) => Ok(url),
+            Ok((next, _)) => Err(Error::Parse(format!("invalid DID URL at: {next}")).into()),
+            Err(err) => Err(Error::Parse(format!("failed to parse DID URL: {err}")).into()),
         }
     }
 }
@@ -191,6 +180,11 @@ pub struct QueryParams {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub version_time: Option<String>,
 
+    /// Identifies a version of a DID document by its version number, starting
+    /// at 1, for methods with numbered versions (such as `did:webvh`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version_number: Option<String>,
+
     /// A resource hash of the DID document to add integrity protection, as
     /// specified in [HASHLINK]. This parameter is non-normative.
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -202,12 +196,10 @@ fn scheme(input: &str) -> IResult<&str, &str> {
     terminated(tag("did"), tag(":")).parse(input)
 }
 
-fn method(input: &str) -> IResult<&str, Method> {
-    let (next, method) = take_until(":").parse(input)?;
-    let method = Method::from_str(method)
-        .map_err(|_| NomErr::Error(NomError::new(method, ErrorKind::TakeUntil)))?;
-    let (next, _) = take(1usize).parse(next)?;
-    Ok((next, method))
+// A method name is one or more lowercase letters or digits.
+fn method(input: &str) -> IResult<&str, &str> {
+    terminated(take_while1(|c: char| c.is_ascii_lowercase() || c.is_ascii_digit()), tag(":"))
+        .parse(input)
 }
 
 fn id(input: &str) -> IResult<&str, &str> {
@@ -220,6 +212,12 @@ fn port(input: &str) -> IResult<&str, u16> {
     Ok((next, p))
 }
 
+// A `did:x509` method-specific ID percent-encodes the values of its
+// policies, so may contain any percent-encoded character.
+fn x509_id(input: &str) -> IResult<&str, &str> {
+    is_not("/?#").parse(input)
+}
+
 fn path(input: &str) -> IResult<&str, Vec<String>> {
     let (next, p) = preceded(tag("/"), is_not("?#")).parse(input)?;
     Ok((next, p.split('/').map(std::string::ToString::to_string).collect()))
@@ -235,6 +233,7 @@ fn query(input: &str) -> IResult<&str, QueryParams> {
             "relativeRef" | "relative-ref" => params.relative_ref = Some(value.to_string()),
             "versionId" => params.version_id = Some(value.to_string()),
             "versionTime" => params.version_time = Some(value.to_string()),
+            "versionNumber" => params.version_number = Some(value.to_string()),
             "hl" => params.hashlink = Some(value.to_string()),
             _ => {}
         }
@@ -248,13 +247,20 @@ fn fragment(input: &str) -> IResult<&str, &str> {
 
 fn parse_url(input: &str) -> IResult<&str, Url> {
     let (next, _scheme) = scheme(input)?;
-    let (next, (parsed_method, parsed_id, parsed_port, parsed_path, parsed_query, parsed_fragment)) =
-        (method, id, opt(port), opt(path), opt(query), opt(fragment)).parse(next)?;
-    let id = parsed_port.map_or_else(|| parsed_id.to_string(), |p| format!("{parsed_id}%3A{p}"));
+    let (next, parsed_method) = method(next)?;
+    let (next, id) = if parsed_method == Method::X509.as_str() {
+        let (next, parsed_id) = x509_id(next)?;
+        (next, parsed_id.to_string())
+    } else {
+        let (next, (parsed_id, parsed_port)) = (id, opt(port)).parse(next)?;
+        (next, parsed_port.map_or_else(|| parsed_id.to_string(), |p| format!("{parsed_id}%3A{p}")))
+    };
+    let (next, (parsed_path, parsed_query, parsed_fragment)) =
+        (opt(path), opt(query), opt(fragment)).parse(next)?;
     Ok((
         next,
         Url {
-            method: parsed_method,
+            method: parsed_method.to_string(),
             id,
             path: parsed_path,
             query: parsed_query,
@@ -266,6 +272,7 @@ fn parse_url(input: &str) -> IResult<&str, Url> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::did::Method;
 
     #[test]
     fn simple_url() {
@@ -374,6 +381,24 @@ mod tests {
         assert_eq!(url.fragment, Some("key0".to_string()));
     }
 
+    #[test]
+    fn percent_encoded_id() {
+        let s = "did:x509:0:sha256:WE4P5dd8DnLHSkyHaIjhp4udlkF9LqoKwCvu9gl38jk::subject:O:Example%20Inc#key-1";
+        let url = Url::from_str(s).expect("should parse url");
+        assert_eq!(url.method, Method::X509);
+        assert_eq!(
+            url.id,
+            "0:sha256:WE4P5dd8DnLHSkyHaIjhp4udlkF9LqoKwCvu9gl38jk::subject:O:Example%20Inc"
+        );
+        assert_eq!(url.fragment, Some("key-1".to_string()));
+        assert_eq!(url.to_string(), s);
+    }
+
+    #[test]
+    fn invalid_port() {
+        assert!(Url::from_str("did:web:example.com%3Afoo").is_err());
+    }
+
     //--- Parser low level tests -----------------------------------------------
 
     #[test]
@@ -388,26 +413,39 @@ mod tests {
 
     #[test]
     fn test_parse_method() {
-        let s = "wibble:123456789abcdefghi#key-1";
+        let s = "Wibble:123456789abcdefghi#key-1";
+        assert!(method(s).is_err());
+        let s = "wibble123456789abcdefghi#key-1";
         assert!(method(s).is_err());
+        let s = "wibble:123456789abcdefghi#key-1";
+        let (next, m) = method(s).expect("should parse method");
+        assert_eq!(m, "wibble");
+        assert_eq!(next, "123456789abcdefghi#key-1");
         let s = "key:123456789abcdefghi#key-1";
         let (next, m) = method(s).expect("should parse method");
-        assert_eq!(m, Method::Key);
+        assert_eq!(m, "key");
         assert_eq!(next, "123456789abcdefghi#key-1");
         let s = "web:credibil.io:dVYzXm5MMzNAMiQodTFKRlpaXjRCKTBOeW5jTExWNzk#key0";
         let (next, m) = method(s).expect("should parse method");
-        assert_eq!(m, Method::Web);
+        assert_eq!(m, "web");
         assert_eq!(
             next,
             "credibil.io:dVYzXm5MMzNAMiQodTFKRlpaXjRCKTBOeW5jTExWNzk#key0"
         );
         let s = "webvh:QmaJp6pmb6RUk4oaDyWQcjeqYbvxsc3kvmHWPpz7B5JwDU:credibil.io%3A8080/path/to/resource?service=example&hl=hashlink#z6MkijyunEqPi7hzgJirb4tQLjztCPbJeeZvXEySuzbY6MLv";
         let (next, m) = method(s).expect("should parse method");
-        assert_eq!(m, Method::WebVh);
+        assert_eq!(m, "webvh");
         assert_eq!(
             next,
             "QmaJp6pmb6RUk4oaDyWQcjeqYbvxsc3kvmHWPpz7B5JwDU:credibil.io%3A8080/path/to/resource?service=example&hl=hashlink#z6MkijyunEqPi7hzgJirb4tQLjztCPbJeeZvXEySuzbY6MLv"
         );
+        let s = "jwk:eyJrdHkiOiJPS1AiLCJjcnYiOiJYMjU1MTkiLCJ1c2UiOiJlbmMiLCJ4IjoiM3A3YmZYdDl3YlRUVzJIQzdPUTFOei1EUThoYmVHZE5yZngtRkctSUswOCJ9#0";
+        let (next, m) = method(s).expect("should parse method");
+        assert_eq!(m, "jwk");
+        assert_eq!(
+            next,
+            "eyJrdHkiOiJPS1AiLCJjcnYiOiJYMjU1MTkiLCJ1c2UiOiJlbmMiLCJ4IjoiM3A3YmZYdDl3YlRUVzJIQzdPUTFOei1EUThoYmVHZE5yZngtRkctSUswOCJ9#0"
+        );
     }
 
     #[test]
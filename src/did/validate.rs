@@ -0,0 +1,276 @@
+//! # Conformance
+//!
+//! Checks a DID document against the data model rules of
+//! [DID Core](https://www.w3.org/TR/did-core/#core-properties), reporting
+//! each problem as a [`Finding`].
+//!
+//! The checks cover DID and DID URL syntax, the uniqueness of verification
+//! method and service IDs, references from verification relationships,
+//! service endpoint URIs and whether verification method key material
+//! matches the method's type.
+
+use std::collections::HashSet;
+use std::fmt::{self, Display, Formatter};
+
+use super::{Document, KeyCodec, KeyPurpose, MethodType, PublicKeyFormat, VerificationMethod};
+use crate::core::{Kind, OneMany};
+
+/// A way in which a DID document does not conform to DID Core.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Finding {
+    /// The kind of problem.
+    pub kind: FindingKind,
+
+    /// A JSON pointer to the offending value, for example
+    /// `/verificationMethod/0/controller`.
+    pub path: String,
+
+    /// A human-readable explanation of the problem.
+    pub detail: String,
+}
+
+impl Display for Finding {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.detail)
+    }
+}
+
+/// The kinds of conformance problem.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum FindingKind {
+    /// The document ID or a controller is not a valid DID.
+    InvalidDid,
+
+    /// A verification method or service ID, or a verification relationship
+    /// reference, is not a valid DID URL.
+    InvalidDidUrl,
+
+    /// More than one verification method or service has the same ID.
+    DuplicateId,
+
+    /// A verification relationship references a verification method that is
+    /// not in the document.
+    UnresolvedReference,
+
+    /// A service endpoint is not a valid URI.
+    InvalidServiceEndpoint,
+
+    /// A verification method's key material is not valid for its type.
+    KeyMismatch,
+}
+
+impl Document {
+    /// Check the document conforms to DID Core, returning any problems found.
+    /// An empty list means the document conforms.
+    ///
+    /// Relative IDs and references (such as `#key-1`) are resolved against
+    /// the document's ID.
+    #[must_use]
+    pub fn validate(&self) -> Vec<Finding> {
+        let mut findings = Findings::default();
+
+        if !is_did(&self.id) {
+            findings.push(FindingKind::InvalidDid, "/id", format!("{} is not a DID", self.id));
+        }
+        match &self.controller {
+            Some(OneMany::One(controller)) => findings.controller("/controller", controller),
+            Some(OneMany::Many(controllers)) => {
+                for (i, controller) in controllers.iter().enumerate() {
+                    findings.controller(&format!("/controller/{i}"), controller);
+                }
+            }
+            None => {}
+        }
+
+        let mut ids = HashSet::new();
+        for (i, vm) in self.verification_method.iter().flatten().enumerate() {
+            self.check_method(&format!("/verificationMethod/{i}"), vm, &mut ids, &mut findings);
+        }
+        for purpose in [
+            KeyPurpose::Authentication,
+            KeyPurpose::AssertionMethod,
+            KeyPurpose::KeyAgreement,
+            KeyPurpose::CapabilityInvocation,
+            KeyPurpose::CapabilityDelegation,
+        ] {
+            let relationship = self.relationship(&purpose).and_then(Option::as_ref);
+            for (i, kind) in relationship.into_iter().flatten().enumerate() {
+                let path = format!("/{purpose}/{i}");
+                match kind {
+                    Kind::Object(vm) => self.check_method(&path, vm, &mut ids, &mut findings),
+                    Kind::String(id) => {
+                        if !is_did_url(id) {
+                            let detail = format!("{id} is not a DID URL");
+                            findings.push(FindingKind::InvalidDidUrl, &path, detail);
+                        } else if self.find_verification_method(id).is_none() {
+                            let detail = format!("verification method {id} is not in the document");
+                            findings.push(FindingKind::UnresolvedReference, &path, detail);
+                        }
+                    }
+                }
+            }
+        }
+
+        for (i, service) in self.service.iter().flatten().enumerate() {
+            let path = format!("/service/{i}");
+            self.check_id(&format!("{path}/id"), &service.id, &mut ids, &mut findings);
+            let endpoints = match &service.service_endpoint {
+                OneMany::One(endpoint) => vec![endpoint],
+                OneMany::Many(endpoints) => endpoints.iter().collect(),
+            };
+            for (j, endpoint) in endpoints.into_iter().enumerate() {
+                let path = match &service.service_endpoint {
+                    OneMany::One(_) => format!("{path}/serviceEndpoint"),
+                    OneMany::Many(_) => format!("{path}/serviceEndpoint/{j}"),
+                };
+                let uri = match endpoint {
+                    Kind::String(uri) => Some(uri.as_str()),
+                    Kind::Object(map) => map.get("uri").and_then(|uri| uri.as_str()),
+                };
+                if let Some(uri) = uri.filter(|uri| url::Url::parse(uri).is_err()) {
+                    let detail = format!("{uri} is not a valid URI");
+                    findings.push(FindingKind::InvalidServiceEndpoint, &path, detail);
+                }
+            }
+        }
+
+        findings.0
+    }
+
+    // Check a verification method's ID, controller and key material.
+    fn check_method(
+        &self, path: &str, vm: &VerificationMethod, ids: &mut HashSet<String>,
+        findings: &mut Findings,
+    ) {
+        self.check_id(&format!("{path}/id"), &vm.id, ids, findings);
+        findings.controller(&format!("{path}/controller"), &vm.controller);
+        if let Err(detail) = check_key(vm) {
+            findings.push(FindingKind::KeyMismatch, path, detail);
+        }
+    }
+
+    // Check a verification method or service ID is a DID URL not used by any
+    // other verification method or service.
+    fn check_id(&self, path: &str, id: &str, ids: &mut HashSet<String>, findings: &mut Findings) {
+        if !is_did_url(id) {
+            findings.push(FindingKind::InvalidDidUrl, path, format!("{id} is not a DID URL"));
+        } else if !ids.insert(self.absolute_id(id)) {
+            findings.push(FindingKind::DuplicateId, path, format!("{id} is not unique"));
+        }
+    }
+}
+
+#[derive(Default)]
+struct Findings(Vec<Finding>);
+
+impl Findings {
+    fn push(&mut self, kind: FindingKind, path: &str, detail: String) {
+        self.0.push(Finding {
+            kind,
+            path: path.to_string(),
+            detail,
+        });
+    }
+
+    fn controller(&mut self, path: &str, controller: &str) {
+        if !is_did(controller) {
+            let detail = format!("controller {controller} is not a DID");
+            self.push(FindingKind::InvalidDid, path, detail);
+        }
+    }
+}
+
+// Check the key material is in a format, and of a key type, allowed for the
+// verification method type.
+fn check_key(vm: &VerificationMethod) -> Result<(), String> {
+    let (format, codec) = match vm.type_ {
+        MethodType::Multikey => (Some("publicKeyMultibase"), None),
+        MethodType::Ed25519VerificationKey2020 => {
+            (Some("publicKeyMultibase"), Some(KeyCodec::Ed25519))
+        }
+        MethodType::X25519KeyAgreementKey2020 => {
+            (Some("publicKeyMultibase"), Some(KeyCodec::X25519))
+        }
+        MethodType::JsonWebKey2020 | MethodType::JsonWebKey => (Some("publicKeyJwk"), None),
+        MethodType::Ed25519VerificationKey2018 => (None, Some(KeyCodec::Ed25519)),
+        MethodType::EcdsaSecp256k1VerificationKey2019 => (None, Some(KeyCodec::Secp256k1)),
+        MethodType::EcdsaSecp256k1RecoveryMethod2020
+        | MethodType::Ed25519PublicKeyBlake2bDigestSize20Base58CheckEncoded2021
+        | MethodType::P256PublicKeyBlake2bDigestSize20Base58CheckEncoded2021 => {
+            (Some("blockchainAccountId"), None)
+        }
+    };
+
+    let actual = match vm.key {
+        PublicKeyFormat::PublicKeyMultibase { .. } => "publicKeyMultibase",
+        PublicKeyFormat::PublicKeyJwk { .. } => "publicKeyJwk",
+        PublicKeyFormat::BlockchainAccountId { .. } => "blockchainAccountId",
+    };
+    let type_ = &vm.type_;
+    match format {
+        Some(format) if format != actual => {
+            return Err(format!("{type_} requires {format} key material, not {actual}"));
+        }
+        None if actual == "blockchainAccountId" => {
+            return Err(format!("{type_} requires a public key, not {actual}"));
+        }
+        _ => {}
+    }
+    if actual == "blockchainAccountId" {
+        return Ok(());
+    }
+
+    // JWKs for key types not supported by this crate are allowed.
+    let multikey = match vm.key.multikey() {
+        Ok(multikey) => multikey,
+        Err(_) if actual == "publicKeyJwk" && codec.is_none() => return Ok(()),
+        Err(e) => return Err(format!("invalid {actual} key material: {e}")),
+    };
+    match codec {
+        Some(codec) if multikey.codec() != codec => {
+            Err(format!("{type_} requires a {codec} key, not {}", multikey.codec()))
+        }
+        _ => Ok(()),
+    }
+}
+
+// Whether a string is a DID: `did:<method-name>:<method-specific-id>`.
+fn is_did(s: &str) -> bool {
+    let Some((method, id)) = s.strip_prefix("did:").and_then(|rest| rest.split_once(':')) else {
+        return false;
+    };
+    let id_chars = |segment: &str| {
+        let bytes = segment.as_bytes();
+        let mut i = 0;
+        while i < bytes.len() {
+            match bytes[i] {
+                b'%' => {
+                    let hex = bytes.get(i + 1..i + 3);
+                    if !hex.is_some_and(|h| h.iter().all(u8::is_ascii_hexdigit)) {
+                        return false;
+                    }
+                    i += 3;
+                }
+                b if b.is_ascii_alphanumeric() || matches!(b, b'.' | b'-' | b'_') => i += 1,
+                _ => return false,
+            }
+        }
+        true
+    };
+
+    !method.is_empty()
+        && method.bytes().all(|b| b.is_ascii_lowercase() || b.is_ascii_digit())
+        && !id.ends_with(':')
+        && !id.is_empty()
+        && id.split(':').all(id_chars)
+}
+
+// Whether a string is a DID URL, or a relative DID URL starting with `#`.
+fn is_did_url(s: &str) -> bool {
+    if let Some(fragment) = s.strip_prefix('#') {
+        return !fragment.is_empty();
+    }
+    let did = s.split(['/', '?', '#']).next().unwrap_or_default();
+    is_did(did)
+}
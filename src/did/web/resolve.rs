@@ -1,6 +1,11 @@
 //! Resolve a DID URL to a DID Document for the `did:web` method.
 
-use crate::{Identity, IdentityResolver, did::{Document, Url}};
+use anyhow::bail;
+
+use crate::{
+    Error, Identity, IdentityResolver,
+    did::{Document, ErrorCode, ResolutionOptions, Url},
+};
 
 impl Url {
     /// Convert a `did:web` URL to an HTTP URL pointing to the location of the
@@ -30,14 +35,27 @@ impl Url {
 
 /// Convert the structured URL to HTTP format and use the provided resolver to
 /// fetch a DID document.
-/// 
+///
+/// The resolution options are passed on to the resolver. `did:web` has no
+/// version history so version selection is not supported.
+///
 /// # Errors
-/// If the URL cannot be converted to an HTTP format or if the resolver fails an
-/// error is returned.
-pub async fn resolve(url: &Url, resolver: &impl IdentityResolver) -> anyhow::Result<Document> {
+/// If the URL cannot be converted to an HTTP format, the options select a
+/// version or if the resolver fails an error is returned.
+pub async fn resolve(
+    url: &Url, options: Option<&ResolutionOptions>, resolver: &impl IdentityResolver,
+) -> anyhow::Result<Document> {
+    let options = ResolutionOptions::merge(options, url.query.as_ref())?;
+    if options.selects_version() {
+        let detail = "did:web does not support version selection";
+        return Err(Error::resolution(ErrorCode::InvalidOptions, detail).into());
+    }
     let http_url = url.to_web_http();
-    let id = resolver.resolve(&http_url).await?;
+    let id = resolver.resolve_with_options(&http_url, &options).await?;
     match id {
         Identity::DidDocument(doc) => Ok(doc),
+        Identity::PlcLog(_) | Identity::X509Chain(_) | Identity::Content(_) => {
+            bail!("resolver did not return a DID document")
+        }
     }
 }
@@ -8,6 +8,8 @@
 
 mod create;
 mod deactivate;
+mod fetch;
+mod jsonl;
 mod resolve;
 mod update;
 mod url;
@@ -21,12 +23,14 @@ use serde_json::Value;
 use sha2::Digest;
 use uuid::Uuid;
 
-use crate::{Key, SignerExt};
+use crate::{Error, Key, LogErrorKind, SignerExt};
 use crate::did::Document;
 use crate::proof::w3c::Proof;
 
 pub use create::{CreateBuilder, CreateResult};
 pub use deactivate::{DeactivateBuilder, DeactivateResult};
+pub use fetch::{Fetcher, Resolver, resolve_http};
+pub use jsonl::{parse_log, read_log, read_log_stream, to_jsonl, write_log};
 pub use resolve::*;
 pub use update::{UpdateBuilder, UpdateResult};
 pub use url::*;
@@ -90,19 +94,22 @@ impl DidLogEntry {
     ///
     /// # Errors
     ///
-    /// Will return an error if the version ID has an unexpected format or if
-    /// the hash does not match the hash computed from the previous log entry.
+    /// Will return an [`Error::LogVerification`] if the version ID has an
+    /// unexpected format or if the hash does not match the hash computed from
+    /// the previous log entry.
     pub fn verify_hash(&self, previous_version: &str) -> anyhow::Result<()> {
         let parts = self.version_id.split('-').collect::<Vec<&str>>();
         if parts.len() != 2 {
-            return Err(anyhow::anyhow!("log entry version id has an unexpected format"));
+            let detail = "log entry version id has an unexpected format";
+            return Err(Error::log(LogErrorKind::VersionId, &self.version_id, detail).into());
         }
         let mut prev_version_entry = self.clone();
         prev_version_entry.proof = Vec::new();
         prev_version_entry.version_id = previous_version.to_string();
         let hash = prev_version_entry.hash()?;
         if hash != parts[1] {
-            return Err(anyhow::anyhow!("log entry hash does not match version id"));
+            let detail = "log entry hash does not match version id";
+            return Err(Error::log(LogErrorKind::Hash, &self.version_id, detail).into());
         }
         Ok(())
     }
@@ -144,7 +151,7 @@ impl DidLogEntry {
             id: Some(format!("urn:uuid:{}", Uuid::new_v4())),
             type_: "DataIntegrityProof".to_string(),
             cryptosuite: Some("eddsa-jcs-2022".to_string()),
-            verification_method: key_id.to_string(),
+            verification_method: key_id.clone(),
             created: Some(Utc::now()),
             proof_purpose: "assertionMethod".to_string(),
             ..Proof::default()
@@ -232,8 +239,10 @@ pub struct WitnessWeight {
 
 /// Entry in the `did-witness.json` file.
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct WitnessEntry {
     /// Version ID of the DID log entry to which the witnesses' proof applies.
+    #[serde(alias = "version_id")]
     pub version_id: String,
 
     /// Witnesses' proof of the DID log entry using the `eddsa-jcs-2022`
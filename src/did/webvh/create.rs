@@ -1,14 +1,12 @@
 //! Create operation for the `did:webvh` method.
 
-use anyhow::bail;
 use chrono::Utc;
 use multibase::Base;
 use serde::{Deserialize, Serialize};
 use sha2::Digest;
 
-use crate::SignerExt;
-use crate::core::Kind;
-use crate::did::{BASE_CONTEXT, Document};
+use crate::{Error, SignerExt};
+use crate::did::Document;
 
 use super::{
     DidLogEntry, METHOD, Parameters, SCID_PLACEHOLDER, VERSION, Witness, 
@@ -25,6 +23,7 @@ pub struct CreateBuilder<U, S, D> {
     next_key_hashes: Option<Vec<String>>,
     witness: Option<Witness>,
     ttl: u64,
+    conformant: bool,
 
     update_keys: U,
     signer: S,
@@ -66,6 +65,7 @@ impl CreateBuilder<NoUpdateKeys, NoSigner, NoDocument> {
             next_key_hashes: None,
             witness: None,
             ttl: 0,
+            conformant: false,
 
             update_keys: NoUpdateKeys,
             signer: NoSigner,
@@ -91,18 +91,14 @@ impl CreateBuilder<NoUpdateKeys, NoSigner, NoDocument> {
         // The document ID must resemble a `did:webvh:{SCID}:<host_and_path>`
         // string.
         if !document.id.starts_with(&format!("did:{METHOD}:{SCID_PLACEHOLDER}")) {
-            bail!("document ID must start with 'did:{METHOD}:{SCID_PLACEHOLDER}'");
+            let detail = format!("document ID must start with 'did:{METHOD}:{SCID_PLACEHOLDER}'");
+            return Err(Error::Builder(detail).into());
         }
 
         let mut doc = document.clone();
 
-        // Ensure we have the base context on the document for this DID method.
-        for ctx in &BASE_CONTEXT {
-            let c = Kind::String((*ctx).to_string());
-            if !document.context.contains(&c) {
-                doc.context.push(c);
-            }
-        }
+        // Ensure the context matches the document's content.
+        doc.update_context();
 
         Ok(CreateBuilder {
             method: self.method.clone(),
@@ -111,6 +107,7 @@ impl CreateBuilder<NoUpdateKeys, NoSigner, NoDocument> {
             next_key_hashes: self.next_key_hashes.clone(),
             witness: self.witness.clone(),
             ttl: self.ttl,
+            conformant: self.conformant,
 
             update_keys: NoUpdateKeys,
             signer: NoSigner,
@@ -138,7 +135,7 @@ impl CreateBuilder<NoUpdateKeys, NoSigner, WithDocument> {
     ) -> anyhow::Result<CreateBuilder<WithUpdateKeys, NoSigner, WithDocument>> {
         // The update keys cannot be empty.
         if update_keys.is_empty() {
-            bail!("update keys must not be empty.");
+            return Err(Error::Builder("update keys must not be empty.".to_string()).into());
         }
 
         Ok(CreateBuilder {
@@ -148,6 +145,7 @@ impl CreateBuilder<NoUpdateKeys, NoSigner, WithDocument> {
             next_key_hashes: self.next_key_hashes.clone(),
             witness: self.witness.clone(),
             ttl: self.ttl,
+            conformant: self.conformant,
 
             update_keys: WithUpdateKeys(
                 update_keys.iter().map(std::string::ToString::to_string).collect(),
@@ -171,6 +169,7 @@ impl CreateBuilder<WithUpdateKeys, NoSigner, WithDocument> {
             next_key_hashes: self.next_key_hashes,
             witness: self.witness,
             ttl: self.ttl,
+            conformant: self.conformant,
 
             update_keys: self.update_keys,
             signer: WithSigner(signer),
@@ -198,6 +197,14 @@ impl<U, S, D> CreateBuilder<U, S, D> {
         self
     }
 
+    /// Refuse to build a log entry for a document that does not conform to
+    /// DID Core (see [`Document::validate`]). Defaults to not checked.
+    #[must_use]
+    pub const fn conformant(mut self, conformant: bool) -> Self {
+        self.conformant = conformant;
+        self
+    }
+
     /// Add a next key hash to the list of next key hashes if required.
     ///
     /// Pass in the multibase-encoded public key to be used as the next key and
@@ -244,7 +251,9 @@ impl<S: SignerExt> CreateBuilder<WithUpdateKeys, WithSigner<'_, S>, WithDocument
     /// Will fail if secondary algorithms fail such as generating a hash of the
     /// log entry to calculate the `SCID` or version ID, or failing to replace
     /// the placeholder `SCID` with the calculated one. Will also fail if the
-    /// provided signer fails to sign the log entry.
+    /// provided signer fails to sign the log entry, or with an
+    /// [`Error::Conformance`] if conformance is required and the document
+    /// does not conform.
     pub async fn build(&self) -> anyhow::Result<CreateResult> {
         // Construct preliminary parameters.
         let params = Parameters {
@@ -278,6 +287,12 @@ impl<S: SignerExt> CreateBuilder<WithUpdateKeys, WithSigner<'_, S>, WithDocument
         let initial_string = serde_json::to_string(&initial_log_entry)?;
         let replaced = initial_string.replace(SCID_PLACEHOLDER, &initial_hash);
         let mut entry = serde_json::from_str::<DidLogEntry>(&replaced)?;
+        if self.conformant {
+            let findings = entry.state.validate();
+            if !findings.is_empty() {
+                return Err(Error::Conformance(findings).into());
+            }
+        }
 
         // Construct a log entry version.
         let entry_hash = entry.hash()?;
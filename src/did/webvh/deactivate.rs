@@ -1,14 +1,13 @@
 //! Deactivate (revoke) operation for the `did:webvh` method.
 //!
 
-use anyhow::bail;
 use chrono::Utc;
 use credibil_se::Signer;
 use multibase::Base;
 use serde::{Deserialize, Serialize};
 use sha2::Digest;
 
-use crate::SignerExt;
+use crate::{Error, LogErrorKind, SignerExt};
 use crate::did::Document;
 
 use super::{DidLogEntry, Witness};
@@ -42,7 +41,7 @@ impl DeactivateBuilder<WithoutSigner> {
     /// Will fail if the log entries are not populated.
     pub fn from(log: &[DidLogEntry]) -> anyhow::Result<Self> {
         let Some(last_entry) = log.last() else {
-            bail!("log must not be empty.");
+            return Err(Error::Builder("log must not be empty.".to_string()).into());
         };
         Ok(Self {
             update_keys: last_entry.parameters.update_keys.clone(),
@@ -92,7 +91,8 @@ impl DeactivateBuilder<WithoutSigner> {
                 let digest = sha2::Sha256::digest(new_key.as_bytes());
                 let hash = multibase::encode(Base::Base58Btc, digest.as_slice());
                 if !next_key_hashes.contains(&hash) {
-                    bail!("new update keys do not match current next key hashes.");
+                    let detail = "new update keys do not match current next key hashes.";
+                    return Err(Error::Builder(detail.to_string()).into());
                 }
             }
         }
@@ -174,7 +174,7 @@ impl<S: SignerExt> DeactivateBuilder<WithSigner<'_, S>> {
     pub async fn build(&self) -> anyhow::Result<DeactivateResult> {
         let mut log = self.log.clone();
         let Some(last_entry) = log.last() else {
-            bail!("log must not be empty.");
+            return Err(Error::Builder("log must not be empty.".to_string()).into());
         };
         let mut last_entry = last_entry.clone();
 
@@ -195,7 +195,9 @@ impl<S: SignerExt> DeactivateBuilder<WithSigner<'_, S>> {
             let entry_hash = entry.hash()?;
             let parts = last_entry.version_id.split('-').collect::<Vec<&str>>();
             if parts.len() != 2 {
-                bail!("log entry version ID has an unexpected format");
+                let detail = "log entry version ID has an unexpected format";
+                let version_id = &last_entry.version_id;
+                return Err(Error::log(LogErrorKind::VersionId, version_id, detail).into());
             }
             let mut version_number = parts[0].parse::<u64>()?;
             version_number += 1;
@@ -226,7 +228,8 @@ impl<S: SignerExt> DeactivateBuilder<WithSigner<'_, S>> {
         let entry_hash = entry.hash()?;
         let parts = last_entry.version_id.split('-').collect::<Vec<&str>>();
         if parts.len() != 2 {
-            bail!("unexpected version ID format");
+            let detail = "unexpected version ID format";
+            return Err(Error::log(LogErrorKind::VersionId, &last_entry.version_id, detail).into());
         }
         let mut version_number = parts[0].parse::<u64>()?;
         version_number += 1;
@@ -0,0 +1,150 @@
+//! # DID Web with Verifiable History Fetching
+//!
+//! End-to-end resolution of a `did:webvh` DID from the files published by
+//! the DID controller: the `did.jsonl` log and, for DIDs with witnesses, the
+//! `did-witness.json` witness proofs.
+//!
+//! Files are fetched using a [`Fetcher`] supplied by the caller, so any HTTP
+//! client (or a local cache) can be used. The log is then verified using
+//! [`resolve_log`] before the DID document is returned.
+
+use std::future::Future;
+use std::str::FromStr;
+
+use super::resolve::{check_version, path_url};
+use super::{METHOD, WitnessEntry, add_implicit_services, parse_log, resolve_log};
+use crate::did::hashlink::verify_content;
+use crate::did::{
+    BoxFuture, DidMethod, Document, ErrorCode, Method, ResolutionOptions, Resource, Url,
+    document_resource,
+};
+use crate::{Error, IdentityResolver, LogErrorKind};
+
+/// Fetches the files published for a `did:webvh` DID.
+pub trait Fetcher: Send + Sync {
+    /// Fetch the content of the file at the given HTTPS URL.
+    ///
+    /// Returns `None` if there is no file at the URL (for example, the server
+    /// responds with `404 Not Found`).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be fetched.
+    fn fetch(&self, url: &str) -> impl Future<Output = anyhow::Result<Option<Vec<u8>>>> + Send;
+}
+
+/// Resolve a `did:webvh` DID URL to a verified DID document.
+///
+/// The DID's `did.jsonl` log is fetched and parsed, along with its
+/// `did-witness.json` file if any log entry declares witnesses. The log is
+/// verified with [`resolve_log`], selecting the version requested by the
+/// resolution options and the URL's query parameters (see
+/// [`ResolutionOptions::merge`]). The resolved document must be for the
+/// requested DID, and is returned with the implicit `#files` and `#whois`
+/// services (see [`add_implicit_services`]).
+///
+/// # Errors
+///
+/// Will fail if the DID URL is invalid, the options conflict with the query
+/// parameters, the files cannot be fetched or parsed, the log cannot be
+/// verified or the requested version is not in the log.
+pub async fn resolve_http(
+    url: &Url, options: Option<&ResolutionOptions>, fetcher: &impl Fetcher,
+) -> anyhow::Result<Document> {
+    if url.method != Method::WebVh {
+        return Err(Error::Parse(format!("DID is not a valid did:webvh: {url}")).into());
+    }
+    let options = ResolutionOptions::merge(options, url.query.as_ref())?;
+
+    let did = url.did();
+    let log_url = Url::from_str(&did)?.to_webvh_http()?;
+    let Some(jsonl) = fetcher.fetch(&log_url).await? else {
+        let detail = format!("no DID log at {log_url}");
+        return Err(Error::resolution(ErrorCode::NotFound, detail).into());
+    };
+    let log = parse_log(&jsonl)?;
+
+    // Witness proofs are only published for DIDs with witnesses.
+    let witness_proofs = if log.iter().any(|entry| entry.parameters.witness.is_some()) {
+        let witness_url = log_url.replace("/did.jsonl", "/did-witness.json");
+        let Some(json) = fetcher.fetch(&witness_url).await? else {
+            return Err(Error::LogVerification {
+                kind: LogErrorKind::Witness,
+                version_id: None,
+                detail: format!("no witness proofs at {witness_url}"),
+            }
+            .into());
+        };
+        Some(serde_json::from_slice::<Vec<WitnessEntry>>(&json)?)
+    } else {
+        None
+    };
+
+    let mut doc =
+        resolve_log(&log, witness_proofs.as_deref(), Some(&options.query_params())).await?;
+    if doc.id != did {
+        let version_id = doc
+            .did_document_metadata
+            .as_ref()
+            .and_then(|md| md.additional.as_ref())
+            .and_then(|a| a.get("versionId"))
+            .and_then(|v| v.as_str())
+            .map(ToString::to_string);
+        return Err(Error::LogVerification {
+            kind: LogErrorKind::Did,
+            version_id,
+            detail: format!("resolved DID document {} does not match {did}", doc.id),
+        }
+        .into());
+    }
+    check_version(&doc, &options)?;
+    add_implicit_services(&mut doc)?;
+    Ok(doc)
+}
+
+/// A `did:webvh` method that fetches and verifies the DID log itself rather
+/// than relying on the identity resolver.
+///
+/// Register it to replace the built-in `did:webvh` method (see
+/// [`crate::did::Registry`]).
+pub struct Resolver<F: Fetcher> {
+    fetcher: F,
+}
+
+impl<F: Fetcher> Resolver<F> {
+    /// Create a new `did:webvh` method using the given fetcher.
+    pub const fn new(fetcher: F) -> Self {
+        Self { fetcher }
+    }
+}
+
+impl<F: Fetcher, R: IdentityResolver> DidMethod<R> for Resolver<F> {
+    fn name(&self) -> &str {
+        METHOD
+    }
+
+    fn resolve<'a>(
+        &'a self, url: &'a Url, options: Option<&'a ResolutionOptions>, _: &'a R,
+    ) -> BoxFuture<'a, anyhow::Result<Document>> {
+        Box::pin(resolve_http(url, options, &self.fetcher))
+    }
+
+    // DID URL paths are dereferenced to the content of the file fetched from
+    // the URL constructed from the document's `#files` or `#whois` service.
+    fn dereference<'a>(
+        &'a self, url: &'a Url, options: Option<&'a ResolutionOptions>, _: &'a R,
+    ) -> BoxFuture<'a, anyhow::Result<Resource>> {
+        Box::pin(async move {
+            let doc = resolve_http(url, options, &self.fetcher).await?;
+            let Some(http_url) = path_url(url, &doc)? else {
+                return document_resource(url, options, &doc);
+            };
+            let Some(content) = self.fetcher.fetch(&http_url).await? else {
+                let detail = format!("no file at {http_url}");
+                return Err(Error::resolution(ErrorCode::NotFound, detail).into());
+            };
+            verify_content(url, options, &content)?;
+            Ok(Resource::Content(content))
+        })
+    }
+}
@@ -0,0 +1,138 @@
+//! # DID Log Files
+//!
+//! Reading and writing the `did.jsonl` DID log file. The log is stored as
+//! [JSON Lines](https://jsonlines.org): one log entry per line, in order.
+//!
+//! When reading, blank lines are skipped and a trailing carriage return is
+//! removed from each line, so logs written with `CRLF` line endings can be
+//! read. Parse failures are reported as an [`Error::LogParse`] with the line
+//! number and, where it can be read, the version ID of the entry.
+//!
+//! When writing, each entry is serialized using the JSON Canonicalization
+//! Scheme (the same serialization used for the entry hash) and terminated by
+//! a line feed.
+
+use std::io::{BufRead, BufReader, Read, Write};
+
+use futures_util::{Stream, StreamExt};
+use serde_json::Value;
+
+use super::{DidLog, DidLogEntry};
+use crate::Error;
+
+/// Parse a DID log from the contents of a `did.jsonl` file.
+///
+/// # Errors
+///
+/// Will fail with an [`Error::LogParse`] if a line is not a valid log entry.
+pub fn parse_log(jsonl: &[u8]) -> anyhow::Result<DidLog> {
+    let mut parser = LineParser::default();
+    for line in jsonl.split(|b| *b == b'\n') {
+        parser.push(line)?;
+    }
+    Ok(parser.log)
+}
+
+/// Read a DID log from a reader of `did.jsonl` content.
+///
+/// # Errors
+///
+/// Will fail if the content cannot be read or with an [`Error::LogParse`] if
+/// a line is not a valid log entry.
+pub fn read_log(reader: impl Read) -> anyhow::Result<DidLog> {
+    let mut reader = BufReader::new(reader);
+    let mut parser = LineParser::default();
+    let mut line = Vec::new();
+    while reader.read_until(b'\n', &mut line)? > 0 {
+        parser.push(line.strip_suffix(b"\n").unwrap_or(&line))?;
+        line.clear();
+    }
+    Ok(parser.log)
+}
+
+/// Read a DID log from a stream of `did.jsonl` content, such as the body of
+/// an HTTP response. Chunks need not be aligned with lines.
+///
+/// # Errors
+///
+/// Will fail if the stream returns an error or with an [`Error::LogParse`] if
+/// a line is not a valid log entry.
+pub async fn read_log_stream<S, B, E>(stream: S) -> anyhow::Result<DidLog>
+where
+    S: Stream<Item = Result<B, E>>,
+    B: AsRef<[u8]>,
+    E: Into<anyhow::Error>,
+{
+    let mut stream = std::pin::pin!(stream);
+    let mut parser = LineParser::default();
+    let mut buffer = Vec::new();
+    while let Some(chunk) = stream.next().await {
+        buffer.extend_from_slice(chunk.map_err(Into::into)?.as_ref());
+        while let Some(pos) = buffer.iter().position(|b| *b == b'\n') {
+            let rest = buffer.split_off(pos + 1);
+            parser.push(&buffer[..pos])?;
+            buffer = rest;
+        }
+    }
+    parser.push(&buffer)?;
+    Ok(parser.log)
+}
+
+/// Serialize a DID log to the contents of a `did.jsonl` file.
+///
+/// # Errors
+///
+/// Will fail if a log entry cannot be serialized.
+pub fn to_jsonl(log: &[DidLogEntry]) -> anyhow::Result<String> {
+    let mut jsonl = String::new();
+    for entry in log {
+        jsonl.push_str(&serde_json_canonicalizer::to_string(entry)?);
+        jsonl.push('\n');
+    }
+    Ok(jsonl)
+}
+
+/// Write a DID log as `did.jsonl` content.
+///
+/// # Errors
+///
+/// Will fail if a log entry cannot be serialized or the content cannot be
+/// written.
+pub fn write_log(log: &[DidLogEntry], mut writer: impl Write) -> anyhow::Result<()> {
+    writer.write_all(to_jsonl(log)?.as_bytes())?;
+    Ok(())
+}
+
+// Parses log entries line by line, keeping count of the lines read.
+#[derive(Default)]
+struct LineParser {
+    line: usize,
+    log: DidLog,
+}
+
+impl LineParser {
+    fn push(&mut self, line: &[u8]) -> anyhow::Result<()> {
+        self.line += 1;
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        if line.iter().all(u8::is_ascii_whitespace) {
+            return Ok(());
+        }
+
+        // Read the version ID first so it can be reported if the entry is
+        // invalid.
+        let value = serde_json::from_slice::<Value>(line).map_err(|e| self.error(None, &e))?;
+        let version_id = value.get("versionId").and_then(Value::as_str).map(ToString::to_string);
+        let entry =
+            serde_json::from_value::<DidLogEntry>(value).map_err(|e| self.error(version_id, &e))?;
+        self.log.push(entry);
+        Ok(())
+    }
+
+    fn error(&self, version_id: Option<String>, err: &serde_json::Error) -> Error {
+        Error::LogParse {
+            line: self.line,
+            version_id,
+            detail: err.to_string(),
+        }
+    }
+}
@@ -4,25 +4,38 @@
 //!
 //! See: <https://identity.foundation/didwebvh/next/>
 
+use std::str::FromStr;
 use std::vec;
 
 use anyhow::bail;
 use chrono::{DateTime, Utc};
 use multibase::Base;
+use serde_json::Value;
 use sha2::Digest;
 
 use super::{
-    DidLogEntry, SCID_PLACEHOLDER, WitnessEntry,
+    DidLogEntry, Parameters, SCID_PLACEHOLDER, WitnessEntry,
     verify::{verify_proofs, verify_witness},
 };
-use crate::did::{Document, DocumentMetadataBuilder, QueryParams, Url};
-use crate::{Identity, IdentityResolver};
+use crate::core::{Kind, OneMany};
+use crate::did::context::LINKED_VP_CONTEXT;
+use crate::did::hashlink::verify_content;
+use crate::did::{
+    Document, DocumentMetadataBuilder, ErrorCode, QueryParams, ResolutionOptions, Resource,
+    Service, Url, document_resource,
+};
+use crate::{Error, Identity, IdentityResolver, LogErrorKind};
 
 impl Url {
     /// Convert a `did:webvh` URL to an HTTP URL pointing to the location of the
     /// DID list document (default) or another location where the root path is
     /// a conversion from the DID to an HTTP URL.
     ///
+    /// A DID URL path maps to a file relative to the DID's location, except
+    /// `/whois`, which maps to the `whois.vp` file alongside the DID log.
+    /// These are the default locations of the implicit `#files` and `#whois`
+    /// services (see [`add_implicit_services`]).
+    ///
     /// # Errors
     ///
     /// Will fail if the DID URL is invalid.
@@ -30,13 +43,28 @@ impl Url {
     /// <https://identity.foundation/didwebvh/#the-did-to-https-transformation>
     ///
     pub fn to_webvh_http(&self) -> anyhow::Result<String> {
+        // 7. Append `/did.jsonl` (default) or the specified file sub-path to
+        // the URL to complete it.
+        let path = self.path.as_ref().map(|p| p.join("/")).unwrap_or_default();
+        let url = match path.as_str() {
+            "" => format!("{}/did.jsonl", self.webvh_base(true)?),
+            "whois" => format!("{}/whois.vp", self.webvh_base(true)?),
+            _ => format!("{}/{path}", self.webvh_base(false)?),
+        };
+        Ok(url)
+    }
+
+    // The HTTPS URL of the DID's location, without a trailing slash. When
+    // `well_known` is set, DIDs without a path are located in the
+    // `/.well-known` directory.
+    fn webvh_base(&self, well_known: bool) -> anyhow::Result<String> {
         // 1. Remove the literal `did:webvh:` prefix from the DID URL.
         let scid_and_fqdn = self.id.clone();
 
         // 2. Remove the `SCID` by removing the text up to and including the
         // first `:` character.
         let Some(fqdn) = scid_and_fqdn.split_once(':').map(|x| x.1) else {
-            bail!("DID is not a valid did:webvh - no SCID");
+            return Err(Error::Parse("DID is not a valid did:webvh - no SCID".to_string()).into());
         };
 
         // 3. Replace `:` with `/` in the domain part of the identifier to obtain
@@ -44,7 +72,7 @@ impl Url {
         let mut domain = fqdn.replace(':', "/");
 
         // 4. If there is no optional path, append `/.well-known` to the URL.
-        if !fqdn.contains(':') {
+        if well_known && !fqdn.contains(':') {
             domain.push_str("/.well-known");
         }
 
@@ -52,20 +80,56 @@ impl Url {
         let domain = domain.replace("%3A", ":");
 
         // 6. Prepend `https://` to the domain to generate the URL.
-        let url = format!("https://{domain}");
+        Ok(format!("https://{domain}"))
+    }
+}
 
-        // 7. Append `/did.jsonl` (default) or the specified file sub-path to
-        // the URL to complete it.
-        let mut fp = "/did.jsonl".to_string();
-        if let Some(path) = &self.path {
-            if !path.is_empty() {
-                fp = path.join("/");
-            }
-        }
-        let url = format!("{url}{fp}");
+/// Add the implicit `#files` and `#whois` services defined by the `did:webvh`
+/// specification to a resolved DID document, unless the document defines
+/// services with the same IDs.
+///
+/// The `#files` service locates files published relative to the DID's
+/// location and is used to dereference DID URL paths. The `#whois` service
+/// locates the DID controller's `whois.vp` Linked Verifiable Presentation.
+///
+/// # Errors
+///
+/// Will fail if the document ID is not a valid `did:webvh` DID.
+///
+/// <https://identity.foundation/didwebvh/#did-url-resolution>
+pub fn add_implicit_services(doc: &mut Document) -> anyhow::Result<()> {
+    let url = Url::from_str(&doc.id)?;
+    let defines = |name: &str| {
+        doc.service.iter().flatten().any(|s| s.id.rsplit_once('#').is_some_and(|(_, f)| f == name))
+    };
+    let files = !defines("files");
+    let whois = !defines("whois");
 
-        Ok(url)
+    let services = doc.service.get_or_insert_with(Vec::new);
+    if files {
+        services.push(Service {
+            id: "#files".to_string(),
+            type_: "relativeRef".to_string(),
+            service_endpoint: OneMany::One(Kind::String(format!("{}/", url.webvh_base(false)?))),
+            ..Service::default()
+        });
+    }
+    if whois {
+        services.push(Service {
+            id: "#whois".to_string(),
+            type_: "LinkedVerifiablePresentation".to_string(),
+            service_endpoint: OneMany::One(Kind::String(format!(
+                "{}/whois.vp",
+                url.webvh_base(true)?
+            ))),
+            ..Service::default()
+        });
+        let context = Kind::String(LINKED_VP_CONTEXT.to_string());
+        if !doc.context.contains(&context) {
+            doc.context.push(context);
+        }
     }
+    Ok(())
 }
 
 /// Resolve a `did:webvh` DID URL to a DID document.
@@ -74,21 +138,143 @@ impl Url {
 /// document. See further functions in this implementation to help with
 /// resolution steps.
 ///
+/// The resolution options are combined with the URL's query parameters (see
+/// [`ResolutionOptions::merge`]) and passed on to the resolver, which should
+/// select the requested version when resolving the log (see
+/// [`ResolutionOptions::query_params`] and [`resolve_log`]). A document
+/// returned for a different version than the one requested is rejected.
+/// The implicit `#files` and `#whois` services are added to the document
+/// (see [`add_implicit_services`]).
+///
+/// The resolver is trusted to have verified the log. To fetch and verify the
+/// log as part of resolution, use [`resolve_http`](super::resolve_http).
+///
 /// # Errors
 ///
-/// Will fail if the DID URL is invalid or the provider returns an error.
-pub async fn resolve(url: &Url, resolver: &impl IdentityResolver) -> anyhow::Result<Document> {
+/// Will fail if the DID URL is invalid, the options conflict with the query
+/// parameters, the provider returns an error or the document is not the
+/// requested version.
+pub async fn resolve(
+    url: &Url, options: Option<&ResolutionOptions>, resolver: &impl IdentityResolver,
+) -> anyhow::Result<Document> {
+    let options = ResolutionOptions::merge(options, url.query.as_ref())?;
+
     // Generate the URL to fetch the DID list (log) document.
-    let http_url = url.to_webvh_http()?;
+    let http_url = Url::from_str(&url.did())?.to_webvh_http()?;
 
     // Perform an HTTP GET request to the URL for the DID log document.
     //
     // The client can use helper methods to unpack the `JSONL` file and extract
     // the DID document.
-    let identity = resolver.resolve(&http_url).await?;
-    match identity {
-        Identity::DidDocument(doc) => Ok(doc),
+    let identity = resolver.resolve_with_options(&http_url, &options).await?;
+    let mut doc = match identity {
+        Identity::DidDocument(doc) => doc,
+        Identity::PlcLog(_) | Identity::X509Chain(_) | Identity::Content(_) => {
+            bail!("resolver did not return a DID document")
+        }
+    };
+    check_version(&doc, &options)?;
+    add_implicit_services(&mut doc)?;
+    Ok(doc)
+}
+
+/// Dereference a `did:webvh` DID URL to a resource.
+///
+/// A DID URL with a path is dereferenced to the content of the file it
+/// identifies, fetched using the resolver: `/whois` using the document's
+/// `#whois` service and any other path relative to its `#files` service. The
+/// resolver should return the file as [`Identity::Content`]. Other DID URLs
+/// are dereferenced using [`document_resource`]. If the DID URL has a
+/// hashlink (`hl`), the file's content must match it.
+///
+/// # Errors
+///
+/// Will fail if the DID cannot be resolved, the resource is not found in the
+/// document or the resolver fails to return the file's content.
+pub async fn dereference(
+    url: &Url, options: Option<&ResolutionOptions>, resolver: &impl IdentityResolver,
+) -> anyhow::Result<Resource> {
+    let doc = resolve(url, options, resolver).await?;
+    let Some(http_url) = path_url(url, &doc)? else {
+        return document_resource(url, options, &doc);
+    };
+    let options = ResolutionOptions::merge(options, url.query.as_ref())?;
+    let Identity::Content(content) = resolver.resolve_with_options(&http_url, &options).await?
+    else {
+        bail!("resolver did not return the content of {http_url}");
+    };
+    verify_content(url, Some(&options), &content)?;
+    Ok(Resource::Content(content))
+}
+
+// The HTTPS URL of the file identified by a DID URL's path, constructed from
+// the document's `#whois` service for `/whois` and its `#files` service
+// otherwise. Returns `None` for DID URLs without a path.
+pub(super) fn path_url(url: &Url, doc: &Document) -> anyhow::Result<Option<String>> {
+    let path = url.path.as_ref().map(|p| p.join("/")).unwrap_or_default();
+    let query = match path.as_str() {
+        "" => return Ok(None),
+        "whois" => QueryParams {
+            service: Some("whois".to_string()),
+            ..QueryParams::default()
+        },
+        _ => QueryParams {
+            service: Some("files".to_string()),
+            relative_ref: Some(path),
+            ..QueryParams::default()
+        },
+    };
+    let service_url = Url {
+        path: None,
+        query: Some(query),
+        fragment: None,
+        ..url.clone()
+    };
+    let Resource::Url(http_url) = document_resource(&service_url, None, doc)? else {
+        bail!("service did not dereference to a URL");
+    };
+    Ok(Some(http_url))
+}
+
+// Check the resolved document is the version selected by the options, using
+// the version metadata added by `resolve_log`. A document without the metadata
+// cannot be shown to be the selected version, so is treated as not found.
+pub(super) fn check_version(doc: &Document, options: &ResolutionOptions) -> anyhow::Result<()> {
+    let additional = doc.did_document_metadata.as_ref().and_then(|md| md.additional.as_ref());
+    let metadata = |key: &str| {
+        additional.and_then(|a| a.get(key)).and_then(|v| v.as_str()).ok_or_else(|| {
+            let detail = format!("resolved document has no {key} metadata");
+            Error::resolution(ErrorCode::NotFound, detail)
+        })
+    };
+
+    if let Some(version_id) = &options.version_id {
+        let resolved = metadata("versionId")?;
+        if resolved != version_id {
+            let detail =
+                format!("resolved version {resolved} but version {version_id} was requested");
+            return Err(Error::resolution(ErrorCode::NotFound, detail).into());
+        }
     }
+    if let Some(version_number) = &options.version_number {
+        let version_number = parse_version_number(version_number)?;
+        let resolved = metadata("versionId")?;
+        if !resolved.starts_with(&format!("{version_number}-")) {
+            let detail = format!(
+                "resolved version {resolved} but version number {version_number} was requested"
+            );
+            return Err(Error::resolution(ErrorCode::NotFound, detail).into());
+        }
+    }
+    if let Some(version_time) = &options.version_time {
+        let version_time = version_time.parse::<DateTime<Utc>>()?;
+        let resolved = metadata("versionTime")?;
+        if resolved.parse::<DateTime<Utc>>()? > version_time {
+            let detail = format!("resolved version from {resolved} is later than {version_time}");
+            return Err(Error::resolution(ErrorCode::NotFound, detail).into());
+        }
+    }
+    Ok(())
 }
 
 /// Verification of the contents of the `did.jsonl` file and resolution into a
@@ -100,72 +286,275 @@ pub async fn resolve(url: &Url, resolver: &impl IdentityResolver) -> anyhow::Res
 /// To skip verification of the witness proofs, pass `None` for the
 /// `witness_proofs` parameter.
 ///
+/// The latest version is returned unless the parameters select a version by
+/// version ID, version number or version time.
+///
 /// # Errors
 ///
-/// Will fail if the log entries are invalid.
-#[allow(clippy::too_many_lines)]
+/// Will fail with an [`Error::LogVerification`] carrying the version ID of the
+/// offending entry if the log entries are invalid, or if a version number
+/// parameter is not a number. Will fail with an [`ErrorCode::NotFound`]
+/// resolution error if the parameters select a version that is not in the
+/// log.
 pub async fn resolve_log(
     log: &[DidLogEntry], witness_proofs: Option<&[WitnessEntry]>, parameters: Option<&QueryParams>,
 ) -> anyhow::Result<Document> {
+    let version_number = match parameters.and_then(|p| p.version_number.as_deref()) {
+        Some(number) => Some(parse_version_number(number)?),
+        None => None,
+    };
+
+    let mut doc = Document::default();
+    let mut matched = false;
+    verify_log(log, witness_proofs, |i, number, resolved| {
+        doc = resolved;
+
+        // Check for explicit version ID, version number or version time
+        // request. (Otherwise the latest version is returned.)
+        let Some(params) = parameters else {
+            return Ok(false);
+        };
+        if let Some(version_id) = &params.version_id {
+            if *version_id == log[i].version_id {
+                matched = true;
+            }
+        }
+        if version_number == Some(number) {
+            matched = true;
+        }
+        if let Some(version_time) = &params.version_time {
+            let version_time = version_time.parse::<DateTime<Utc>>()?;
+            if version_time >= log[i].version_time
+                && (i == log.len() - 1 || version_time <= log[i + 1].version_time)
+            {
+                matched = true;
+            }
+        }
+        Ok(matched)
+    })
+    .await?;
+
+    // A selected version that is not in the log is not found, rather than
+    // falling back to the latest version.
+    if !matched {
+        if let Some(params) = parameters {
+            let selector = params
+                .version_id
+                .as_ref()
+                .map(|id| format!("version {id}"))
+                .or_else(|| params.version_number.as_ref().map(|n| format!("version number {n}")))
+                .or_else(|| params.version_time.as_ref().map(|t| format!("version at {t}")));
+            if let Some(selector) = selector {
+                let detail = format!("{selector} not found in log");
+                return Err(Error::resolution(ErrorCode::NotFound, detail).into());
+            }
+        }
+    }
+    Ok(doc)
+}
+
+/// A version of a DID document in a `did:webvh` log.
+#[derive(Clone, Debug)]
+pub struct LogVersion {
+    /// The version number, starting at 1.
+    pub version_number: u64,
+
+    /// The version ID of the log entry.
+    pub version_id: String,
+
+    /// The time the version was created.
+    pub version_time: DateTime<Utc>,
+
+    /// A summary of the log entry's parameters.
+    pub parameters: ParameterSummary,
+
+    /// The DID document resolved for the version, with the same metadata
+    /// as returned by [`resolve_log`].
+    pub document: Document,
+}
+
+/// A summary of the parameters in effect for a version of a DID document.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ParameterSummary {
+    /// The `did:webvh` specification version.
+    pub method: String,
+
+    /// The number of keys authorized to update the DID.
+    pub update_keys: usize,
+
+    /// Whether key pre-rotation is enabled.
+    pub prerotation: bool,
+
+    /// Whether the DID can be moved to a different domain.
+    pub portable: bool,
+
+    /// The witness threshold, if the DID has witnesses.
+    pub witness_threshold: Option<u64>,
+
+    /// The number of witnesses.
+    pub witnesses: usize,
+
+    /// Whether the DID has been deactivated.
+    pub deactivated: bool,
+
+    /// The time in seconds the DID may be cached.
+    pub ttl: u64,
+
+    /// The names of the parameters (as they appear in the log) that changed
+    /// from the previous version. For the first version, all parameters are
+    /// listed.
+    pub changed: Vec<String>,
+}
+
+/// Verify a `did:webvh` log and list every version of the DID document, in
+/// order. Use this to show the history of a DID.
+///
+/// The log is verified in the same way as [`resolve_log`].
+///
+/// # Errors
+///
+/// Will fail with an [`Error::LogVerification`] carrying the version ID of the
+/// offending entry if the log entries are invalid.
+pub async fn history(
+    log: &[DidLogEntry], witness_proofs: Option<&[WitnessEntry]>,
+) -> anyhow::Result<Vec<LogVersion>> {
+    let mut versions = Vec::with_capacity(log.len());
+    verify_log(log, witness_proofs, |i, number, document| {
+        let entry = &log[i];
+        let previous = i.checked_sub(1).map(|p| &log[p].parameters);
+        versions.push(LogVersion {
+            version_number: number,
+            version_id: entry.version_id.clone(),
+            version_time: entry.version_time,
+            parameters: ParameterSummary {
+                method: entry.parameters.method.clone(),
+                update_keys: entry.parameters.update_keys.len(),
+                prerotation: entry.parameters.next_key_hashes.is_some(),
+                portable: entry.parameters.portable,
+                witness_threshold: entry.parameters.witness.as_ref().map(|w| w.threshold),
+                witnesses: entry.parameters.witness.as_ref().map_or(0, |w| w.witnesses.len()),
+                deactivated: entry.parameters.deactivated,
+                ttl: entry.parameters.ttl,
+                changed: changed_parameters(previous, &entry.parameters)?,
+            },
+            document,
+        });
+        Ok(false)
+    })
+    .await?;
+    Ok(versions)
+}
+
+// The names of the parameters that differ between two log entries.
+fn changed_parameters(
+    previous: Option<&Parameters>, current: &Parameters,
+) -> anyhow::Result<Vec<String>> {
+    let Value::Object(current) = serde_json::to_value(current)? else {
+        return Ok(vec![]);
+    };
+    let previous = match previous {
+        Some(previous) => serde_json::to_value(previous)?,
+        None => Value::Null,
+    };
+    let mut changed = current
+        .iter()
+        .filter(|(name, value)| previous.get(name.as_str()) != Some(*value))
+        .map(|(name, _)| name.clone())
+        .collect::<Vec<_>>();
+    if let Value::Object(previous) = &previous {
+        changed.extend(previous.keys().filter(|name| !current.contains_key(*name)).cloned());
+    }
+    Ok(changed)
+}
+
+// Parse a `versionNumber` parameter.
+fn parse_version_number(number: &str) -> anyhow::Result<u64> {
+    number.parse::<u64>().map_err(|_| {
+        let detail = format!("versionNumber is not a valid version number: {number}");
+        Error::resolution(ErrorCode::InvalidOptions, detail).into()
+    })
+}
+
+// Verify the log entries in order, calling `visit` with the position and
+// version number of each verified entry and the DID document resolved for
+// it. Verification stops early if `visit` returns `true`.
+#[allow(clippy::too_many_lines)]
+async fn verify_log(
+    log: &[DidLogEntry], witness_proofs: Option<&[WitnessEntry]>,
+    mut visit: impl FnMut(usize, u64, Document) -> anyhow::Result<bool>,
+) -> anyhow::Result<()> {
     if log.is_empty() {
-        bail!("log entries are empty");
+        return Err(Error::LogVerification {
+            kind: LogErrorKind::Empty,
+            version_id: None,
+            detail: "log entries are empty".to_string(),
+        }
+        .into());
     }
 
     let mut prev_index = 0;
     // let mut prev_version = SCID_PLACEHOLDER.to_string();
     let mut prev_version = log[0].parameters.scid.clone();
     let mut prev_time = DateTime::<Utc>::MIN_UTC;
-    let mut doc = Document::default();
     let mut prev_next_key_hashes: Option<Vec<String>> = None;
-    for i in 0..log.len() {
+    for (i, entry) in log.iter().enumerate() {
+        let version_id = &entry.version_id;
+
         // 1. Update current parameters with parameters from the entry being
         // processed.
 
         // 2. Verify controller proofs.
-        verify_proofs(&log[i]).await?;
+        verify_proofs(entry).await?;
 
         // 3.1. Verify the version number is incremented by one for each entry.
         // 3.2. Verify the version number and entry hash is separated by `-`.
-        let version_parts = log[i].version_id.split('-').collect::<Vec<&str>>();
-        if version_parts.len() != 2 {
-            bail!("log entry version id has an unexpected format");
-        }
-        let index = version_parts[0].parse::<u64>()?;
+        let version_parts = version_id.split('-').collect::<Vec<&str>>();
+        let index = match version_parts[..] {
+            [number, _] => number.parse::<u64>().ok(),
+            _ => None,
+        };
+        let Some(index) = index else {
+            let detail = "log entry version id has an unexpected format";
+            return Err(Error::log(LogErrorKind::VersionId, version_id, detail).into());
+        };
         if index != prev_index + 1 {
-            bail!("log entries are not sequential");
+            let detail = "log entries are not sequential";
+            return Err(Error::log(LogErrorKind::Sequence, version_id, detail).into());
         }
 
         // 3.3. Verify the entry hash.
-        log[i].verify_hash(&prev_version)?;
+        entry.verify_hash(&prev_version)?;
 
         // 4. The version time must be in the past and monotonically increasing.
-        if log[i].version_time > Utc::now() {
-            bail!("log entry time is in the future");
+        if entry.version_time > Utc::now() {
+            let detail = "log entry time is in the future";
+            return Err(Error::log(LogErrorKind::VersionTime, version_id, detail).into());
         }
-        if log[i].version_time <= prev_time {
-            bail!(
+        if entry.version_time <= prev_time {
+            let detail = format!(
                 "log entry times are not monotonically increasing: {} -> {}",
-                log[i].version_time,
-                prev_time
+                entry.version_time, prev_time
             );
+            return Err(Error::log(LogErrorKind::VersionTime, version_id, detail).into());
         }
 
         // 5. If the entry is the first one, verify the SCID.
         if i == 0 {
-            let initial_string = serde_json::to_string(&log[i])?;
-            let replaced = initial_string.replace(&log[i].parameters.scid, SCID_PLACEHOLDER);
+            let initial_string = serde_json::to_string(entry)?;
+            let replaced = initial_string.replace(&entry.parameters.scid, SCID_PLACEHOLDER);
             let mut initial_log_entry = serde_json::from_str::<DidLogEntry>(&replaced)?;
             initial_log_entry.version_id = SCID_PLACEHOLDER.to_string();
             initial_log_entry.proof = vec![];
             let hash = initial_log_entry.hash()?;
-            if hash != log[i].parameters.scid {
-                bail!("first log entry SCID does not match calculated hash");
+            if hash != entry.parameters.scid {
+                let detail = "first log entry SCID does not match calculated hash";
+                return Err(Error::log(LogErrorKind::Scid, version_id, detail).into());
             }
         }
 
         // 6. Record the state as the document to return (if everything else is
         // successful).
-        doc = log[i].state.clone();
+        let mut doc = entry.state.clone();
 
         // Add method-specific metadata to the document from the log parameters.
         let mut mdb = doc
@@ -173,69 +562,50 @@ pub async fn resolve_log(
             .as_ref()
             .map_or_else(DocumentMetadataBuilder::new, DocumentMetadataBuilder::from);
         mdb = mdb
-            .additional("versionId", log[i].version_id.clone())
-            .additional("versionTime", log[i].version_time.to_rfc3339())
-            .additional("scid", log[i].parameters.scid.clone())
-            .additional("portable", log[i].parameters.portable);
-        if log[i].parameters.witness.is_some() {
-            mdb = mdb.additional("witness", log[i].parameters.witness.clone());
+            .additional("versionId", entry.version_id.clone())
+            .additional("versionTime", entry.version_time.to_rfc3339())
+            .additional("scid", entry.parameters.scid.clone())
+            .additional("portable", entry.parameters.portable);
+        if entry.parameters.witness.is_some() {
+            mdb = mdb.additional("witness", entry.parameters.witness.clone());
         }
         doc.did_document_metadata = Some(mdb.build());
 
         // 7. If key pre-rotation is enabled, check the update keys match the
         // previous entry's next-key hashes.
         if let Some(next_key_hashes) = &prev_next_key_hashes {
-            for key in &log[i].parameters.update_keys {
+            for key in &entry.parameters.update_keys {
                 let key_digest = sha2::Sha256::digest(key.as_bytes());
                 let key_hash = multibase::encode(Base::Base58Btc, key_digest.as_slice());
                 if !next_key_hashes.contains(&key_hash) {
-                    bail!("update key not found in pre-rotation hashes");
+                    let detail = "update key not found in pre-rotation hashes";
+                    return Err(Error::log(LogErrorKind::PreRotation, version_id, detail).into());
                 }
             }
         }
 
         // 8. Increment.
         prev_index = index;
-        prev_version.clone_from(&log[i].version_id);
-        prev_time.clone_from(&log[i].version_time);
-        prev_next_key_hashes.clone_from(&log[i].parameters.next_key_hashes);
+        prev_version.clone_from(&entry.version_id);
+        prev_time.clone_from(&entry.version_time);
+        prev_next_key_hashes.clone_from(&entry.parameters.next_key_hashes);
 
         // 9. Check witness proofs if provided.
-        if witness_proofs.is_some() && log[i].parameters.witness.is_some() {
+        if witness_proofs.is_some() && entry.parameters.witness.is_some() {
             if let Some(witness_entries) = witness_proofs {
-                verify_witness(&log[i], witness_entries).await?;
+                verify_witness(entry, witness_entries).await?;
             }
         }
 
-        // Check for explicit version ID or version time request. (Otherwise
-        // the latest version is returned.)
-        if let Some(params) = parameters {
-            if let Some(version_id) = &params.version_id {
-                if *version_id == log[i].version_id {
-                    break;
-                }
-            }
-            if let Some(version_time) = &params.version_time {
-                let version_time = version_time.parse::<DateTime<Utc>>()?;
-                if version_time >= log[i].version_time {
-                    if i < log.len() - 1 {
-                        if version_time <= log[i + 1].version_time {
-                            break;
-                        }
-                    } else {
-                        break;
-                    }
-                }
-            }
+        if visit(i, index, doc)? {
+            break;
         }
     }
-    Ok(doc)
+    Ok(())
 }
 
 #[cfg(test)]
 mod test {
-    use std::str::FromStr;
-
     use super::*;
 
     #[test]
@@ -264,4 +634,20 @@ mod test {
         let url = structured_url.to_webvh_http().expect("should serialize");
         assert_eq!(url, "https://domain.with-hyphens.computer:8080/.well-known/did.jsonl");
     }
+
+    #[test]
+    fn file_url() {
+        let did = "did:webvh:QmaJp6pmb6RUk4oaDyWQcjeqYbvxsc3kvmHWPpz7B5JwDU:example.com";
+        let url = Url::from_str(&format!("{did}/path/file.json")).expect("should parse");
+        let http_url = url.to_webvh_http().expect("should serialize");
+        assert_eq!(http_url, "https://example.com/path/file.json");
+
+        let url = Url::from_str(&format!("{did}/whois")).expect("should parse");
+        let http_url = url.to_webvh_http().expect("should serialize");
+        assert_eq!(http_url, "https://example.com/.well-known/whois.vp");
+
+        let url = Url::from_str(&format!("{did}:dids:issuer/whois")).expect("should parse");
+        let http_url = url.to_webvh_http().expect("should serialize");
+        assert_eq!(http_url, "https://example.com/dids/issuer/whois.vp");
+    }
 }
@@ -1,13 +1,12 @@
 //! Update operation for the `did:webvh` method.
 
-use anyhow::bail;
 use chrono::Utc;
 use credibil_se::Signer;
 use multibase::Base;
 use serde::{Deserialize, Serialize};
 use sha2::Digest;
 
-use crate::SignerExt;
+use crate::{Error, LogErrorKind, SignerExt};
 use crate::did::Document;
 
 use super::{
@@ -23,6 +22,7 @@ pub struct UpdateBuilder<S, D> {
     next_key_hashes: Option<Vec<String>>,
     witness: Option<Witness>,
     ttl: u64,
+    conformant: bool,
 
     log: DidLog,
     signer: S,
@@ -58,7 +58,7 @@ impl UpdateBuilder<WithoutSigner, WithoutDocument> {
         // Validate the current log entries by resolving the DID document.
         let _ = resolve_log(log, witness_proofs, None).await?;
         let Some(last_entry) = log.last() else {
-            anyhow::bail!("log must not be empty.");
+            return Err(Error::Builder("log must not be empty.".to_string()).into());
         };
 
         Ok(Self {
@@ -67,6 +67,7 @@ impl UpdateBuilder<WithoutSigner, WithoutDocument> {
             next_key_hashes: last_entry.parameters.next_key_hashes.clone(),
             witness: last_entry.parameters.witness.clone(),
             ttl: last_entry.parameters.ttl,
+            conformant: false,
 
             log: log.to_vec(),
             doc: WithoutDocument,
@@ -86,30 +87,39 @@ impl UpdateBuilder<WithoutSigner, WithoutDocument> {
         // allowed portability. If the location has changed, the SCID must be
         // unchanged.
         let Some(last_entry) = self.log.last() else {
-            anyhow::bail!("log must not be empty.");
+            return Err(Error::Builder("log must not be empty.".to_string()).into());
         };
         if last_entry.state.id != document.id {
             if !last_entry.parameters.portable {
-                anyhow::bail!("location has changed for non-portable DID.");
+                let detail = "location has changed for non-portable DID.";
+                return Err(Error::Builder(detail.to_string()).into());
             }
             let parts = last_entry.state.id.split(':').collect::<Vec<&str>>();
             if parts.len() < 4 {
-                anyhow::bail!("invalid DID format.");
+                return Err(Error::Builder("invalid DID format.".to_string()).into());
             }
             let starts_with = format!("did:webvh:{}:", parts[2]);
             if !document.id.starts_with(&starts_with) {
-                anyhow::bail!("SCID has changed for portable DID.");
+                let detail = "SCID has changed for portable DID.";
+                return Err(Error::Builder(detail.to_string()).into());
             }
         }
+
+        let mut doc = document.clone();
+
+        // Ensure the context matches the document's content.
+        doc.update_context();
+
         Ok(UpdateBuilder {
             update_keys: self.update_keys.clone(),
             portable: self.portable,
             next_key_hashes: self.next_key_hashes.clone(),
             witness: self.witness.clone(),
             ttl: self.ttl,
+            conformant: self.conformant,
 
             log: self.log.clone(),
-            doc: WithDocument(document.clone()),
+            doc: WithDocument(doc),
             signer: WithoutSigner,
         })
     }
@@ -153,7 +163,8 @@ impl UpdateBuilder<WithoutSigner, WithDocument> {
                 let digest = sha2::Sha256::digest(new_key.as_bytes());
                 let hash = multibase::encode(Base::Base58Btc, digest.as_slice());
                 if !next_key_hashes.contains(&hash) {
-                    bail!("new update keys do not match current next key hashes.");
+                    let detail = "new update keys do not match current next key hashes.";
+                    return Err(Error::Builder(detail.to_string()).into());
                 }
             }
         }
@@ -219,6 +230,14 @@ impl UpdateBuilder<WithoutSigner, WithDocument> {
         self
     }
 
+    /// Refuse to build a log entry for a document that does not conform to
+    /// DID Core (see [`Document::validate`]). Defaults to not checked.
+    #[must_use]
+    pub const fn conformant(mut self, conformant: bool) -> Self {
+        self.conformant = conformant;
+        self
+    }
+
     /// Add a signer to the builder.
     #[must_use]
     pub fn signer<S: Signer>(self, signer: &S) -> UpdateBuilder<WithSigner<'_, S>, WithDocument> {
@@ -228,6 +247,7 @@ impl UpdateBuilder<WithoutSigner, WithDocument> {
             next_key_hashes: self.next_key_hashes,
             witness: self.witness,
             ttl: self.ttl,
+            conformant: self.conformant,
 
             log: self.log,
             doc: self.doc,
@@ -246,11 +266,18 @@ impl<S: SignerExt> UpdateBuilder<WithSigner<'_, S>, WithDocument> {
     ///
     /// Will fail if secondary algorithms fail such as generating a hash of the
     /// log entry to calculate the version ID. Will also fail if the provided
-    /// signer fails to sign the log entry.
+    /// signer fails to sign the log entry, or with an [`Error::Conformance`]
+    /// if conformance is required and the document does not conform.
     pub async fn build(&self) -> anyhow::Result<UpdateResult> {
+        if self.conformant {
+            let findings = self.doc.0.validate();
+            if !findings.is_empty() {
+                return Err(Error::Conformance(findings).into());
+            }
+        }
         let mut log = self.log.clone();
         let Some(last_entry) = log.last() else {
-            anyhow::bail!("log must not be empty.");
+            return Err(Error::Builder("log must not be empty.".to_string()).into());
         };
 
         let mut params = last_entry.parameters.clone();
@@ -277,7 +304,8 @@ impl<S: SignerExt> UpdateBuilder<WithSigner<'_, S>, WithDocument> {
         let entry_hash = entry.hash()?;
         let parts = last_entry.version_id.split('-').collect::<Vec<&str>>();
         if parts.len() != 2 {
-            anyhow::bail!("unexpected version ID format.");
+            let detail = "unexpected version ID format.";
+            return Err(Error::log(LogErrorKind::VersionId, &last_entry.version_id, detail).into());
         }
         let mut version_number = parts[0].parse::<u64>()?;
         version_number += 1;
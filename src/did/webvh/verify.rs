@@ -8,6 +8,7 @@ use credibil_jose::PublicKeyJwk;
 use sha2::Digest;
 
 use crate::proof::w3c::Proof;
+use crate::{Error, LogErrorKind};
 
 /// Verify the controller's proofs in a log entry.
 ///
@@ -15,14 +16,17 @@ use crate::proof::w3c::Proof;
 /// method referenced in the proof.
 ///
 /// # Errors
-/// Will return an error if any of the proofs on the log entry are invalid.
+/// Will return an [`Error::LogVerification`] if any of the proofs on the log
+/// entry are invalid.
 pub async fn verify_proofs(log_entry: &DidLogEntry) -> anyhow::Result<()> {
+    let version_id = &log_entry.version_id;
     if log_entry.proof.is_empty() {
-        bail!("log entry has no proof");
+        return Err(Error::log(LogErrorKind::Proof, version_id, "log entry has no proof").into());
     }
 
     for proof in &log_entry.proof {
-        verify_proof(log_entry, proof, &ProofSigner::Controller)?;
+        verify_proof(log_entry, proof, &ProofSigner::Controller)
+            .map_err(|e| Error::log(LogErrorKind::Proof, version_id, e.to_string()))?;
     }
     Ok(())
 }
@@ -153,8 +157,9 @@ pub fn validate_witness(witness: &Witness) -> anyhow::Result<()> {
 ///
 /// # Errors
 ///
-/// Will fail if the total weight of witness proofs does not meet the threshold.
-/// Will also fail if called for a log entry that has no witness parameters.
+/// Will fail with an [`Error::LogVerification`] if the total weight of witness
+/// proofs does not meet the threshold. Will also fail if called for a log
+/// entry that has no witness parameters.
 pub async fn verify_witness(
     log_entry: &DidLogEntry, witnesses: &[WitnessEntry],
 ) -> anyhow::Result<u64> {
@@ -177,7 +182,8 @@ pub async fn verify_witness(
         }
     }
     if total_weight < witness_weights.threshold {
-        bail!("total witness weight does not meet the threshold");
+        let detail = "total witness weight does not meet the threshold";
+        return Err(Error::log(LogErrorKind::Witness, &log_entry.version_id, detail).into());
     }
     Ok(total_weight)
 }
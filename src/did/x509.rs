@@ -0,0 +1,447 @@
+//! # DID X509
+//!
+//! The `did:x509` method binds a DID to an X.509 certificate chain. The DID
+//! identifies a CA certificate by its fingerprint and constrains the leaf
+//! (end-entity) certificate with one or more policies. Resolution requires
+//! the certificate chain: the chain is validated against the DID and the
+//! leaf certificate's public key becomes the DID document's verification
+//! method.
+//!
+//! When dereferencing a `did:x509` URL (see [`crate::did::deref_url`]), the
+//! identity resolver is passed the DID and should return the certificate
+//! chain presented with it as an [`crate::Identity::X509Chain`].
+//!
+//! Supported policies:
+//!
+//! - `subject`: subject name attributes (`CN`, `L`, `ST`, `O`, `OU`, `C`,
+//!   `STREET` or a dotted OID) the leaf certificate must have.
+//! - `san`: a subject alternative name (`email`, `dns` or `uri`) the leaf
+//!   certificate must have.
+//! - `eku`: an extended key usage OID the leaf certificate must have.
+//!
+//! See:
+//!
+//! - <https://github.com/microsoft/did-x509/blob/main/specification.md>
+//! - <https://w3c.github.io/did-resolution>
+
+mod resolve;
+
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail};
+use base64ct::{Base64UrlUnpadded, Encoding};
+use chrono::{DateTime, Utc};
+use percent_encoding::{AsciiSet, NON_ALPHANUMERIC, percent_decode_str, utf8_percent_encode};
+pub use resolve::*;
+use sha2::Digest;
+use x509_cert::der::asn1::ObjectIdentifier;
+use x509_cert::der::{Encode, Tag, Tagged};
+use x509_cert::ext::pkix::name::GeneralName;
+use x509_cert::ext::pkix::{ExtendedKeyUsage, SubjectAltName};
+
+use crate::did::{Method, Url};
+use crate::x509::{Certificate, verify_chain};
+
+/// Characters that must be percent-encoded in a policy value.
+const ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC.remove(b'-').remove(b'.').remove(b'_');
+
+/// Subject name attribute keys and their OIDs.
+const SUBJECT_KEYS: [(&str, &str); 7] = [
+    ("CN", "2.5.4.3"),
+    ("L", "2.5.4.7"),
+    ("ST", "2.5.4.8"),
+    ("O", "2.5.4.10"),
+    ("OU", "2.5.4.11"),
+    ("C", "2.5.4.6"),
+    ("STREET", "2.5.4.9"),
+];
+
+/// A validated `did:x509` identifier.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DidX509 {
+    id: String,
+    algorithm: FingerprintAlgorithm,
+    ca_fingerprint: String,
+    policies: Vec<Policy>,
+}
+
+/// The hash algorithm used for the CA certificate fingerprint.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FingerprintAlgorithm {
+    /// SHA-256
+    Sha256,
+
+    /// SHA-384
+    Sha384,
+
+    /// SHA-512
+    Sha512,
+}
+
+/// A constraint on the leaf certificate of a `did:x509` certificate chain.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Policy {
+    /// Subject name attributes, as (key, value) pairs. The key is one of
+    /// `CN`, `L`, `ST`, `O`, `OU`, `C`, `STREET` or a dotted OID.
+    Subject(Vec<(String, String)>),
+
+    /// A subject alternative name.
+    San(SanType, String),
+
+    /// An extended key usage OID.
+    Eku(String),
+}
+
+/// Subject alternative name types supported by the `san` policy.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SanType {
+    /// RFC 822 email address.
+    Email,
+
+    /// DNS name.
+    Dns,
+
+    /// URI.
+    Uri,
+}
+
+impl DidX509 {
+    /// Create a `did:x509` from a DID or DID URL.
+    ///
+    /// # Errors
+    /// Will fail if the URL is not a `did:x509`, uses an unsupported version,
+    /// fingerprint algorithm or policy, or a policy is malformed.
+    pub fn from_url(url: &Url) -> anyhow::Result<Self> {
+        if url.method != Method::X509 {
+            bail!("DID is not a valid did:x509: {url}");
+        }
+        if url.path.is_some() {
+            bail!("DID is not a valid did:x509 - paths are not supported");
+        }
+
+        let mut parts = url.id.split("::");
+        let Some(ca) = parts.next() else {
+            bail!("DID is not a valid did:x509 - missing CA fingerprint");
+        };
+        let ca = ca.split(':').collect::<Vec<_>>();
+        let [version, algorithm, ca_fingerprint] = ca.as_slice() else {
+            bail!("DID is not a valid did:x509 - expected version, algorithm and CA fingerprint");
+        };
+        if *version != "0" {
+            bail!("unsupported did:x509 version: {version}");
+        }
+        let algorithm = FingerprintAlgorithm::from_str(algorithm)?;
+        let fingerprint = Base64UrlUnpadded::decode_vec(ca_fingerprint)
+            .map_err(|e| anyhow!("invalid did:x509 CA fingerprint encoding: {e}"))?;
+        if fingerprint.len() != algorithm.digest_len() {
+            bail!("did:x509 CA fingerprint length does not match the {algorithm} algorithm");
+        }
+
+        let policies = parts.map(Policy::from_str).collect::<anyhow::Result<Vec<_>>>()?;
+        if policies.is_empty() {
+            bail!("DID is not a valid did:x509 - at least one policy is required");
+        }
+
+        Ok(Self {
+            id: url.id.clone(),
+            algorithm,
+            ca_fingerprint: (*ca_fingerprint).to_string(),
+            policies,
+        })
+    }
+
+    /// The DID, without a fragment.
+    #[must_use]
+    pub fn did(&self) -> String {
+        format!("did:x509:{}", self.id)
+    }
+
+    /// The hash algorithm used for the CA certificate fingerprint.
+    #[must_use]
+    pub const fn algorithm(&self) -> FingerprintAlgorithm {
+        self.algorithm
+    }
+
+    /// The base64url-encoded CA certificate fingerprint.
+    #[must_use]
+    pub fn ca_fingerprint(&self) -> &str {
+        &self.ca_fingerprint
+    }
+
+    /// The policies the leaf certificate must satisfy.
+    #[must_use]
+    pub fn policies(&self) -> &[Policy] {
+        &self.policies
+    }
+
+    /// Validate a certificate chain against the DID.
+    ///
+    /// The chain is ordered from the leaf certificate to the most senior CA
+    /// certificate and must be a valid chain at `time` (see
+    /// [`verify_chain`]). One of the CA certificates (any certificate other
+    /// than the leaf) must match the CA fingerprint and the leaf certificate
+    /// must satisfy every policy.
+    ///
+    /// # Errors
+    /// Will fail if the chain is invalid or does not match the DID.
+    pub fn validate(&self, chain: &[Certificate], time: DateTime<Utc>) -> anyhow::Result<()> {
+        if chain.len() < 2 {
+            bail!("did:x509 certificate chain must include a CA certificate");
+        }
+        verify_chain(chain, time)?;
+
+        let mut found = false;
+        for ca in &chain[1..] {
+            let fingerprint = self.algorithm.digest(&ca.to_der()?);
+            if Base64UrlUnpadded::encode_string(&fingerprint) == self.ca_fingerprint {
+                found = true;
+                break;
+            }
+        }
+        if !found {
+            bail!("no CA certificate in the chain matches the did:x509 CA fingerprint");
+        }
+
+        for policy in &self.policies {
+            policy.validate(&chain[0])?;
+        }
+        Ok(())
+    }
+}
+
+impl TryFrom<&Url> for DidX509 {
+    type Error = anyhow::Error;
+
+    fn try_from(url: &Url) -> anyhow::Result<Self> {
+        Self::from_url(url)
+    }
+}
+
+impl FromStr for DidX509 {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        Self::from_url(&Url::from_str(s)?)
+    }
+}
+
+impl Display for DidX509 {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.did())
+    }
+}
+
+impl FingerprintAlgorithm {
+    /// Hash a DER-encoded certificate.
+    #[must_use]
+    pub fn digest(&self, der: &[u8]) -> Vec<u8> {
+        match self {
+            Self::Sha256 => sha2::Sha256::digest(der).to_vec(),
+            Self::Sha384 => sha2::Sha384::digest(der).to_vec(),
+            Self::Sha512 => sha2::Sha512::digest(der).to_vec(),
+        }
+    }
+
+    const fn digest_len(self) -> usize {
+        match self {
+            Self::Sha256 => 32,
+            Self::Sha384 => 48,
+            Self::Sha512 => 64,
+        }
+    }
+}
+
+impl FromStr for FingerprintAlgorithm {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "sha256" => Ok(Self::Sha256),
+            "sha384" => Ok(Self::Sha384),
+            "sha512" => Ok(Self::Sha512),
+            _ => Err(anyhow!("unsupported did:x509 fingerprint algorithm: {s}")),
+        }
+    }
+}
+
+impl Display for FingerprintAlgorithm {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Sha256 => write!(f, "sha256"),
+            Self::Sha384 => write!(f, "sha384"),
+            Self::Sha512 => write!(f, "sha512"),
+        }
+    }
+}
+
+impl Policy {
+    /// Check the leaf certificate satisfies the policy.
+    ///
+    /// # Errors
+    /// Will fail if the certificate does not satisfy the policy or the
+    /// relevant certificate fields cannot be decoded.
+    pub fn validate(&self, leaf: &Certificate) -> anyhow::Result<()> {
+        let tbs = &leaf.tbs_certificate;
+        match self {
+            Self::Subject(attributes) => {
+                for (key, value) in attributes {
+                    let oid = subject_oid(key)?;
+                    let mut matched = false;
+                    for atv in tbs.subject.0.iter().flat_map(|rdn| rdn.0.iter()) {
+                        if atv.oid == oid && attribute_value(&atv.value)? == Some(value.as_str()) {
+                            matched = true;
+                            break;
+                        }
+                    }
+                    if !matched {
+                        bail!("certificate subject does not match did:x509 policy {key}={value}");
+                    }
+                }
+            }
+            Self::San(san_type, value) => {
+                let names = tbs.get::<SubjectAltName>()?.map(|(_, san)| san.0).unwrap_or_default();
+                let matched = names.iter().any(|name| match (san_type, name) {
+                    (SanType::Email, GeneralName::Rfc822Name(n))
+                    | (SanType::Dns, GeneralName::DnsName(n))
+                    | (SanType::Uri, GeneralName::UniformResourceIdentifier(n)) => {
+                        n.as_str() == value
+                    }
+                    _ => false,
+                });
+                if !matched {
+                    bail!("certificate has no {san_type} subject alternative name {value}");
+                }
+            }
+            Self::Eku(oid) => {
+                let oid = ObjectIdentifier::new(oid)
+                    .map_err(|e| anyhow!("invalid did:x509 EKU OID {oid}: {e}"))?;
+                let ekus = tbs.get::<ExtendedKeyUsage>()?.map(|(_, eku)| eku.0).unwrap_or_default();
+                if !ekus.contains(&oid) {
+                    bail!("certificate does not have extended key usage {oid}");
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Policy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let Some((name, value)) = s.split_once(':') else {
+            bail!("did:x509 policy has no value: {s}");
+        };
+        match name {
+            "subject" => {
+                let parts = value.split(':').collect::<Vec<_>>();
+                if parts.len() % 2 != 0 {
+                    bail!("did:x509 subject policy must be a list of key:value pairs");
+                }
+                let mut attributes: Vec<(String, String)> = vec![];
+                for pair in parts.chunks(2) {
+                    let key = pair[0].to_string();
+                    subject_oid(&key)?;
+                    if attributes.iter().any(|(k, _)| *k == key) {
+                        bail!("did:x509 subject policy has a duplicate key: {key}");
+                    }
+                    attributes.push((key, decode(pair[1])?));
+                }
+                Ok(Self::Subject(attributes))
+            }
+            "san" => {
+                let Some((san_type, san_value)) = value.split_once(':') else {
+                    bail!("did:x509 san policy must be type:value");
+                };
+                Ok(Self::San(SanType::from_str(san_type)?, decode(san_value)?))
+            }
+            "eku" => {
+                ObjectIdentifier::new(value)
+                    .map_err(|e| anyhow!("invalid did:x509 EKU OID {value}: {e}"))?;
+                Ok(Self::Eku(value.to_string()))
+            }
+            _ => bail!("unsupported did:x509 policy: {name}"),
+        }
+    }
+}
+
+impl Display for Policy {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Subject(attributes) => {
+                write!(f, "subject")?;
+                for (key, value) in attributes {
+                    write!(f, ":{key}:{}", utf8_percent_encode(value, ENCODE_SET))?;
+                }
+                Ok(())
+            }
+            Self::San(san_type, value) => {
+                write!(f, "san:{san_type}:{}", utf8_percent_encode(value, ENCODE_SET))
+            }
+            Self::Eku(oid) => write!(f, "eku:{oid}"),
+        }
+    }
+}
+
+impl FromStr for SanType {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "email" => Ok(Self::Email),
+            "dns" => Ok(Self::Dns),
+            "uri" => Ok(Self::Uri),
+            _ => Err(anyhow!("unsupported did:x509 san type: {s}")),
+        }
+    }
+}
+
+impl Display for SanType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Email => write!(f, "email"),
+            Self::Dns => write!(f, "dns"),
+            Self::Uri => write!(f, "uri"),
+        }
+    }
+}
+
+/// Construct a `did:x509` from a CA certificate and a set of policies.
+///
+/// # Errors
+/// Will fail if the certificate cannot be encoded or no policies are
+/// provided.
+pub fn did_from_ca(
+    ca: &Certificate, algorithm: FingerprintAlgorithm, policies: &[Policy],
+) -> anyhow::Result<String> {
+    if policies.is_empty() {
+        bail!("did:x509 requires at least one policy");
+    }
+    let fingerprint = Base64UrlUnpadded::encode_string(&algorithm.digest(&ca.to_der()?));
+    let policies = policies.iter().map(ToString::to_string).collect::<Vec<_>>();
+    Ok(format!("did:x509:0:{algorithm}:{fingerprint}::{}", policies.join("::")))
+}
+
+// The OID of a subject name attribute key.
+fn subject_oid(key: &str) -> anyhow::Result<ObjectIdentifier> {
+    let oid = SUBJECT_KEYS.iter().find_map(|(k, oid)| (*k == key).then_some(*oid)).unwrap_or(key);
+    ObjectIdentifier::new(oid).map_err(|_| anyhow!("unsupported did:x509 subject key: {key}"))
+}
+
+// The string value of a subject name attribute. Returns `None` for values
+// that are not strings.
+fn attribute_value(value: &x509_cert::der::Any) -> anyhow::Result<Option<&str>> {
+    match value.tag() {
+        Tag::Utf8String
+        | Tag::PrintableString
+        | Tag::Ia5String
+        | Tag::TeletexString
+        | Tag::VisibleString => Ok(Some(std::str::from_utf8(value.value())?)),
+        _ => Ok(None),
+    }
+}
+
+fn decode(value: &str) -> anyhow::Result<String> {
+    Ok(percent_decode_str(value).decode_utf8()?.into_owned())
+}
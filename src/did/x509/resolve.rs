@@ -0,0 +1,89 @@
+//! Resolution of a DID into a DID document using the `did:x509` method.
+
+use chrono::Utc;
+use x509_cert::ext::pkix::KeyUsage;
+
+use super::DidX509;
+use crate::core::Kind;
+use crate::did::{
+    Document, DocumentBuilder, KeyPurpose, MethodType, PublicKeyFormat, Resource, Url,
+    VerificationMethod, VerificationMethodBuilder, VmKeyId, document_resource,
+};
+use crate::x509::{Certificate, public_key_jwk};
+
+/// Resolve a `did:x509` URL into a [`Resource`] using the certificate chain
+/// presented with the DID.
+///
+/// A URL without a fragment resolves to the full document.
+///
+/// # Errors
+/// If the URL is not a valid `did:x509` URL, the chain does not validate
+/// against the DID or the requested resource is not in the document, an
+/// error is returned.
+pub fn resolve(url: &Url, chain: &[Certificate]) -> anyhow::Result<Resource> {
+    let doc = document(url, chain)?;
+    document_resource(url, None, &doc)
+}
+
+/// Build the DID document for a `did:x509` URL from the certificate chain
+/// presented with the DID.
+///
+/// See [`DidX509::document`].
+///
+/// # Errors
+/// If the URL is not a valid `did:x509` URL or the chain does not validate
+/// against the DID, an error is returned.
+pub fn document(url: &Url, chain: &[Certificate]) -> anyhow::Result<Document> {
+    DidX509::from_url(url)?.document(chain)
+}
+
+impl DidX509 {
+    /// Validate the certificate chain against the DID (see
+    /// [`DidX509::validate`]) at the current time and build the DID document.
+    ///
+    /// The leaf certificate's public key is used as a `JsonWebKey2020`
+    /// verification method with the ID `#key-1`. If the leaf certificate has
+    /// no key usage extension or allows `digitalSignature`, the method is
+    /// referenced by the `authentication` and `assertionMethod`
+    /// relationships. If it has no key usage extension or allows
+    /// `keyAgreement`, the method is referenced by `keyAgreement`.
+    ///
+    /// # Errors
+    /// If the chain does not validate against the DID or the leaf
+    /// certificate's public key is not supported, an error is returned.
+    pub fn document(&self, chain: &[Certificate]) -> anyhow::Result<Document> {
+        self.validate(chain, Utc::now())?;
+        let leaf = &chain[0];
+        let did = self.did();
+
+        let vm = VerificationMethodBuilder::new(&PublicKeyFormat::PublicKeyJwk {
+            public_key_jwk: public_key_jwk(leaf)?,
+        })
+        .key_id(&did, VmKeyId::Index("key-".to_string(), 1))?
+        .method_type(&MethodType::JsonWebKey2020)?
+        .build();
+        let vm_ref = Kind::<VerificationMethod>::String(vm.id.clone());
+
+        let key_usage = leaf.tbs_certificate.get::<KeyUsage>()?.map(|(_, ku)| ku);
+        let mut builder = DocumentBuilder::new(&did)
+            .add_verification_method(&Kind::Object(vm), &KeyPurpose::VerificationMethod)?;
+        if key_usage.as_ref().is_none_or(KeyUsage::digital_signature) {
+            builder = builder
+                .add_verification_method(&vm_ref, &KeyPurpose::Authentication)?
+                .add_verification_method(&vm_ref, &KeyPurpose::AssertionMethod)?;
+        }
+        let mut doc = builder.build();
+
+        // As for `did:jwk`, the key agreement key is the (only) verification
+        // method so is referenced rather than embedded.
+        if key_usage.as_ref().is_none_or(KeyUsage::key_agreement) {
+            doc.key_agreement = Some(vec![vm_ref]);
+        }
+
+        // The document is derived from the certificate chain, never created
+        // or updated, so there is no meaningful metadata.
+        doc.did_document_metadata = None;
+
+        Ok(doc)
+    }
+}
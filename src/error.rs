@@ -0,0 +1,202 @@
+//! # Errors
+//!
+//! Functions in this crate return [`anyhow::Result`] for convenience. Errors
+//! callers may need to act on are raised as an [`Error`], which can be
+//! recovered from the `anyhow::Error` to classify the failure without relying
+//! on the error message:
+//!
+//! ```rust,ignore
+//! match err.downcast_ref::<Error>() {
+//!     Some(Error::LogVerification { kind, version_id, .. }) => {
+//!         // the DID log has been tampered with
+//!     }
+//!     Some(Error::Parse(_)) => {
+//!         // the DID is not valid
+//!     }
+//!     _ => {}
+//! }
+//! ```
+//!
+//! Identity resolvers and custom DID methods can also return a
+//! [`ResolutionError`] directly.
+
+use std::fmt::{self, Display, Formatter};
+
+use crate::did::{ErrorCode, Finding, ResolutionError};
+
+/// Errors raised by this crate.
+///
+/// The error message (`Display`) is the human-readable detail of the error.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Error {
+    /// A DID, DID URL or method-specific identifier is not valid.
+    Parse(String),
+
+    /// DID resolution failed with a standard error code.
+    Resolution(ResolutionError),
+
+    /// A DID log (a `did:webvh` log or a `did:plc` audit log) failed
+    /// verification.
+    LogVerification {
+        /// The kind of verification failure.
+        kind: LogErrorKind,
+
+        /// The version ID of the offending log entry. For `did:plc`, this is
+        /// the CID of the operation. `None` when the failure does not relate
+        /// to a single entry.
+        version_id: Option<String>,
+
+        /// A human-readable explanation of the failure.
+        detail: String,
+    },
+
+    /// A line of a DID log file (such as `did.jsonl`) could not be parsed.
+    LogParse {
+        /// The line number, starting at 1.
+        line: usize,
+
+        /// The version ID of the entry on the line, if it could be read.
+        version_id: Option<String>,
+
+        /// A human-readable explanation of the failure.
+        detail: String,
+    },
+
+    /// A builder was given invalid input.
+    Builder(String),
+
+    /// A resolved DID document or dereferenced resource does not match the
+    /// hashlink (`hl`) of the DID URL.
+    Hashlink(String),
+
+    /// A builder refused to emit a DID document that does not conform to DID
+    /// Core.
+    Conformance(Vec<Finding>),
+}
+
+impl Error {
+    /// Create a log verification error for the log entry with the given
+    /// version ID.
+    #[must_use]
+    pub fn log(
+        kind: LogErrorKind, version_id: impl Into<String>, detail: impl Into<String>,
+    ) -> Self {
+        Self::LogVerification {
+            kind,
+            version_id: Some(version_id.into()),
+            detail: detail.into(),
+        }
+    }
+
+    /// Create a resolution error with the given error code.
+    #[must_use]
+    pub fn resolution(code: ErrorCode, detail: impl Into<String>) -> Self {
+        Self::Resolution(ResolutionError::new(code, detail))
+    }
+
+    /// The version ID of the offending log entry, for log verification and
+    /// log parsing errors.
+    #[must_use]
+    pub fn version_id(&self) -> Option<&str> {
+        match self {
+            Self::LogVerification { version_id, .. } | Self::LogParse { version_id, .. } => {
+                version_id.as_deref()
+            }
+            _ => None,
+        }
+    }
+
+    /// The DID resolution error code best describing the error.
+    #[must_use]
+    pub const fn code(&self) -> ErrorCode {
+        match self {
+            Self::Parse(_) => ErrorCode::InvalidDid,
+            Self::Resolution(re) => re.code(),
+            Self::LogVerification { .. }
+            | Self::LogParse { .. }
+            | Self::Hashlink(_)
+            | Self::Builder(_)
+            | Self::Conformance(_) => ErrorCode::InternalError,
+        }
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Parse(detail)
+            | Self::Builder(detail)
+            | Self::Hashlink(detail)
+            | Self::LogVerification { detail, .. } => f.write_str(detail),
+            Self::Resolution(re) => f.write_str(re.detail()),
+            Self::LogParse {
+                line,
+                version_id,
+                detail,
+            } => match version_id {
+                Some(version_id) => write!(f, "line {line} (version {version_id}): {detail}"),
+                None => write!(f, "line {line}: {detail}"),
+            },
+            Self::Conformance(findings) => {
+                let findings = findings.iter().map(ToString::to_string).collect::<Vec<_>>();
+                write!(f, "document does not conform to DID Core: {}", findings.join("; "))
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<ResolutionError> for Error {
+    fn from(err: ResolutionError) -> Self {
+        Self::Resolution(err)
+    }
+}
+
+/// The kinds of DID log verification failure.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum LogErrorKind {
+    /// The log has no entries.
+    Empty,
+
+    /// A version ID does not have the expected format.
+    VersionId,
+
+    /// Version numbers are not sequential.
+    Sequence,
+
+    /// A log entry's hash does not match its version ID (`did:webvh`) or an
+    /// operation does not match its CID (`did:plc`).
+    Hash,
+
+    /// The SCID does not match the first log entry.
+    Scid,
+
+    /// A version time is in the future or versions are not in time order.
+    VersionTime,
+
+    /// A log entry is for a different DID.
+    Did,
+
+    /// A log entry's proof or signature is missing or invalid, or was made
+    /// with a key that is not authorized to update the DID.
+    Proof,
+
+    /// Update keys do not match the pre-rotation commitment of the previous
+    /// log entry.
+    PreRotation,
+
+    /// Witness proofs do not meet the witness threshold.
+    Witness,
+
+    /// Operations are not correctly chained: a genesis operation is out of
+    /// place, an operation references an unknown operation or follows a
+    /// tombstone.
+    Chain,
+
+    /// An operation nullifies operations it is not permitted to, or the
+    /// log's nullified operations do not match the operation history.
+    Nullification,
+}
@@ -11,11 +11,15 @@
 pub mod core;
 pub mod did;
 pub mod proof;
+pub mod x509;
+
+mod error;
 
 use std::future::Future;
 
 pub use credibil_jose as jose;
 pub use credibil_se as se;
+pub use error::{Error, LogErrorKind};
 use serde::{Deserialize, Serialize};
 
 /// Types of public key material supported by this crate.
@@ -67,10 +71,23 @@ pub trait SignerExt: se::Signer + Send + Sync {
 }
 
 /// Return value from an identity resolver.
+#[allow(clippy::large_enum_variant)]
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize, Eq)]
 pub enum Identity {
     /// A DID document.
     DidDocument(did::Document),
+
+    /// A `did:plc` audit log of signed operations, as returned by a PLC
+    /// directory.
+    PlcLog(Vec<did::plc::LogEntry>),
+
+    /// An X.509 certificate chain, for example fetched from an `x5u` URL or
+    /// decoded from an `x5c` header parameter.
+    X509Chain(x509::Chain),
+
+    /// The content of a file, such as a file published alongside a
+    /// `did:webvh` DID log and identified by a DID URL path.
+    Content(Vec<u8>),
 }
 
 /// [`IdentityResolver`] is used to proxy the resolution of an identity.
@@ -95,4 +112,21 @@ pub trait IdentityResolver: Send + Sync + Clone {
     ///
     /// Returns an error if the URL cannot be resolved.
     fn resolve(&self, url: &str) -> impl Future<Output = anyhow::Result<Identity>> + Send;
+
+    /// Resolve the URL to identity information, taking into account the
+    /// caller's resolution options. For example, a caching resolver should
+    /// bypass its cache when `no_cache` is set.
+    ///
+    /// The default implementation ignores the options and calls
+    /// [`IdentityResolver::resolve`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the URL cannot be resolved.
+    fn resolve_with_options(
+        &self, url: &str, options: &did::ResolutionOptions,
+    ) -> impl Future<Output = anyhow::Result<Identity>> + Send {
+        let _ = options;
+        self.resolve(url)
+    }
 }
@@ -0,0 +1,218 @@
+//! # X.509 Certificates
+//!
+//! Utilities for working with X.509 certificate chains: verifying that each
+//! certificate in a chain is issued by the next, validating a chain against a
+//! set of trust anchors and extracting the public key of a certificate.
+//!
+//! Certificates signed with ECDSA (`P-256`, `P-384` and `secp256k1`),
+//! `Ed25519` and RSA (PKCS #1 v1.5) are supported. Only `Ed25519`, `X25519`,
+//! `P-256`, `P-384` and `secp256k1` public keys can be extracted as JWKs.
+
+use anyhow::{anyhow, bail};
+use chrono::{DateTime, Utc};
+use credibil_jose::PublicKeyJwk;
+use k256::ecdsa::signature::Verifier;
+use p256::elliptic_curve::sec1::ToEncodedPoint;
+use rsa::pkcs1::DecodeRsaPublicKey;
+use sha2::{Sha256, Sha384, Sha512};
+pub use x509_cert::Certificate;
+use x509_cert::der::Encode;
+use x509_cert::der::asn1::ObjectIdentifier;
+use x509_cert::ext::pkix::{BasicConstraints, KeyUsage};
+
+use crate::did::{KeyCodec, Multikey};
+
+mod chain;
+
+pub use chain::{Chain, TrustAnchors, resolve_key};
+
+const EC_PUBLIC_KEY: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.10045.2.1");
+const SECP256R1: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.10045.3.1.7");
+const SECP384R1: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.132.0.34");
+const SECP256K1: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.132.0.10");
+const ED25519: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.101.112");
+const X25519: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.101.110");
+const RSA_ENCRYPTION: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.1.1");
+
+const ECDSA_WITH_SHA256: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.10045.4.3.2");
+const ECDSA_WITH_SHA384: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.10045.4.3.3");
+const SHA256_WITH_RSA: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.1.11");
+const SHA384_WITH_RSA: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.1.12");
+const SHA512_WITH_RSA: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.1.13");
+
+/// Verify a certificate chain.
+///
+/// The chain is ordered from the leaf (end-entity) certificate to the most
+/// senior CA certificate. Each certificate must be issued and signed by the
+/// next certificate in the chain, each issuing certificate must be a CA
+/// certificate whose path length constraint (if any) is satisfied and whose
+/// key usage (if present) allows certificate signing, and every certificate
+/// must be valid at `time`. The signature algorithm of each certificate must
+/// match the algorithm in its signed content (RFC 5280 §4.1.1.2).
+///
+/// The last certificate is not checked against a trust anchor: callers are
+/// responsible for establishing trust in the chain (see [`TrustAnchors`]).
+///
+/// # Errors
+/// Will fail if the chain is empty or any of the checks fails.
+pub fn verify_chain(chain: &[Certificate], time: DateTime<Utc>) -> anyhow::Result<()> {
+    if chain.is_empty() {
+        bail!("certificate chain is empty");
+    }
+    for cert in chain {
+        check_validity(cert, time)?;
+        if cert.signature_algorithm != cert.tbs_certificate.signature {
+            bail!(
+                "certificate {} signature algorithm does not match its signed content",
+                cert.tbs_certificate.subject
+            );
+        }
+    }
+    for (intermediates, pair) in chain.windows(2).enumerate() {
+        let (cert, issuer) = (&pair[0], &pair[1]);
+        if cert.tbs_certificate.issuer != issuer.tbs_certificate.subject {
+            bail!(
+                "certificate {} is not issued by {}",
+                cert.tbs_certificate.subject,
+                issuer.tbs_certificate.subject
+            );
+        }
+        let constraints = issuer.tbs_certificate.get::<BasicConstraints>()?.map(|(_, bc)| bc);
+        let Some(constraints) = constraints.filter(|bc| bc.ca) else {
+            bail!("issuing certificate {} is not a CA", issuer.tbs_certificate.subject);
+        };
+
+        // The number of intermediate CA certificates between the issuer and
+        // the leaf may not exceed the issuer's path length constraint.
+        if constraints.path_len_constraint.is_some_and(|max| intermediates > usize::from(max)) {
+            bail!(
+                "issuing certificate {} path length constraint exceeded",
+                issuer.tbs_certificate.subject
+            );
+        }
+
+        // RFC 5280 §6.1.4 (n): a key usage extension must allow signing
+        // certificates.
+        let key_usage = issuer.tbs_certificate.get::<KeyUsage>()?.map(|(_, ku)| ku);
+        if key_usage.is_some_and(|ku| !ku.key_cert_sign()) {
+            bail!(
+                "issuing certificate {} key usage does not include keyCertSign",
+                issuer.tbs_certificate.subject
+            );
+        }
+        verify_signature(cert, issuer)?;
+    }
+    Ok(())
+}
+
+/// The public key of a certificate.
+///
+/// # Errors
+/// Will fail if the key type is not supported.
+pub fn public_key(cert: &Certificate) -> anyhow::Result<Multikey> {
+    let spki = &cert.tbs_certificate.subject_public_key_info;
+    let Some(key) = spki.subject_public_key.as_bytes() else {
+        bail!("certificate public key is not octet-aligned");
+    };
+
+    let oid = spki.algorithm.oid;
+    if oid == ED25519 {
+        return Multikey::new(KeyCodec::Ed25519, key);
+    }
+    if oid == X25519 {
+        return Multikey::new(KeyCodec::X25519, key);
+    }
+    if oid != EC_PUBLIC_KEY {
+        bail!("unsupported certificate public key algorithm: {oid}");
+    }
+
+    let curve = spki
+        .algorithm
+        .parameters
+        .as_ref()
+        .ok_or_else(|| anyhow!("certificate EC public key has no named curve"))?
+        .decode_as::<ObjectIdentifier>()?;
+    let invalid = |_| anyhow!("invalid certificate EC public key");
+    let (codec, point) = if curve == SECP256R1 {
+        let pk = p256::PublicKey::from_sec1_bytes(key).map_err(invalid)?;
+        (KeyCodec::P256, pk.to_encoded_point(true).as_bytes().to_vec())
+    } else if curve == SECP384R1 {
+        let pk = p384::PublicKey::from_sec1_bytes(key).map_err(invalid)?;
+        (KeyCodec::P384, pk.to_encoded_point(true).as_bytes().to_vec())
+    } else if curve == SECP256K1 {
+        let pk = k256::PublicKey::from_sec1_bytes(key).map_err(invalid)?;
+        (KeyCodec::Secp256k1, pk.to_encoded_point(true).as_bytes().to_vec())
+    } else {
+        bail!("unsupported certificate EC curve: {curve}");
+    };
+    Multikey::new(codec, &point)
+}
+
+/// The public key of a certificate as a JWK.
+///
+/// # Errors
+/// Will fail if the key type is not supported.
+pub fn public_key_jwk(cert: &Certificate) -> anyhow::Result<PublicKeyJwk> {
+    public_key(cert)?.to_jwk()
+}
+
+// Check the certificate is valid at the given time.
+fn check_validity(cert: &Certificate, time: DateTime<Utc>) -> anyhow::Result<()> {
+    let validity = &cert.tbs_certificate.validity;
+    let not_before = validity.not_before.to_unix_duration();
+    let not_after = validity.not_after.to_unix_duration();
+    let Ok(now) = u64::try_from(time.timestamp()) else {
+        bail!("validation time is before the Unix epoch");
+    };
+    if now < not_before.as_secs() || now > not_after.as_secs() {
+        bail!("certificate {} is not valid at {time}", cert.tbs_certificate.subject);
+    }
+    Ok(())
+}
+
+// Verify the certificate's signature using the issuer's public key.
+fn verify_signature(cert: &Certificate, issuer: &Certificate) -> anyhow::Result<()> {
+    let msg = cert.tbs_certificate.to_der()?;
+    let Some(sig) = cert.signature.as_bytes() else {
+        bail!("certificate signature is not octet-aligned");
+    };
+    let alg = cert.signature_algorithm.oid;
+    let spki = &issuer.tbs_certificate.subject_public_key_info;
+
+    if spki.algorithm.oid == RSA_ENCRYPTION {
+        let Some(key) = spki.subject_public_key.as_bytes() else {
+            bail!("issuer public key is not octet-aligned");
+        };
+        let key = rsa::RsaPublicKey::from_pkcs1_der(key)?;
+        let sig = rsa::pkcs1v15::Signature::try_from(sig)?;
+        let verified = match alg {
+            SHA256_WITH_RSA => rsa::pkcs1v15::VerifyingKey::<Sha256>::new(key).verify(&msg, &sig),
+            SHA384_WITH_RSA => rsa::pkcs1v15::VerifyingKey::<Sha384>::new(key).verify(&msg, &sig),
+            SHA512_WITH_RSA => rsa::pkcs1v15::VerifyingKey::<Sha512>::new(key).verify(&msg, &sig),
+            _ => bail!("unsupported signature algorithm for RSA key: {alg}"),
+        };
+        return verified.map_err(|_| anyhow!("invalid certificate signature"));
+    }
+
+    let key = public_key(issuer)?;
+    let invalid = |_| anyhow!("invalid certificate signature");
+    match (key.codec(), alg) {
+        (KeyCodec::P256, ECDSA_WITH_SHA256) => {
+            let vk = p256::ecdsa::VerifyingKey::from_sec1_bytes(key.key()).map_err(invalid)?;
+            let sig = p256::ecdsa::Signature::from_der(sig).map_err(invalid)?;
+            vk.verify(&msg, &sig).map_err(invalid)
+        }
+        (KeyCodec::P384, ECDSA_WITH_SHA384) => {
+            let vk = p384::ecdsa::VerifyingKey::from_sec1_bytes(key.key()).map_err(invalid)?;
+            let sig = p384::ecdsa::Signature::from_der(sig).map_err(invalid)?;
+            vk.verify(&msg, &sig).map_err(invalid)
+        }
+        (KeyCodec::Secp256k1, ECDSA_WITH_SHA256) => {
+            let vk = k256::ecdsa::VerifyingKey::from_sec1_bytes(key.key()).map_err(invalid)?;
+            let sig = k256::ecdsa::Signature::from_der(sig).map_err(invalid)?;
+            vk.verify(&msg, &sig).map_err(invalid)
+        }
+        (KeyCodec::Ed25519, ED25519) => key.to_jwk()?.verify_bytes(&msg, sig),
+        (codec, alg) => bail!("unsupported signature algorithm {alg} for {codec} key"),
+    }
+}
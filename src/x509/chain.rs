@@ -0,0 +1,236 @@
+//! # Certificate Chains
+//!
+//! A [`Chain`] is X.509 identity material returned by an identity resolver as
+//! [`Identity::X509Chain`]. Before the leaf certificate's key is trusted, the
+//! chain should be validated against a set of [`TrustAnchors`].
+
+use anyhow::{anyhow, bail};
+use base64ct::{Base64, Encoding};
+use chrono::{DateTime, Utc};
+use credibil_jose::PublicKeyJwk;
+use serde::de::Error as _;
+use serde::ser::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use x509_cert::Certificate;
+use x509_cert::der::{Decode, Encode};
+
+use super::{public_key_jwk, verify_chain};
+use crate::{Identity, IdentityResolver, Key};
+
+/// A certificate chain, ordered from the leaf (end-entity) certificate to the
+/// most senior CA certificate.
+///
+/// Serializes to an array of base64-encoded DER certificates, the same
+/// representation as the JOSE `x5c` header parameter.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Chain(Vec<Certificate>);
+
+impl Chain {
+    /// Create a chain from certificates ordered from leaf to most senior CA.
+    ///
+    /// # Errors
+    /// Will fail if there are no certificates.
+    pub fn new(certificates: Vec<Certificate>) -> anyhow::Result<Self> {
+        if certificates.is_empty() {
+            bail!("certificate chain is empty");
+        }
+        Ok(Self(certificates))
+    }
+
+    /// Parse a chain from one or more concatenated PEM-encoded certificates.
+    ///
+    /// # Errors
+    /// Will fail if the PEM cannot be parsed or contains no certificates.
+    pub fn from_pem(pem: impl AsRef<[u8]>) -> anyhow::Result<Self> {
+        Self::new(load_pem(pem.as_ref())?)
+    }
+
+    /// Parse a chain from DER-encoded certificates.
+    ///
+    /// # Errors
+    /// Will fail if any certificate cannot be parsed or there are no
+    /// certificates.
+    pub fn from_der(der: &[impl AsRef<[u8]>]) -> anyhow::Result<Self> {
+        let certificates = der
+            .iter()
+            .map(|cert| Certificate::from_der(cert.as_ref()))
+            .collect::<Result<Vec<_>, _>>()?;
+        Self::new(certificates)
+    }
+
+    /// Parse a chain from the value of a JOSE `x5c` header parameter: an
+    /// array of base64-encoded (not base64url) DER certificates.
+    ///
+    /// # Errors
+    /// Will fail if any certificate cannot be decoded or parsed or there are no
+    /// certificates.
+    pub fn from_x5c(x5c: &[impl AsRef<str>]) -> anyhow::Result<Self> {
+        let der = x5c
+            .iter()
+            .map(|cert| Base64::decode_vec(cert.as_ref()))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| anyhow!("invalid x5c certificate encoding: {e}"))?;
+        Self::from_der(&der)
+    }
+
+    /// Encode the chain as the value of a JOSE `x5c` header parameter.
+    ///
+    /// # Errors
+    /// Will fail if a certificate cannot be DER-encoded.
+    pub fn to_x5c(&self) -> anyhow::Result<Vec<String>> {
+        self.0.iter().map(|cert| Ok(Base64::encode_string(&cert.to_der()?))).collect()
+    }
+
+    /// The certificates in the chain, ordered from leaf to most senior CA.
+    #[must_use]
+    pub fn certificates(&self) -> &[Certificate] {
+        &self.0
+    }
+
+    /// The leaf (end-entity) certificate.
+    #[must_use]
+    pub fn leaf(&self) -> &Certificate {
+        &self.0[0]
+    }
+
+    /// The public key of the leaf certificate as a JWK.
+    ///
+    /// The key should only be trusted once the chain has been validated (see
+    /// [`TrustAnchors::verify`]).
+    ///
+    /// # Errors
+    /// Will fail if the key type is not supported.
+    pub fn public_key_jwk(&self) -> anyhow::Result<PublicKeyJwk> {
+        public_key_jwk(self.leaf())
+    }
+}
+
+impl Serialize for Chain {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_x5c().map_err(S::Error::custom)?.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Chain {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let x5c = Vec::<String>::deserialize(deserializer)?;
+        Self::from_x5c(&x5c).map_err(D::Error::custom)
+    }
+}
+
+/// The set of CA certificates trusted to issue certificate chains.
+#[derive(Clone, Debug, Default)]
+pub struct TrustAnchors(Vec<Certificate>);
+
+impl TrustAnchors {
+    /// Create an empty set of trust anchors.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self(Vec::new())
+    }
+
+    /// Parse trust anchors from one or more concatenated PEM-encoded
+    /// certificates.
+    ///
+    /// # Errors
+    /// Will fail if the PEM cannot be parsed.
+    pub fn from_pem(pem: impl AsRef<[u8]>) -> anyhow::Result<Self> {
+        Ok(Self(load_pem(pem.as_ref())?))
+    }
+
+    /// Add a trust anchor to the set.
+    #[must_use]
+    pub fn add_anchor(mut self, anchor: Certificate) -> Self {
+        self.0.push(anchor);
+        self
+    }
+
+    /// The trusted CA certificates.
+    #[must_use]
+    pub fn certificates(&self) -> &[Certificate] {
+        &self.0
+    }
+
+    /// Validate a certificate chain at `time`.
+    ///
+    /// The chain is verified (see [`verify_chain`]) and must end at a trust
+    /// anchor: either the most senior certificate in the chain is itself a
+    /// trust anchor, or it is issued by one. In the latter case, the trust
+    /// anchor is verified as part of the certification path.
+    ///
+    /// # Errors
+    /// Will fail if the chain does not verify or does not end at a trust
+    /// anchor.
+    pub fn verify(&self, chain: &Chain, time: DateTime<Utc>) -> anyhow::Result<()> {
+        if self.0.is_empty() {
+            bail!("no trust anchors configured");
+        }
+        verify_chain(chain.certificates(), time)?;
+
+        let Some(top) = chain.0.last() else {
+            bail!("certificate chain is empty");
+        };
+        if self.0.contains(top) {
+            return Ok(());
+        }
+
+        let mut rejected = None;
+        for anchor in
+            self.0.iter().filter(|a| a.tbs_certificate.subject == top.tbs_certificate.issuer)
+        {
+            let mut path = chain.0.clone();
+            path.push(anchor.clone());
+            match verify_chain(&path, time) {
+                Ok(()) => return Ok(()),
+                Err(e) => rejected = Some(e),
+            }
+        }
+        Err(rejected.unwrap_or_else(|| {
+            anyhow!("certificate {} is not issued by a trust anchor", top.tbs_certificate.subject)
+        }))
+    }
+}
+
+impl From<Vec<Certificate>> for TrustAnchors {
+    fn from(anchors: Vec<Certificate>) -> Self {
+        Self(anchors)
+    }
+}
+
+/// Resolve a key ID referencing X.509 certificate material to the public key
+/// of the leaf certificate.
+///
+/// The key ID (for example, an `x5u` URL) is passed to the resolver, which
+/// should return the certificate chain as an [`Identity::X509Chain`]. How the
+/// chain is obtained is up to the resolver: it may fetch PEM-encoded
+/// certificates from the `x5u` URL or decode an `x5c` header parameter
+/// presented with the key ID (see [`Chain::from_x5c`]).
+///
+/// The chain is validated against the trust anchors at `time` (usually now,
+/// or the time a signature was made) before the leaf certificate's key is
+/// returned.
+///
+/// # Errors
+/// Will fail if the key is not a key ID, the resolver does not return a
+/// certificate chain or the chain does not validate.
+pub async fn resolve_key(
+    key: &Key, resolver: &impl IdentityResolver, anchors: &TrustAnchors, time: DateTime<Utc>,
+) -> anyhow::Result<PublicKeyJwk> {
+    let Key::KeyId(kid) = key else {
+        bail!("key does not reference a certificate chain");
+    };
+    let Identity::X509Chain(chain) = resolver.resolve(kid).await? else {
+        bail!("resolver did not return a certificate chain");
+    };
+    anchors.verify(&chain, time)?;
+    chain.public_key_jwk()
+}
+
+// Parse concatenated PEM-encoded certificates, allowing for input without any
+// certificates (which `Certificate::load_pem_chain` does not).
+fn load_pem(pem: &[u8]) -> anyhow::Result<Vec<Certificate>> {
+    if pem.iter().all(u8::is_ascii_whitespace) {
+        return Ok(vec![]);
+    }
+    Ok(Certificate::load_pem_chain(pem)?)
+}
@@ -0,0 +1,71 @@
+//! Resolvers and documents shared by the integration tests.
+
+#![allow(dead_code)]
+
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail};
+use credibil_identity::did::{Document, ErrorCode, ResolutionError, Url};
+use credibil_identity::{Identity, IdentityResolver};
+use serde_json::json;
+
+// The Ed25519 public key of the verification method in `document`.
+pub const SIGNING_KEY: &str = "z6MkhaXgBZDvotDkL5257faiztiGiC2QtKLGpbnnEGta2doK";
+
+// For self-resolving methods (such as `did:key`), where the resolver should
+// never be called.
+#[derive(Clone)]
+pub struct NoopResolver;
+
+impl IdentityResolver for NoopResolver {
+    async fn resolve(&self, url: &str) -> anyhow::Result<Identity> {
+        bail!("resolver should not be called for {url}")
+    }
+}
+
+// Serves `did:web` documents from memory. Any other URL is not found.
+#[derive(Clone, Default)]
+pub struct WebResolver {
+    documents: Vec<Document>,
+}
+
+impl WebResolver {
+    pub fn new(documents: impl IntoIterator<Item = Document>) -> Self {
+        Self {
+            documents: documents.into_iter().collect(),
+        }
+    }
+}
+
+impl IdentityResolver for WebResolver {
+    async fn resolve(&self, url: &str) -> anyhow::Result<Identity> {
+        let served =
+            |doc: &&Document| Url::from_str(&doc.id).is_ok_and(|did| did.to_web_http() == url);
+        let Some(doc) = self.documents.iter().find(served) else {
+            let detail = format!("no DID at {url}");
+            return Err(anyhow!(ResolutionError::new(ErrorCode::NotFound, detail)));
+        };
+        Ok(Identity::DidDocument(doc.clone()))
+    }
+}
+
+// A DID document with a single Multikey verification method (`#key-1`) and a
+// `#files` service.
+pub fn document(did: &str) -> Document {
+    serde_json::from_value(json!({
+        "@context": ["https://www.w3.org/ns/did/v1", "https://w3id.org/security/multikey/v1"],
+        "id": did,
+        "verificationMethod": [{
+            "id": format!("{did}#key-1"),
+            "type": "Multikey",
+            "controller": did,
+            "publicKeyMultibase": SIGNING_KEY
+        }],
+        "service": [{
+            "id": format!("{did}#files"),
+            "type": "LinkedResource",
+            "serviceEndpoint": "https://example.com/files/"
+        }]
+    }))
+    .expect("should deserialize")
+}
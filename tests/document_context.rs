@@ -0,0 +1,151 @@
+//! Tests for computing the `@context` of a DID document from the verification
+//! method and service types it contains.
+
+use credibil_identity::core::Kind;
+use credibil_identity::did::webvh::{CreateBuilder, UpdateBuilder, default_did};
+use credibil_identity::did::{
+    DID_CONTEXT, Document, DocumentBuilder, KeyPurpose, MethodType, PublicKeyFormat,
+    ServiceBuilder, VerificationMethodBuilder, VmKeyId,
+};
+use kms::Keyring;
+use serde_json::json;
+
+const DID: &str = "did:web:example.com";
+const KEY: &str = "z6MkhaXgBZDvotDkL5257faiztiGiC2QtKLGpbnnEGta2doK";
+
+fn contexts(context: &[Kind<serde_json::Value>]) -> Vec<String> {
+    context
+        .iter()
+        .map(|c| match c {
+            Kind::String(s) => s.clone(),
+            Kind::Object(o) => o.to_string(),
+        })
+        .collect()
+}
+
+// Contexts are added for the types used in the document and pruned when
+// they are no longer used.
+#[test]
+fn computed_context() {
+    let vm = |name: &str, method_type: &MethodType| {
+        let vm = VerificationMethodBuilder::new(&PublicKeyFormat::PublicKeyMultibase {
+            public_key_multibase: KEY.to_string(),
+        })
+        .key_id(DID, VmKeyId::Fragment(name.to_string()))
+        .expect("should apply key ID")
+        .method_type(method_type)
+        .expect("should apply method type")
+        .build();
+        Kind::Object(vm)
+    };
+    let service = ServiceBuilder::new(&format!("{DID}#domains"))
+        .service_type(&"LinkedDomains")
+        .endpoint_str(&"https://example.com")
+        .build();
+
+    let doc = DocumentBuilder::new(DID).build();
+    assert_eq!(contexts(&doc.context), [DID_CONTEXT]);
+
+    let custom = json!({ "nickname": "https://example.com/vocab#nickname" });
+    let doc = DocumentBuilder::new(DID)
+        .add_context(&Kind::Object(custom.clone()))
+        .add_verification_method(
+            &vm("key-1", &MethodType::Ed25519VerificationKey2020),
+            &KeyPurpose::VerificationMethod,
+        )
+        .expect("should add verification method")
+        .add_verification_method(&vm("key-2", &MethodType::Multikey), &KeyPurpose::Authentication)
+        .expect("should add verification method")
+        .add_service(&service)
+        .build();
+    assert_eq!(
+        contexts(&doc.context),
+        [
+            DID_CONTEXT,
+            &custom.to_string(),
+            "https://w3id.org/security/suites/ed25519-2020/v1",
+            "https://w3id.org/security/multikey/v1",
+            "https://identity.foundation/.well-known/did-configuration/v1",
+        ]
+    );
+
+    // removing the Ed25519 key and the service prunes their contexts
+    let doc = DocumentBuilder::from(&doc)
+        .remove_verification_method(&format!("{DID}#key-1"))
+        .expect("should remove verification method")
+        .remove_service(&format!("{DID}#domains"))
+        .expect("should remove service")
+        .build();
+    assert_eq!(
+        contexts(&doc.context),
+        [DID_CONTEXT, &custom.to_string(), "https://w3id.org/security/multikey/v1"]
+    );
+}
+
+// Unknown contexts are kept and the DID context is always first.
+#[test]
+fn kept_context() {
+    let other = Kind::String("https://example.com/context/v1".to_string());
+    let jws = Kind::String("https://w3id.org/security/suites/jws-2020/v1".to_string());
+    let doc = DocumentBuilder::new(DID)
+        .add_context(&other)
+        .add_context(&jws)
+        .add_context(&Kind::String(DID_CONTEXT.to_string()))
+        .build();
+    assert_eq!(contexts(&doc.context), [DID_CONTEXT, "https://example.com/context/v1"]);
+}
+
+// A document updated without the document builder has its context brought
+// into line with its content.
+#[tokio::test]
+async fn webvh_update_context() {
+    const JWS_2020: &str = "https://w3id.org/security/suites/jws-2020/v1";
+
+    let mut signer = Keyring::new("document_context_webvh").await.expect("should create keyring");
+    let update_multi = signer.multibase("signing").await.expect("should get multibase key");
+
+    let did = default_did("https://example.com").expect("should get default DID");
+    let doc: Document = serde_json::from_value(json!({
+        "@context": [DID_CONTEXT],
+        "id": did,
+        "verificationMethod": [{
+            "id": format!("{did}#key-1"),
+            "type": "JsonWebKey2020",
+            "controller": did,
+            "publicKeyJwk": {
+                "kty": "OKP",
+                "crv": "Ed25519",
+                "x": "11qYAYKxCrfVS_7TyWQHOg7hcvPapiMlrwIaaPcHURo"
+            }
+        }]
+    }))
+    .expect("should deserialize");
+    let created = CreateBuilder::new()
+        .document(&doc)
+        .expect("should apply document")
+        .update_keys(&[&update_multi])
+        .expect("should apply update keys")
+        .signer(&signer)
+        .build()
+        .await
+        .expect("should build document");
+    assert_eq!(contexts(&created.document.context), [DID_CONTEXT, JWS_2020]);
+
+    // remove the JsonWebKey2020 method directly, leaving its context behind
+    let mut doc = created.document.clone();
+    doc.verification_method = None;
+    assert_eq!(contexts(&doc.context), [DID_CONTEXT, JWS_2020]);
+
+    let updated = UpdateBuilder::from(created.log.as_slice(), None)
+        .await
+        .expect("should create builder")
+        .document(&doc)
+        .expect("should apply document")
+        .signer(&signer)
+        .build()
+        .await
+        .expect("should build document");
+    assert_eq!(contexts(&updated.document.context), [DID_CONTEXT]);
+    let logged = updated.log.last().expect("should have log entry");
+    assert_eq!(contexts(&logged.state.context), [DID_CONTEXT]);
+}
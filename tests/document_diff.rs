@@ -0,0 +1,262 @@
+//! Tests for comparing DID documents and for generating and applying JSON
+//! Patches between them.
+
+use credibil_identity::did::webvh::{CreateBuilder, UpdateBuilder, default_did};
+use credibil_identity::did::{
+    Document, DocumentBuilder, KeyPurpose, PatchOperation, ServiceBuilder, SetChanges, apply_patch,
+};
+use kms::Keyring;
+use serde_json::json;
+
+const DID: &str = "did:web:example.com";
+const KEY: &str = "z6MkhaXgBZDvotDkL5257faiztiGiC2QtKLGpbnnEGta2doK";
+
+fn document(value: serde_json::Value) -> Document {
+    serde_json::from_value(value).expect("should deserialize")
+}
+
+fn method(id: &str) -> serde_json::Value {
+    json!({ "id": id, "type": "Multikey", "controller": DID, "publicKeyMultibase": KEY })
+}
+
+// Array order, relative IDs, metadata and context are not differences.
+#[test]
+fn unchanged() {
+    let previous = document(json!({
+        "@context": ["https://www.w3.org/ns/did/v1"],
+        "id": DID,
+        "controller": ["did:example:a", "did:example:b"],
+        "verificationMethod": [method("#key-1"), method("#key-2")],
+        "authentication": ["#key-1", format!("{DID}#key-2")],
+        "service": [{
+            "id": "#files",
+            "type": "relativeRef",
+            "serviceEndpoint": ["https://a.example.com", "https://b.example.com"]
+        }],
+        "didDocumentMetadata": { "created": "2025-01-01T00:00:00Z" }
+    }));
+    let next = document(json!({
+        "id": DID,
+        "controller": ["did:example:b", "did:example:a"],
+        "verificationMethod": [method(&format!("{DID}#key-2")), method(&format!("{DID}#key-1"))],
+        "authentication": ["#key-2", "#key-1"],
+        "service": [{
+            "id": format!("{DID}#files"),
+            "type": "relativeRef",
+            "serviceEndpoint": ["https://b.example.com", "https://a.example.com"]
+        }],
+        "didDocumentMetadata": { "created": "2025-06-01T00:00:00Z" }
+    }));
+    let diff = previous.diff(&next);
+    assert!(diff.is_empty(), "unexpected diff: {diff:?}");
+}
+
+// Each kind of change is reported.
+#[test]
+fn changed() {
+    let previous = document(json!({
+        "id": DID,
+        "alsoKnownAs": ["https://example.com"],
+        "controller": "did:example:a",
+        "verificationMethod": [method("#key-1"), method("#key-2")],
+        "authentication": ["#key-1"],
+        "service": [
+            { "id": "#files", "type": "relativeRef", "serviceEndpoint": "https://a.example.com" },
+            { "id": "#old", "type": "relativeRef", "serviceEndpoint": "https://old.example.com" }
+        ]
+    }));
+    let next = document(json!({
+        "id": DID,
+        "alsoKnownAs": ["https://example.com", "https://example.org"],
+        "controller": "did:example:b",
+        "verificationMethod": [method("#key-1")],
+        "authentication": ["#key-1"],
+        "keyAgreement": [{
+            "id": "#key-3",
+            "type": "X25519KeyAgreementKey2020",
+            "controller": DID,
+            "publicKeyMultibase": "z6LSbysY2xFMRpGMhb7tFTLMpeuPRaqaWM1yECx2AtzE3KCc"
+        }],
+        "assertionMethod": ["#key-1"],
+        "service": [
+            { "id": "#files", "type": "relativeRef", "serviceEndpoint": "https://b.example.com" }
+        ]
+    }));
+
+    let diff = previous.diff(&next);
+    assert_eq!(diff.id, None);
+    assert_eq!(diff.also_known_as.added, ["https://example.org"]);
+    assert_eq!(diff.also_known_as.removed, Vec::<String>::new());
+    assert_eq!(
+        diff.controllers,
+        SetChanges {
+            added: vec!["did:example:b".to_string()],
+            removed: vec!["did:example:a".to_string()],
+        }
+    );
+
+    let ids = |vms: &[credibil_identity::did::VerificationMethod]| {
+        vms.iter().map(|vm| vm.id.clone()).collect::<Vec<_>>()
+    };
+    assert_eq!(ids(&diff.verification_methods.added), ["#key-3"]);
+    assert_eq!(ids(&diff.verification_methods.removed), ["#key-2"]);
+    assert_eq!(diff.verification_methods.changed, []);
+
+    let relationships = diff
+        .relationships
+        .iter()
+        .map(|(purpose, changes)| (purpose.clone(), changes.added.clone()));
+    assert_eq!(
+        relationships.collect::<Vec<_>>(),
+        [
+            (KeyPurpose::AssertionMethod, vec![format!("{DID}#key-1")]),
+            (KeyPurpose::KeyAgreement, vec![format!("{DID}#key-3")]),
+        ]
+    );
+
+    assert_eq!(diff.services.added, []);
+    assert_eq!(diff.services.removed.len(), 1);
+    assert_eq!(diff.services.removed[0].id, "#old");
+    assert_eq!(diff.services.changed.len(), 1);
+    let (before, after) = &diff.services.changed[0];
+    assert_eq!(
+        before.service_endpoint,
+        previous.service.as_ref().expect("services")[0].service_endpoint
+    );
+    assert_eq!(
+        after.service_endpoint,
+        next.service.as_ref().expect("services")[0].service_endpoint
+    );
+}
+
+// A generated patch turns one document into the other.
+#[test]
+fn patch_documents() {
+    let previous = document(json!({
+        "id": DID,
+        "verificationMethod": [method("#key-1"), method("#key-2")],
+        "authentication": ["#key-1", "#key-2"],
+        "nickname": "old",
+        "didDocumentMetadata": { "created": "2025-01-01T00:00:00Z" }
+    }));
+    let next = document(json!({
+        "id": DID,
+        "verificationMethod": [method("#key-1")],
+        "authentication": ["#key-1"],
+        "alsoKnownAs": ["https://example.com/a/b~c"]
+    }));
+
+    let patch = previous.patch_to(&next).expect("should generate patch");
+    assert_eq!(
+        serde_json::to_value(&patch).expect("should serialize"),
+        json!([
+            { "op": "remove", "path": "/authentication/1" },
+            { "op": "remove", "path": "/nickname" },
+            { "op": "remove", "path": "/verificationMethod/1" },
+            { "op": "add", "path": "/alsoKnownAs", "value": ["https://example.com/a/b~c"] }
+        ])
+    );
+
+    // metadata is kept
+    let patched = previous.apply_patch(&patch).expect("should apply patch");
+    assert_eq!(patched.did_document_metadata, previous.did_document_metadata);
+    assert_eq!(
+        patched,
+        Document {
+            did_document_metadata: previous.did_document_metadata.clone(),
+            ..next
+        }
+    );
+    assert_eq!(previous.patch_to(&previous).expect("should generate patch"), []);
+}
+
+// Every RFC 6902 operation can be applied, and a failed operation leaves the
+// value unchanged.
+#[test]
+fn apply_operations() {
+    let mut value = json!({ "a/b": { "c~d": [1, 2] }, "e": "f" });
+    let patch: Vec<PatchOperation> = serde_json::from_value(json!([
+        { "op": "test", "path": "/a~1b/c~0d/1", "value": 2 },
+        { "op": "add", "path": "/a~1b/c~0d/0", "value": 0 },
+        { "op": "add", "path": "/a~1b/c~0d/-", "value": 3 },
+        { "op": "copy", "from": "/e", "path": "/g" },
+        { "op": "move", "from": "/e", "path": "/h" },
+        { "op": "replace", "path": "/g", "value": "i" },
+        { "op": "remove", "path": "/a~1b/c~0d/1" }
+    ]))
+    .expect("should deserialize");
+    apply_patch(&mut value, &patch).expect("should apply patch");
+    assert_eq!(value, json!({ "a/b": { "c~d": [0, 2, 3] }, "g": "i", "h": "f" }));
+
+    let original = value.clone();
+    for op in [
+        json!({ "op": "test", "path": "/g", "value": "x" }),
+        json!({ "op": "remove", "path": "/missing" }),
+        json!({ "op": "replace", "path": "/a~1b/c~0d/3", "value": 4 }),
+        json!({ "op": "add", "path": "/a~1b/c~0d/01", "value": 4 }),
+        json!({ "op": "move", "from": "/a~1b", "path": "/a~1b/x" }),
+        json!({ "op": "add", "path": "g", "value": 4 }),
+    ] {
+        let patch = vec![
+            PatchOperation::Remove {
+                path: "/h".to_string(),
+            },
+            serde_json::from_value(op).expect("should deserialize"),
+        ];
+        apply_patch(&mut value, &patch).expect_err("should fail");
+        assert_eq!(value, original);
+    }
+}
+
+// Consecutive `did:webvh` log entries can be compared, and the patch between
+// them applied with the document builder.
+#[tokio::test]
+async fn webvh_log() {
+    let mut signer = Keyring::new("document_diff").await.expect("should create keyring");
+    let update_multi = signer.multibase("signing").await.expect("should get multibase key");
+    let did = default_did("https://credibil.io/issuers/example").expect("should get default DID");
+    let doc = DocumentBuilder::new(&did).build();
+    let created = CreateBuilder::new()
+        .document(&doc)
+        .expect("should apply document")
+        .update_keys(&[&update_multi])
+        .expect("should apply update keys")
+        .signer(&signer)
+        .build()
+        .await
+        .expect("should build document");
+
+    let service = ServiceBuilder::new(&format!("{}#files", created.did))
+        .service_type(&"relativeRef")
+        .endpoint_str(&"https://cdn.credibil.io/example/")
+        .build();
+    let doc = DocumentBuilder::from(&created.document)
+        .also_known_as("https://credibil.io/issuers/example")
+        .add_service(&service)
+        .build();
+    let updated = UpdateBuilder::from(created.log.as_slice(), None)
+        .await
+        .expect("should create builder")
+        .document(&doc)
+        .expect("should apply document")
+        .signer(&signer)
+        .build()
+        .await
+        .expect("should build document");
+
+    let (first, second) = (&updated.log[0].state, &updated.log[1].state);
+    let diff = first.diff(second);
+    assert_eq!(diff.also_known_as.added, ["https://credibil.io/issuers/example"]);
+    assert_eq!(diff.services.added, [service]);
+    assert!(diff.verification_methods.is_empty());
+
+    let patch = first.patch_to(second).expect("should generate patch");
+    let next = DocumentBuilder::from(first).patch(&patch).expect("should apply patch").build();
+    assert!(next.diff(second).is_empty());
+    assert_eq!(next.patch_to(second).expect("should generate patch"), []);
+
+    let invalid = [PatchOperation::Remove {
+        path: "/id".to_string(),
+    }];
+    DocumentBuilder::from(first).patch(&invalid).expect_err("should fail");
+}
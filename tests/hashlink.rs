@@ -0,0 +1,117 @@
+//! Tests for verifying the integrity of resolved documents and dereferenced
+//! resources using the hashlink (`hl`) DID URL parameter.
+
+mod common;
+
+use std::str::FromStr;
+
+use credibil_identity::Error;
+use credibil_identity::did::{
+    DocumentMetadataBuilder, ErrorCode, Registry, ResolutionOptions, Resource, Url, dereference,
+    hashlink, resolve_did, verify_hashlink,
+};
+
+use common::{WebResolver, document};
+
+const DID: &str = "did:web:credibil.io";
+
+// A hashlink pins the resolved document.
+#[tokio::test]
+async fn pinned_document() {
+    let doc = document(DID);
+    let resolver = WebResolver::new([doc.clone()]);
+
+    let url = doc.hashlink_url().expect("should generate URL");
+    let hl = doc.hashlink().expect("should generate hashlink");
+    assert_eq!(url, format!("{DID}?hl={hl}"));
+
+    let result = resolve_did(&url, None, &resolver).await;
+    assert!(result.error_code().is_none());
+    let resource = dereference(&url, None, &resolver).await.expect("should dereference");
+    assert!(matches!(resource, Resource::Document(_)));
+
+    // resources found in the pinned document are verified against it
+    let key_url = format!("{url}#key-1");
+    let resource = dereference(&key_url, None, &resolver).await.expect("should dereference");
+    assert!(matches!(resource, Resource::VerificationMethod(_)));
+    let service_url = format!("{url}&service=files&relativeRef=schema.json");
+    let resource = dereference(&service_url, None, &resolver).await.expect("should dereference");
+    assert!(matches!(resource, Resource::Url(_)));
+
+    // the hashlink is not affected by document metadata
+    let mut updated = doc.clone();
+    updated.did_document_metadata = Some(DocumentMetadataBuilder::new().deactivated(true).build());
+    assert_eq!(updated.hashlink().expect("should generate hashlink"), hl);
+}
+
+// A document that does not match the hashlink is rejected.
+#[tokio::test]
+async fn tampered_document() {
+    let doc = document(DID);
+    let url = doc.hashlink_url().expect("should generate URL");
+
+    let mut tampered = doc.clone();
+    tampered.service = None;
+    let resolver = WebResolver::new([tampered]);
+
+    let result = resolve_did(&url, None, &resolver).await;
+    assert_eq!(result.error_code(), Some(ErrorCode::InternalError));
+
+    let err = dereference(&format!("{url}#key-1"), None, &resolver).await.expect_err("should fail");
+    assert!(matches!(err.downcast_ref::<Error>(), Some(Error::Hashlink(_))));
+
+    // invalid hashlinks are invalid DID URLs
+    let result = resolve_did(&format!("{DID}?hl=zInvalid0"), None, &resolver).await;
+    assert_eq!(result.error_code(), Some(ErrorCode::InvalidDidUrl));
+}
+
+// The expected hash can be given as a resolution option instead of the `hl`
+// query parameter.
+#[tokio::test]
+async fn expected_hash_option() {
+    let doc = document(DID);
+    let hl = doc.hashlink().expect("should generate hashlink");
+    let options = ResolutionOptions {
+        expected_hash: Some(hl.clone()),
+        ..ResolutionOptions::default()
+    };
+
+    let resolver = WebResolver::new([doc.clone()]);
+    let result = resolve_did(DID, Some(&options), &resolver).await;
+    assert!(result.error_code().is_none());
+    let key_url = format!("{DID}#key-1");
+    dereference(&key_url, Some(&options), &resolver).await.expect("should dereference");
+
+    let mut tampered = doc.clone();
+    tampered.service = None;
+    let resolver = WebResolver::new([tampered]);
+
+    let result = resolve_did(DID, Some(&options), &resolver).await;
+    assert_eq!(result.error_code(), Some(ErrorCode::InternalError));
+    let err = dereference(&key_url, Some(&options), &resolver).await.expect_err("should fail");
+    assert!(matches!(err.downcast_ref::<Error>(), Some(Error::Hashlink(_))));
+
+    let registry = Registry::default();
+    let url = Url::from_str(DID).expect("should parse");
+    let err = registry.resolve(&url, Some(&options), &resolver).await.expect_err("should fail");
+    assert!(matches!(err.downcast_ref::<Error>(), Some(Error::Hashlink(_))));
+
+    // the option must agree with the query parameter
+    let url = format!("{DID}?hl={}", hashlink(b"other"));
+    let result = resolve_did(&url, Some(&options), &resolver).await;
+    assert_eq!(result.error_code(), Some(ErrorCode::InvalidOptions));
+}
+
+// Hashlinks can be generated and verified for any content.
+#[test]
+fn content() {
+    let hl = hashlink(b"Hello World!");
+    assert_eq!(hl, "zQmWvQxTqbG2Z9HPJgG57jjwR154cKhbtJenbyYTWkjgF3e");
+    verify_hashlink(&hl, b"Hello World!").expect("should verify");
+
+    // metadata is ignored
+    verify_hashlink(&format!("{hl}:zMetadata"), b"Hello World!").expect("should verify");
+
+    let err = verify_hashlink(&hl, b"Hello World?").expect_err("should fail");
+    assert!(matches!(err.downcast_ref::<Error>(), Some(Error::Hashlink(_))));
+}
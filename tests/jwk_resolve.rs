@@ -0,0 +1,88 @@
+//! Tests for expanding a `did:jwk` into a DID document.
+
+mod common;
+
+use std::str::FromStr;
+
+use base64ct::{Base64UrlUnpadded, Encoding};
+use credibil_identity::core::Kind;
+use credibil_identity::did::jwk::DidJwk;
+use credibil_identity::did::{MethodType, Resource, Url, dereference, jwk};
+use credibil_jose::PublicKeyJwk;
+
+use common::NoopResolver;
+
+// Examples from the `did:jwk` specification.
+const DID_P256: &str = "did:jwk:eyJjcnYiOiJQLTI1NiIsImt0eSI6IkVDIiwieCI6ImFjYklRaXVNczNpOF91c3pFakoydHBUdFJNNEVVM3l6OTFQSDZDZEgyVjAiLCJ5IjoiX0tjeUxqOXZXTXB0bm1LdG00NkdxRHo4d2Y3NEk1TEtncmwyR3pIM25TRSJ9";
+const DID_X25519: &str = "did:jwk:eyJrdHkiOiJPS1AiLCJjcnYiOiJYMjU1MTkiLCJ1c2UiOiJlbmMiLCJ4IjoiM3A3YmZYdDl3YlRUVzJIQzdPUTFOei1EUThoYmVHZE5yZngtRkctSUswOCJ9";
+
+// A JWK without a `use` is referenced by all verification relationships.
+#[tokio::test]
+async fn signing_key() {
+    let resource = dereference(DID_P256, None, &NoopResolver).await.expect("should dereference");
+    let Resource::Document(doc) = resource else {
+        panic!("should be a document");
+    };
+    assert_eq!(doc.id, DID_P256);
+
+    let vm_id = format!("{DID_P256}#0");
+    let vms = doc.verification_method.as_ref().expect("should have verification methods");
+    assert_eq!(vms.len(), 1);
+    assert_eq!(vms[0].id, vm_id);
+    assert_eq!(vms[0].controller, DID_P256);
+    assert_eq!(vms[0].type_, MethodType::JsonWebKey2020);
+
+    let reference = Kind::String(vm_id.clone());
+    for relationship in [
+        &doc.authentication,
+        &doc.assertion_method,
+        &doc.capability_invocation,
+        &doc.capability_delegation,
+        &doc.key_agreement,
+    ] {
+        assert_eq!(relationship.as_deref(), Some([reference.clone()].as_slice()));
+    }
+
+    let resource = dereference(&vm_id, None, &NoopResolver).await.expect("should dereference");
+    assert!(matches!(resource, Resource::VerificationMethod(vm) if vm.id == vm_id));
+}
+
+// An encryption key is only used for key agreement.
+#[test]
+fn encryption_key() {
+    let url = Url::from_str(DID_X25519).expect("should parse");
+    let doc = jwk::document(&url).expect("should expand");
+
+    let reference = Kind::String(format!("{DID_X25519}#0"));
+    assert_eq!(doc.key_agreement.as_deref(), Some([reference].as_slice()));
+    assert!(doc.authentication.is_none());
+    assert!(doc.assertion_method.is_none());
+    assert!(doc.capability_invocation.is_none());
+    assert!(doc.capability_delegation.is_none());
+}
+
+// Creating a `did:jwk` from a JWK round-trips through resolution.
+#[test]
+fn create() {
+    let did_jwk = DidJwk::from_str(DID_P256).expect("should parse");
+    let created = DidJwk::from_jwk(did_jwk.public_key_jwk()).expect("should create");
+    assert_eq!(created.did(), DID_P256);
+    assert_eq!(created.verification_method_id(), format!("{DID_P256}#0"));
+
+    // Only `#0` identifies a resource.
+    assert!(DidJwk::from_str(&format!("{DID_P256}#1")).is_err());
+
+    // Private keys are rejected.
+    let private = r#"{"kty":"OKP","crv":"Ed25519","x":"11qYAYKxCrfVS_7TyWQHOg7hcvPapiMlrwIaaPcHURo","d":"nWGxne_9WmC6hEr0kuwsxERJxWl7MmkZcDusAxyuf2A"}"#;
+    let did = format!("did:jwk:{}", Base64UrlUnpadded::encode_string(private.as_bytes()));
+    assert!(DidJwk::from_str(&did).is_err());
+
+    let jwk: PublicKeyJwk = serde_json::from_str(
+        r#"{"kty":"OKP","crv":"Ed25519","x":"11qYAYKxCrfVS_7TyWQHOg7hcvPapiMlrwIaaPcHURo"}"#,
+    )
+    .expect("should deserialize");
+    let did_jwk = DidJwk::from_jwk(&jwk).expect("should create");
+    let doc = jwk::document(&Url::from_str(&did_jwk.did()).expect("should parse"))
+        .expect("should expand");
+    assert_eq!(doc.id, did_jwk.did());
+}
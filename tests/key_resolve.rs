@@ -0,0 +1,131 @@
+//! Tests for expanding a `did:key` into a DID document.
+
+mod common;
+
+use std::str::FromStr;
+
+use credibil_identity::core::Kind;
+use credibil_identity::did::key::DidKey;
+use credibil_identity::did::{Resource, Url, deref_url, dereference, document_resource, key};
+
+use common::NoopResolver;
+
+const DID: &str = "did:key:z6MkhaXgBZDvotDkL5257faiztiGiC2QtKLGpbnnEGta2doK";
+const KEY_AGREEMENT: &str = "z6LSj72tK8brWgZja8NLRwPigth2T9QRiG1uH9oKZuKjdh9p";
+
+// A bare `did:key` should dereference to a complete DID document.
+#[tokio::test]
+async fn bare_did() {
+    let resource = dereference(DID, None, &NoopResolver).await.expect("should dereference");
+    let Resource::Document(doc) = resource else {
+        panic!("should be a document");
+    };
+    assert_eq!(doc.id, DID);
+
+    let vm_id = format!("{DID}#z6MkhaXgBZDvotDkL5257faiztiGiC2QtKLGpbnnEGta2doK");
+    let vms = doc.verification_method.as_ref().expect("should have verification methods");
+    assert_eq!(vms.len(), 1);
+    assert_eq!(vms[0].id, vm_id);
+    assert_eq!(vms[0].controller, DID);
+
+    let reference = Kind::String(vm_id.clone());
+    for relationship in [
+        &doc.authentication,
+        &doc.assertion_method,
+        &doc.capability_invocation,
+        &doc.capability_delegation,
+    ] {
+        assert_eq!(relationship.as_deref(), Some([reference.clone()].as_slice()));
+    }
+
+    let key_agreement = doc.key_agreement.as_ref().expect("should have key agreement");
+    let Kind::Object(ka) = &key_agreement[0] else {
+        panic!("key agreement should be embedded");
+    };
+    assert_eq!(ka.id, format!("{DID}#{KEY_AGREEMENT}"));
+    assert_eq!(ka.key.multibase().expect("should get multibase"), KEY_AGREEMENT);
+
+    // The document can be used like any other for further dereferencing.
+    let url = Url::from_str(&vm_id).expect("should parse");
+    let vm = document_resource(&url, None, &doc).expect("should find verification method");
+    assert!(matches!(vm, Resource::VerificationMethod(vm) if vm.id == vm_id));
+}
+
+// A fragment should dereference to the matching verification method,
+// including the derived key agreement key.
+#[tokio::test]
+async fn fragment() {
+    let url = Url::from_str(&format!("{DID}#{KEY_AGREEMENT}")).expect("should parse");
+    let resource = deref_url(&url, None, &NoopResolver).await.expect("should dereference");
+    let Resource::VerificationMethod(vm) = resource else {
+        panic!("should be a verification method");
+    };
+    assert_eq!(vm.id, url.to_string());
+
+    let url = Url::from_str(&format!("{DID}#unknown")).expect("should parse");
+    assert!(key::resolve(&url).is_err());
+}
+
+// Non-Ed25519 keys expand to a `Multikey` verification method without a
+// derived key agreement.
+#[tokio::test]
+async fn elliptic_curves() {
+    for (multi, crv) in [
+        ("zDnaerDaTF5BXEavCrfRZEk316dpbLsfPDZ3WJ5hRTPFU2169", "P-256"),
+        ("z82Lm1MpAkeJcix9K8TMiLd5NMAhnwkjjCBeWHXyu3U4oT2MVJJKXkcVBgjGhnLBn2Kaau9", "P-384"),
+        ("zQ3shokFTS3brHcDQrn82RUDfCZESWL1ZdCEJwekUDPQiYBme", "secp256k1"),
+    ] {
+        let did = format!("did:key:{multi}");
+        let resource = dereference(&did, None, &NoopResolver).await.expect("should dereference");
+        let Resource::Document(doc) = resource else {
+            panic!("should be a document");
+        };
+
+        let vms = doc.verification_method.as_ref().expect("should have verification methods");
+        assert_eq!(vms.len(), 1);
+        assert_eq!(vms[0].id, format!("{did}#{multi}"));
+        assert!(doc.key_agreement.is_none());
+
+        let jwk = vms[0].key.jwk().expect("should convert to JWK");
+        let value = serde_json::to_value(&jwk).expect("should serialize");
+        assert_eq!(value["kty"], "EC");
+        assert_eq!(value["crv"], crv);
+        let did_key = DidKey::from_jwk(&jwk).expect("should create from JWK");
+        assert_eq!(did_key.did(), did);
+    }
+
+    let url = Url::from_str("did:key:zDnaerDaTF5BXEavCrfRZEk316dpbLsfPDZ3WJ5hRTPFU216")
+        .expect("should parse");
+    assert!(key::document(&url).is_err());
+}
+
+// A `DidKey` separates the DID from its verification method and only accepts
+// fragments that identify a key of the DID.
+#[test]
+fn did_key() {
+    let did_key = DidKey::from_str(DID).expect("should parse");
+    assert_eq!(did_key.did(), DID);
+    assert_eq!(
+        did_key.verification_method_id(),
+        format!("{DID}#z6MkhaXgBZDvotDkL5257faiztiGiC2QtKLGpbnnEGta2doK")
+    );
+
+    let jwk = did_key.public_key_jwk().expect("should convert to JWK");
+    assert_eq!(DidKey::from_jwk(&jwk).expect("should create"), did_key);
+
+    let url = Url::from_str(&did_key.verification_method_id()).expect("should parse");
+    assert_eq!(DidKey::try_from(&url).expect("should parse"), did_key);
+    let url = Url::from_str(&format!("{DID}#{KEY_AGREEMENT}")).expect("should parse");
+    assert_eq!(DidKey::try_from(&url).expect("should parse"), did_key);
+
+    // A fragment naming another key must not resolve to that key.
+    let other = "z6MkjchhfUsD6mmvni8mCdXHw216Xrm9bQe2mBH1P5RDjVJG";
+    let url = Url::from_str(&format!("{DID}#{other}")).expect("should parse");
+    assert!(DidKey::try_from(&url).is_err());
+    assert!(key::resolve(&url).is_err());
+
+    // Unknown multicodec prefix (sha2-256 multihash).
+    let unknown =
+        multibase::encode(multibase::Base::Base58Btc, [[0x12, 0x20].as_slice(), &[1; 32]].concat());
+    assert!(DidKey::from_str(&format!("did:key:{unknown}")).is_err());
+}
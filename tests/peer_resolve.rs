@@ -0,0 +1,164 @@
+//! Tests for creating and expanding `did:peer` DIDs.
+
+mod common;
+
+use std::str::FromStr;
+
+use credibil_identity::core::{Kind, OneMany};
+use credibil_identity::did::peer::{self, Numalgo2Builder};
+use credibil_identity::did::{
+    Document, KeyPurpose, Multikey, Resource, ServiceBuilder, Url, deref_url, dereference,
+};
+use serde_json::json;
+
+use common::NoopResolver;
+
+const SIGNING_KEY: &str = "z6MkhaXgBZDvotDkL5257faiztiGiC2QtKLGpbnnEGta2doK";
+const KEY_AGREEMENT: &str = "z6LSj72tK8brWgZja8NLRwPigth2T9QRiG1uH9oKZuKjdh9p";
+
+// Numalgo 0 expands the inception key in the same way as `did:key`.
+#[tokio::test]
+async fn numalgo_0() {
+    let key = Multikey::from_multibase(SIGNING_KEY).expect("should decode");
+    let did = peer::did_from_key(&key);
+    assert_eq!(did, format!("did:peer:0{SIGNING_KEY}"));
+
+    let Resource::Document(doc) =
+        dereference(&did, None, &NoopResolver).await.expect("should resolve")
+    else {
+        panic!("should be a document");
+    };
+    assert_eq!(doc.id, did);
+    let vm_id = format!("{did}#{SIGNING_KEY}");
+    assert_eq!(doc.authentication.as_deref(), Some([Kind::String(vm_id.clone())].as_slice()));
+
+    let url = Url::from_str(&format!("{did}#{KEY_AGREEMENT}")).expect("should parse");
+    let resource = deref_url(&url, None, &NoopResolver).await.expect("should dereference");
+    assert!(matches!(resource, Resource::VerificationMethod(vm) if vm.id == url.to_string()));
+}
+
+// Numalgo 2 encodes keys with their purposes and abbreviated services.
+#[tokio::test]
+async fn numalgo_2() {
+    let signing = Multikey::from_multibase(SIGNING_KEY).expect("should decode");
+    let encryption = Multikey::from_multibase(KEY_AGREEMENT).expect("should decode");
+    let service = ServiceBuilder::new(&"#service")
+        .service_type(&"DIDCommMessaging")
+        .endpoint_json(&json!({
+            "uri": "https://example.com/didcomm",
+            "accept": ["didcomm/v2"],
+            "routingKeys": []
+        }))
+        .build();
+
+    let did = Numalgo2Builder::new()
+        .key(&encryption, &KeyPurpose::KeyAgreement)
+        .expect("should add key")
+        .key(&signing, &KeyPurpose::Authentication)
+        .expect("should add key")
+        .service(&service)
+        .expect("should add service")
+        .build()
+        .expect("should build");
+    assert!(did.starts_with(&format!("did:peer:2.E{KEY_AGREEMENT}.V{SIGNING_KEY}.S")));
+
+    let Resource::Document(doc) =
+        dereference(&did, None, &NoopResolver).await.expect("should resolve")
+    else {
+        panic!("should be a document");
+    };
+    assert_eq!(doc.id, did);
+
+    // Keys are numbered in order and every key is a verification method.
+    let ka_id = format!("{did}#key-1");
+    assert_eq!(doc.key_agreement.as_deref(), Some([Kind::String(ka_id.clone())].as_slice()));
+    let ka = doc.get_verification_method(&ka_id).expect("should have key agreement method");
+    assert_eq!(ka.key.multibase().expect("should get multibase"), KEY_AGREEMENT);
+    assert_eq!(doc.verification_method.as_ref().map(Vec::len), Some(2));
+    let auth_id = format!("{did}#key-2");
+    assert_eq!(doc.authentication.as_deref(), Some([Kind::String(auth_id.clone())].as_slice()));
+    assert!(doc.get_verification_method(&auth_id).is_some());
+
+    // Services are expanded.
+    let services = doc.service.as_ref().expect("should have services");
+    assert_eq!(services[0].id, format!("{did}#service"));
+    assert_eq!(services[0].type_, "DIDCommMessaging");
+    let OneMany::One(Kind::Object(endpoint)) = &services[0].service_endpoint else {
+        panic!("should have a single endpoint");
+    };
+    assert_eq!(endpoint["uri"], "https://example.com/didcomm");
+    assert_eq!(endpoint["accept"], json!(["didcomm/v2"]));
+
+    let url = Url::from_str(&format!("{did}#key-1")).expect("should parse");
+    let resource = deref_url(&url, None, &NoopResolver).await.expect("should dereference");
+    assert!(matches!(resource, Resource::VerificationMethod(vm) if vm.id == url.to_string()));
+}
+
+// The example from the specification, with abbreviated services.
+#[test]
+fn numalgo_2_spec() {
+    let did = "did:peer:2.Vz6Mkj3PUd1WjvaDhNZhhhXQdz5UnZXmS7ehtx8bsPpD47kKc.Ez6LSg8zQom395jKLrGiBNruB9MM6V8PWuf2FpEy4uRFiqQBR.SeyJ0IjoiZG0iLCJzIjp7InVyaSI6Imh0dHA6Ly9leGFtcGxlLmNvbS9kaWRjb21tIiwiYSI6WyJkaWRjb21tL3YyIl0sInIiOlsiZGlkOmV4YW1wbGU6MTIzNDU2Nzg5YWJjZGVmZ2hpI2tleS0xIl19fQ.SeyJ0IjoiZXhhbXBsZSIsInMiOnsidXJpIjoiaHR0cDovL2V4YW1wbGUuY29tL2Fub3RoZXIiLCJhIjpbImRpZGNvbW0vdjIiXSwiciI6WyJkaWQ6ZXhhbXBsZToxMjM0NTY3ODlhYmNkZWZnaGkja2V5LTIiXX19";
+    let doc = peer::document(&Url::from_str(did).expect("should parse")).expect("should expand");
+
+    let services = doc.service.as_ref().expect("should have services");
+    assert_eq!(services.len(), 2);
+    assert_eq!(services[0].id, format!("{did}#service"));
+    assert_eq!(services[0].type_, "DIDCommMessaging");
+    assert_eq!(services[1].id, format!("{did}#service-1"));
+    assert_eq!(services[1].type_, "example");
+    let OneMany::One(Kind::Object(endpoint)) = &services[1].service_endpoint else {
+        panic!("should have a single endpoint");
+    };
+    assert_eq!(endpoint["routingKeys"], json!(["did:example:123456789abcdefghi#key-2"]));
+}
+
+// Numalgo 4 resolves the long form directly and the short form with the help
+// of the long form.
+#[test]
+fn numalgo_4() {
+    let input: Document = serde_json::from_value(json!({
+        "@context": ["https://www.w3.org/ns/did/v1", "https://w3id.org/security/multikey/v1"],
+        "id": "",
+        "verificationMethod": [{
+            "id": "#key-1",
+            "type": "Multikey",
+            "controller": "",
+            "publicKeyMultibase": SIGNING_KEY
+        }],
+        "authentication": ["#key-1"],
+        "service": [{
+            "id": "#didcomm",
+            "type": "DIDCommMessaging",
+            "serviceEndpoint": "https://example.com/didcomm"
+        }]
+    }))
+    .expect("should deserialize");
+
+    let long_form = peer::did_from_document(&input).expect("should create");
+    let short_form = peer::short_form(&long_form).expect("should shorten");
+    assert!(long_form.starts_with(&format!("{short_form}:z")));
+
+    let doc =
+        peer::document(&Url::from_str(&long_form).expect("should parse")).expect("should expand");
+    assert_eq!(doc.id, long_form);
+    assert_eq!(doc.also_known_as, Some(vec![short_form.clone()]));
+    let vm = doc.get_verification_method(&format!("{long_form}#key-1")).expect("should find key");
+    assert_eq!(vm.controller, long_form);
+    assert_eq!(
+        doc.authentication.as_deref(),
+        Some([Kind::String(format!("{long_form}#key-1"))].as_slice())
+    );
+    assert!(doc.get_service(&format!("{long_form}#didcomm")).is_some());
+
+    let short_url = Url::from_str(&short_form).expect("should parse");
+    assert!(peer::document(&short_url).is_err());
+    let doc = peer::short_form_document(&short_url, &long_form).expect("should expand");
+    assert_eq!(doc.id, short_form);
+    assert_eq!(doc.also_known_as, Some(vec![long_form.clone()]));
+
+    // The hash must match the encoded document.
+    let (_, encoded) = long_form.rsplit_once(':').expect("should have document");
+    let other = peer::did_from_document(&Document::default()).expect("should create");
+    let tampered = format!("{}:{encoded}", other.rsplit_once(':').expect("should split").0);
+    assert!(peer::document(&Url::from_str(&tampered).expect("should parse")).is_err());
+}
@@ -0,0 +1,101 @@
+//! Tests for expanding a `did:pkh` into a DID document.
+
+mod common;
+
+use std::str::FromStr;
+
+use credibil_identity::core::Kind;
+use credibil_identity::did::pkh::{self, AccountId};
+use credibil_identity::did::{MethodType, PublicKeyFormat, Resource, Url, dereference};
+
+use common::NoopResolver;
+
+// An Ethereum account resolves to a recovery method referenced by the
+// authentication and assertion relationships.
+#[tokio::test]
+async fn eip155() {
+    let did = "did:pkh:eip155:1:0xb9c5714089478a327f09197987f16f9e5d936e8a";
+    let Resource::Document(doc) =
+        dereference(did, None, &NoopResolver).await.expect("should resolve")
+    else {
+        panic!("should be a document");
+    };
+    assert_eq!(doc.id, did);
+
+    let vm_id = format!("{did}#blockchainAccountId");
+    let vm = doc.get_verification_method(&vm_id).expect("should have verification method");
+    assert_eq!(vm.type_, MethodType::EcdsaSecp256k1RecoveryMethod2020);
+    assert_eq!(vm.controller, did);
+    assert_eq!(
+        vm.key,
+        PublicKeyFormat::BlockchainAccountId {
+            blockchain_account_id: "eip155:1:0xb9c5714089478a327f09197987f16f9e5d936e8a"
+                .to_string()
+        }
+    );
+    assert!(vm.key.jwk().is_err());
+
+    let reference = Kind::String(vm_id.clone());
+    assert_eq!(doc.authentication.as_deref(), Some([reference.clone()].as_slice()));
+    assert_eq!(doc.assertion_method.as_deref(), Some([reference].as_slice()));
+
+    let resource = dereference(&vm_id, None, &NoopResolver).await.expect("should dereference");
+    assert!(matches!(resource, Resource::VerificationMethod(vm) if vm.id == vm_id));
+
+    // The document round-trips through JSON.
+    let json = serde_json::to_value(&doc).expect("should serialize");
+    assert_eq!(
+        json["verificationMethod"][0]["blockchainAccountId"],
+        "eip155:1:0xb9c5714089478a327f09197987f16f9e5d936e8a"
+    );
+    let parsed: credibil_identity::did::Document =
+        serde_json::from_value(json).expect("should deserialize");
+    assert_eq!(parsed, doc);
+}
+
+// Each namespace uses the verification method type for its keys.
+#[test]
+fn namespaces() {
+    for (did, method_type) in [
+        (
+            "did:pkh:solana:4sGjMW1sUnHzSxGspuhpqLDx6wiyjNtZ:CKg5d12Jhpej1JqtmxLJgaFqqeYjxgPqToJ4LBdvG9Ev",
+            MethodType::Ed25519VerificationKey2018,
+        ),
+        (
+            "did:pkh:tezos:NetXdQprcVkpaWU:tz1TzrmTBSuiVHV2VfMnGRMYvTEPCP42oSM8",
+            MethodType::Ed25519PublicKeyBlake2bDigestSize20Base58CheckEncoded2021,
+        ),
+        (
+            "did:pkh:tezos:NetXdQprcVkpaWU:tz2BFTyPeYRzxd5aiBchbXN3WCZhx7BqbMBq",
+            MethodType::EcdsaSecp256k1RecoveryMethod2020,
+        ),
+        (
+            "did:pkh:bip122:000000000019d6689c085ae165831e93:128Lkh3S7CkDTBZ8W7BbpsN3YYizJMp8p6",
+            MethodType::EcdsaSecp256k1RecoveryMethod2020,
+        ),
+    ] {
+        let doc = pkh::document(&Url::from_str(did).expect("should parse")).expect("should expand");
+        let vms = doc.verification_method.expect("should have verification method");
+        assert_eq!(vms[0].type_, method_type, "{did}");
+    }
+}
+
+// Account IDs are validated for the namespace.
+#[test]
+fn account_id() {
+    let account = AccountId::from_str("eip155:1:0xb9c5714089478a327f09197987f16f9e5d936e8a")
+        .expect("should parse");
+    assert_eq!(account.namespace, "eip155");
+    assert_eq!(account.reference, "1");
+    assert_eq!(account.did(), "did:pkh:eip155:1:0xb9c5714089478a327f09197987f16f9e5d936e8a");
+
+    assert!(AccountId::from_str("eip155:1:0x1234").is_err());
+    assert!(AccountId::from_str("eip155:1").is_err());
+    assert!(
+        AccountId::from_str("tezos:NetXdQprcVkpaWU:KT1TzrmTBSuiVHV2VfMnGRMYvTEPCP42oSM8").is_err()
+    );
+    assert!(
+        AccountId::from_str("cosmos:cosmoshub-3:cosmos1t2uflqwqe0fsj0shcfkrvpukewcw40yjj6hdc0")
+            .is_err()
+    );
+}
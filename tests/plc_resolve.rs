@@ -0,0 +1,415 @@
+//! Tests for resolving a `did:plc` audit log into a DID document.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+
+use anyhow::anyhow;
+use chrono::{DateTime, Duration, Utc};
+use credibil_identity::did::plc::{self, LogEntry, Operation, PlcOperation, PlcService, Tombstone};
+use credibil_identity::did::{
+    KeyCodec, MethodType, Multikey, ResolutionOptions, Resource, Url, dereference, key::DidKey,
+};
+use credibil_identity::{Error, Identity, IdentityResolver, LogErrorKind};
+use k256::ecdsa::SigningKey;
+use k256::ecdsa::signature::Signer;
+use serde_json::json;
+
+// A local stand-in for a PLC directory, serving audit logs by URL.
+#[derive(Clone, Default)]
+struct Directory {
+    logs: Arc<Mutex<HashMap<String, Vec<LogEntry>>>>,
+}
+
+impl Directory {
+    fn publish(&self, did: &str, log: Vec<LogEntry>) {
+        let url = format!("{}/{did}/log/audit", plc::DEFAULT_DIRECTORY);
+        self.logs.lock().expect("should lock").insert(url, log);
+    }
+}
+
+impl IdentityResolver for Directory {
+    async fn resolve(&self, url: &str) -> anyhow::Result<Identity> {
+        let log = self.logs.lock().expect("should lock").get(url).cloned();
+        let log = log.ok_or_else(|| anyhow!("DID not registered: {url}"))?;
+        Ok(Identity::PlcLog(log))
+    }
+}
+
+// Create a deterministic secp256k1 key and its `did:key`.
+fn keypair(seed: u8) -> (SigningKey, String) {
+    let key = SigningKey::from_slice(&[seed; 32]).expect("should create key");
+    let public = key.verifying_key().to_encoded_point(true);
+    let multikey = Multikey::new(KeyCodec::Secp256k1, public.as_bytes()).expect("should be valid");
+    (key, DidKey::new(multikey).did())
+}
+
+fn state(rotation_keys: &[&str], handle: &str, prev: Option<&str>) -> Operation {
+    let (_, atproto) = keypair(9);
+    Operation::Plc(PlcOperation {
+        rotation_keys: rotation_keys.iter().map(ToString::to_string).collect(),
+        verification_methods: BTreeMap::from([("atproto".to_string(), atproto)]),
+        also_known_as: vec![format!("at://{handle}")],
+        services: BTreeMap::from([(
+            "atproto_pds".to_string(),
+            PlcService {
+                type_: "AtprotoPersonalDataServer".to_string(),
+                endpoint: "https://pds.example.com".to_string(),
+            },
+        )]),
+        prev: prev.map(ToString::to_string),
+        sig: String::new(),
+    })
+}
+
+fn sign(mut operation: Operation, key: &SigningKey) -> Operation {
+    let msg = operation.signing_input().expect("should encode");
+    let sig: k256::ecdsa::Signature = key.sign(&msg);
+    operation.set_signature(&sig.to_bytes());
+    operation
+}
+
+fn entry(did: &str, operation: Operation, created_at: DateTime<Utc>) -> LogEntry {
+    LogEntry {
+        did: did.to_string(),
+        cid: operation.cid().expect("should get CID"),
+        operation,
+        nullified: false,
+        created_at,
+    }
+}
+
+// A DID with a recovery key (highest priority) and a signing key.
+struct Fixture {
+    recovery: SigningKey,
+    signer: SigningKey,
+    rotation_keys: Vec<String>,
+    did: String,
+    genesis: LogEntry,
+}
+
+fn fixture() -> Fixture {
+    let (recovery, recovery_key) = keypair(1);
+    let (signer, signer_key) = keypair(2);
+    let rotation_keys = vec![recovery_key, signer_key];
+    let keys = rotation_keys.iter().map(String::as_str).collect::<Vec<_>>();
+
+    let genesis = sign(state(&keys, "alice.example.com", None), &signer);
+    let did = genesis.did().expect("should derive DID");
+    let created = Utc::now() - Duration::hours(1);
+    let genesis = entry(&did, genesis, created);
+
+    Fixture {
+        recovery,
+        signer,
+        rotation_keys,
+        did,
+        genesis,
+    }
+}
+
+fn fixture_with_handle(handle: &str) -> Fixture {
+    let mut f = fixture();
+    let keys = f.rotation_keys.iter().map(String::as_str).collect::<Vec<_>>();
+    let genesis = sign(state(&keys, handle, None), &f.signer);
+    f.did = genesis.did().expect("should derive DID");
+    f.genesis = entry(&f.did, genesis, f.genesis.created_at);
+    f
+}
+
+// A genesis operation resolves to a document with the verification method,
+// handle and service from the operation.
+#[tokio::test]
+async fn resolve_genesis() {
+    let f = fixture();
+    assert!(f.did.starts_with("did:plc:"));
+    assert_eq!(f.did.len(), "did:plc:".len() + 24);
+
+    let directory = Directory::default();
+    directory.publish(&f.did, vec![f.genesis.clone()]);
+
+    let Resource::Document(doc) =
+        dereference(&f.did, None, &directory).await.expect("should resolve")
+    else {
+        panic!("should be a document");
+    };
+    assert_eq!(doc.id, f.did);
+    assert_eq!(doc.also_known_as, Some(vec!["at://alice.example.com".to_string()]));
+
+    let vm = doc
+        .get_verification_method(&format!("{}#atproto", f.did))
+        .expect("should have verification method");
+    assert_eq!(vm.type_, MethodType::Multikey);
+    assert_eq!(vm.controller, f.did);
+
+    let service =
+        doc.get_service(&format!("{}#atproto_pds", f.did)).expect("should have PDS service");
+    assert_eq!(service.type_, "AtprotoPersonalDataServer");
+
+    let md = doc.did_document_metadata.expect("should have metadata");
+    assert_eq!(md.created, f.genesis.created_at);
+    assert_eq!(md.version_id, Some(f.genesis.cid));
+}
+
+// Updates signed by a rotation key are applied in order and earlier versions
+// can be requested by CID.
+#[tokio::test]
+async fn resolve_update() {
+    let f = fixture();
+    let keys = f.rotation_keys.iter().map(String::as_str).collect::<Vec<_>>();
+
+    let update = sign(state(&keys, "alice.bsky.social", Some(&f.genesis.cid)), &f.signer);
+    let update = entry(&f.did, update, f.genesis.created_at + Duration::minutes(5));
+
+    let directory = Directory::default();
+    directory.publish(&f.did, vec![f.genesis.clone(), update.clone()]);
+
+    let Resource::Document(doc) =
+        dereference(&f.did, None, &directory).await.expect("should resolve")
+    else {
+        panic!("should be a document");
+    };
+    assert_eq!(doc.also_known_as, Some(vec!["at://alice.bsky.social".to_string()]));
+    let md = doc.did_document_metadata.expect("should have metadata");
+    assert_eq!(md.updated, Some(update.created_at));
+    assert_eq!(md.version_id, Some(update.cid.clone()));
+
+    let url = format!("{}?versionId={}", f.did, f.genesis.cid);
+    let Resource::Document(doc) =
+        dereference(&url, None, &directory).await.expect("should resolve")
+    else {
+        panic!("should be a document");
+    };
+    assert_eq!(doc.also_known_as, Some(vec!["at://alice.example.com".to_string()]));
+    let md = doc.did_document_metadata.expect("should have metadata");
+    assert_eq!(md.next_version_id, Some(update.cid));
+}
+
+// An operation signed by a key that is not a rotation key is rejected.
+#[tokio::test]
+async fn invalid_signature() {
+    let f = fixture();
+    let (attacker, attacker_key) = keypair(3);
+
+    let update =
+        sign(state(&[&attacker_key], "mallory.example.com", Some(&f.genesis.cid)), &attacker);
+    let update = entry(&f.did, update, f.genesis.created_at + Duration::minutes(5));
+
+    let directory = Directory::default();
+    directory.publish(&f.did, vec![f.genesis.clone(), update.clone()]);
+
+    let err = dereference(&f.did, None, &directory).await.expect_err("should fail");
+    assert!(err.to_string().contains("not signed by a valid rotation key"));
+    let Some(Error::LogVerification { kind, version_id, .. }) = err.downcast_ref::<Error>() else {
+        panic!("should be a log verification error");
+    };
+    assert_eq!(*kind, LogErrorKind::Proof);
+    assert_eq!(version_id.as_deref(), Some(update.cid.as_str()));
+}
+
+// The genesis operation must hash to the DID.
+#[tokio::test]
+async fn genesis_mismatch() {
+    let f = fixture();
+    let other = fixture_with_handle("bob.example.com");
+
+    let directory = Directory::default();
+    let mut genesis = other.genesis;
+    genesis.did.clone_from(&f.did);
+    directory.publish(&f.did, vec![genesis]);
+
+    let err = dereference(&f.did, None, &directory).await.expect_err("should fail");
+    assert!(err.to_string().contains("genesis operation does not match the DID"));
+}
+
+// The recovery key can fork the log to nullify an operation signed by the
+// lower-priority signing key.
+#[tokio::test]
+async fn nullify() {
+    let f = fixture();
+    let keys = f.rotation_keys.iter().map(String::as_str).collect::<Vec<_>>();
+
+    let hijack = sign(state(&keys, "mallory.example.com", Some(&f.genesis.cid)), &f.signer);
+    let mut hijack = entry(&f.did, hijack, f.genesis.created_at + Duration::minutes(5));
+    let recover = sign(state(&keys, "alice.example.com", Some(&f.genesis.cid)), &f.recovery);
+    let recover = entry(&f.did, recover, f.genesis.created_at + Duration::minutes(10));
+
+    // The directory must report the nullified operation.
+    let directory = Directory::default();
+    directory.publish(&f.did, vec![f.genesis.clone(), hijack.clone(), recover.clone()]);
+    let err = dereference(&f.did, None, &directory).await.expect_err("should fail");
+    assert!(err.to_string().contains("nullified flag"));
+
+    hijack.nullified = true;
+    directory.publish(&f.did, vec![f.genesis.clone(), hijack, recover.clone()]);
+    let Resource::Document(doc) =
+        dereference(&f.did, None, &directory).await.expect("should resolve")
+    else {
+        panic!("should be a document");
+    };
+    assert_eq!(doc.also_known_as, Some(vec!["at://alice.example.com".to_string()]));
+    let md = doc.did_document_metadata.expect("should have metadata");
+    assert_eq!(md.version_id, Some(recover.cid));
+}
+
+// A lower-priority key cannot nullify an operation signed by a
+// higher-priority key, and nullification is only allowed for 72 hours.
+#[tokio::test]
+async fn nullify_rejected() {
+    let f = fixture();
+    let keys = f.rotation_keys.iter().map(String::as_str).collect::<Vec<_>>();
+
+    let first = sign(state(&keys, "first.example.com", Some(&f.genesis.cid)), &f.recovery);
+    let mut first = entry(&f.did, first, f.genesis.created_at + Duration::minutes(5));
+    first.nullified = true;
+    let second = sign(state(&keys, "second.example.com", Some(&f.genesis.cid)), &f.signer);
+    let second = entry(&f.did, second, f.genesis.created_at + Duration::minutes(10));
+
+    let directory = Directory::default();
+    directory.publish(&f.did, vec![f.genesis.clone(), first, second]);
+    let err = dereference(&f.did, None, &directory).await.expect_err("should fail");
+    assert!(err.to_string().contains("higher-priority rotation key"));
+
+    let first = sign(state(&keys, "first.example.com", Some(&f.genesis.cid)), &f.signer);
+    let mut first = entry(&f.did, first, f.genesis.created_at + Duration::minutes(5));
+    first.nullified = true;
+    let late = sign(state(&keys, "late.example.com", Some(&f.genesis.cid)), &f.recovery);
+    let late = entry(&f.did, late, first.created_at + Duration::hours(73));
+
+    directory.publish(&f.did, vec![f.genesis.clone(), first, late]);
+    let err = dereference(&f.did, None, &directory).await.expect_err("should fail");
+    assert!(err.to_string().contains("older than 72 hours"));
+}
+
+// A tombstone deactivates the DID and no further operations are allowed.
+#[tokio::test]
+async fn tombstone() {
+    let f = fixture();
+    let keys = f.rotation_keys.iter().map(String::as_str).collect::<Vec<_>>();
+
+    let tombstone = Operation::Tombstone(Tombstone {
+        prev: f.genesis.cid.clone(),
+        sig: String::new(),
+    });
+    let tombstone =
+        entry(&f.did, sign(tombstone, &f.signer), f.genesis.created_at + Duration::minutes(5));
+
+    let directory = Directory::default();
+    directory.publish(&f.did, vec![f.genesis.clone(), tombstone.clone()]);
+    let Resource::Document(doc) =
+        dereference(&f.did, None, &directory).await.expect("should resolve")
+    else {
+        panic!("should be a document");
+    };
+    assert!(doc.verification_method.is_none());
+    let md = doc.did_document_metadata.expect("should have metadata");
+    assert_eq!(md.deactivated, Some(true));
+
+    let revive = sign(state(&keys, "alice.example.com", Some(&tombstone.cid)), &f.signer);
+    let revive = entry(&f.did, revive, tombstone.created_at + Duration::minutes(5));
+    directory.publish(&f.did, vec![f.genesis.clone(), tombstone, revive]);
+    let err = dereference(&f.did, None, &directory).await.expect_err("should fail");
+    assert!(err.to_string().contains("follows a tombstone"));
+}
+
+// The signing input, CID and DID of an operation match values derived
+// independently from the DAG-CBOR and did:plc specifications.
+#[test]
+fn known_answer() {
+    let operation: Operation = serde_json::from_value(json!({
+        "type": "plc_operation",
+        "rotationKeys": [
+            "did:key:zQ3shhCGUqDKjStzuDxPkTxN6ujddP4RkEKJJouJGRRkaLGbg",
+            "did:key:zQ3shpKnbdPx3g3CmPf5cRVTPe1HtSwVn5ish3wSnDPQCbLJK"
+        ],
+        "verificationMethods": {
+            "atproto": "did:key:zQ3shXjHeiBuRCKmM36cuYnm7YEMzhGnCmCyW92sRJ9pribSF"
+        },
+        "alsoKnownAs": ["at://alice.example.com"],
+        "services": {
+            "atproto_pds": {
+                "type": "AtprotoPersonalDataServer",
+                "endpoint": "https://pds.example.com"
+            }
+        },
+        "prev": null,
+        "sig": "0JNA4Eq6F1E5bgqeBbNxN2pBvQx9IbYbxF9iDhRFXi5mQfR3_7c6K1ZcL1hLBpRvyK8aKzCxJ3Y2k6K1d2H5tQ"
+    }))
+    .expect("should deserialize");
+
+    let input = operation.signing_input().expect("should encode");
+    let hex = input.iter().fold(String::new(), |mut hex, b| {
+        let _ = write!(hex, "{b:02x}");
+        hex
+    });
+    assert_eq!(hex, KNOWN_SIGNING_INPUT);
+    assert_eq!(
+        operation.cid().expect("should get CID"),
+        "bafyreiepebmpzvac24b3cmlja77jzvvxhdxruzo5zg6w6qzhizzsmddx5e"
+    );
+    assert_eq!(operation.did().expect("should derive DID"), "did:plc:r4qfr7guallqhmjrned75hgw");
+}
+
+// Map keys sorted by length then bytewise: prev, type, services, alsoKnownAs,
+// rotationKeys, verificationMethods.
+const KNOWN_SIGNING_INPUT: &str = concat!(
+    "a66470726576f664747970656d706c635f6f7065726174696f6e687365727669636573a1",
+    "6b617470726f746f5f706473a264747970657819417470726f746f506572736f6e616c44",
+    "61746153657276657268656e64706f696e747768747470733a2f2f7064732e6578616d70",
+    "6c652e636f6d6b616c736f4b6e6f776e4173817661743a2f2f616c6963652e6578616d70",
+    "6c652e636f6d6c726f746174696f6e4b6579738278396469643a6b65793a7a5133736868",
+    "43475571444b6a53747a754478506b54784e36756a64645034526b454b4a4a6f754a4752",
+    "526b614c47626778396469643a6b65793a7a51337368704b6e62645078336733436d5066",
+    "356352565450653148745377566e356973683377536e44505143624c4a4b737665726966",
+    "69636174696f6e4d6574686f6473a167617470726f746f78396469643a6b65793a7a5133",
+    "7368586a486569427552434b6d4d33366375596e6d3759454d7a68476e436d4379573932",
+    "73524a39707269625346",
+);
+
+// The audit log can be fetched from a directory other than the default.
+#[tokio::test]
+async fn mirror_directory() {
+    let f = fixture();
+    let url = Url::from_str(&f.did).expect("should parse");
+    let mirror = "https://plc.mirror.example.com/";
+    assert_eq!(
+        url.to_plc_http_in(mirror),
+        format!("https://plc.mirror.example.com/{}/log/audit", f.did)
+    );
+
+    let directory = Directory::default();
+    directory.logs.lock().expect("should lock").insert(url.to_plc_http_in(mirror), vec![f.genesis]);
+    plc::resolve(&url, None, &directory).await.expect_err("should not use the default directory");
+    let doc = plc::resolve_in(mirror, &url, None, &directory).await.expect("should resolve");
+    assert_eq!(doc.id, f.did);
+}
+
+// An audit log in the format served by a PLC directory, with a genesis
+// operation and an update changing the handle.
+const AUDIT_LOG: &str = include_str!("fixtures/plc/audit_log.json");
+
+// A directory's audit log resolves to the latest version, or the version in
+// effect at a given time.
+#[tokio::test]
+async fn directory_audit_log() {
+    let log: Vec<LogEntry> = serde_json::from_str(AUDIT_LOG).expect("should deserialize");
+    let did = "did:plc:q77c7idakwidohumb6qdmoys";
+    let url = Url::from_str(did).expect("should parse");
+    let mirror = "https://plc.mirror.example.com";
+
+    let directory = Directory::default();
+    directory.logs.lock().expect("should lock").insert(url.to_plc_http_in(mirror), log);
+
+    let doc = plc::resolve_in(mirror, &url, None, &directory).await.expect("should resolve");
+    assert_eq!(doc.id, did);
+    assert_eq!(doc.also_known_as, Some(vec!["at://bob.example.com".to_string()]));
+
+    let options = ResolutionOptions {
+        version_time: Some("2025-03-15T00:00:00Z".to_string()),
+        ..ResolutionOptions::default()
+    };
+    let doc =
+        plc::resolve_in(mirror, &url, Some(&options), &directory).await.expect("should resolve");
+    assert_eq!(doc.also_known_as, Some(vec!["at://alice.example.com".to_string()]));
+}
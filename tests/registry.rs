@@ -0,0 +1,124 @@
+//! Tests for dispatching DID URLs to pluggable DID methods using a registry.
+
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail};
+use credibil_identity::did::{
+    BoxFuture, DidMethod, Document, DocumentBuilder, Method, Registry, ResolutionOptions, Resource,
+    Url, dereference,
+};
+use credibil_identity::{Identity, IdentityResolver};
+
+const DID_KEY: &str = "did:key:z6MkhaXgBZDvotDkL5257faiztiGiC2QtKLGpbnnEGta2doK";
+
+// Serves DID documents for the `did:example` method.
+#[derive(Clone)]
+struct ExampleResolver;
+
+impl IdentityResolver for ExampleResolver {
+    async fn resolve(&self, url: &str) -> anyhow::Result<Identity> {
+        let Some(did) = url.strip_prefix("https://registry.example.com/") else {
+            bail!("unexpected URL {url}");
+        };
+        Ok(Identity::DidDocument(DocumentBuilder::new(did).build()))
+    }
+}
+
+// A method not built into the crate whose IDs must be numeric.
+struct Example;
+
+impl<R: IdentityResolver> DidMethod<R> for Example {
+    fn name(&self) -> &'static str {
+        "example"
+    }
+
+    fn parse(&self, did_url: &str) -> anyhow::Result<Url> {
+        let url = Url::from_str(did_url)?;
+        if url.method != "example" || !url.id.chars().all(|c| c.is_ascii_digit()) {
+            bail!("invalid did:example {did_url}");
+        }
+        Ok(url)
+    }
+
+    fn resolve<'a>(
+        &'a self, url: &'a Url, _: Option<&'a ResolutionOptions>, resolver: &'a R,
+    ) -> BoxFuture<'a, anyhow::Result<Document>> {
+        Box::pin(async move {
+            let http_url = format!("https://registry.example.com/{}", url.did());
+            let Identity::DidDocument(doc) = resolver.resolve(&http_url).await? else {
+                bail!("resolver did not return a DID document");
+            };
+            Ok(doc)
+        })
+    }
+}
+
+// Replaces the built-in `did:key` method.
+struct NoKeys;
+
+impl<R: IdentityResolver> DidMethod<R> for NoKeys {
+    fn name(&self) -> &'static str {
+        "key"
+    }
+
+    fn resolve<'a>(
+        &'a self, url: &'a Url, _: Option<&'a ResolutionOptions>, _: &'a R,
+    ) -> BoxFuture<'a, anyhow::Result<Document>> {
+        Box::pin(async move { Err(anyhow!("did:key is disabled: {url}")) })
+    }
+}
+
+// URLs for methods not built into the crate parse but are only dereferenced
+// by a registry the method is registered with.
+#[tokio::test]
+async fn custom_method() {
+    let url = Url::from_str("did:example:123#key-1").expect("should parse");
+    assert_eq!(url.method, "example");
+    assert_eq!(url.to_string(), "did:example:123#key-1");
+
+    let err =
+        dereference("did:example:123", None, &ExampleResolver).await.expect_err("should fail");
+    assert_eq!(err.to_string(), "method not supported: example");
+
+    let registry = Registry::default().register(Example);
+    let resource = registry
+        .dereference("did:example:123", None, &ExampleResolver)
+        .await
+        .expect("should dereference");
+    let Resource::Document(doc) = resource else {
+        panic!("should be a document");
+    };
+    assert_eq!(doc.id, "did:example:123");
+
+    // the method's own parsing applies
+    registry.parse("did:example:123").expect("should parse");
+    registry.parse("did:example:abc").expect_err("should fail");
+
+    // built-in methods remain available
+    let resource =
+        registry.dereference(DID_KEY, None, &ExampleResolver).await.expect("should dereference");
+    assert!(matches!(resource, Resource::Document(_)));
+
+    let err =
+        Registry::new().dereference(DID_KEY, None, &ExampleResolver).await.expect_err("empty");
+    assert_eq!(err.to_string(), "method not supported: key");
+}
+
+// Registering a method with the same name as a built-in method replaces it.
+#[tokio::test]
+async fn replace_builtin() {
+    let registry = Registry::default().register(NoKeys);
+    let err = registry.dereference(DID_KEY, None, &ExampleResolver).await.expect_err("should fail");
+    assert!(err.to_string().starts_with("did:key is disabled"));
+}
+
+// Built-in methods validate the method-specific ID when parsing.
+#[test]
+fn builtin_parse() {
+    let registry = Registry::<ExampleResolver>::default();
+    let url = registry.parse(DID_KEY).expect("should parse");
+    assert_eq!(url.method, Method::Key);
+    registry.parse("did:key:z6Mk").expect_err("should fail");
+    registry.parse("did:plc:not-a-plc-id").expect_err("should fail");
+    registry.parse("did:Key:z6Mk").expect_err("invalid method name should fail");
+}
@@ -0,0 +1,136 @@
+//! Tests for producing and consuming the JSON, JSON-LD and CBOR
+//! representations of a DID document, and selecting a representation at
+//! resolution time.
+
+mod common;
+
+use credibil_identity::did::{
+    DID_CBOR, DID_JSON, DID_LD_JSON, Document, ErrorCode, Representation, ResolutionOptions,
+    resolve_did,
+};
+use serde_json::{Value, json};
+
+use common::WebResolver;
+
+const DID: &str = "did:web:example.com";
+
+// The shared test document with properties not defined by this crate.
+fn document() -> Document {
+    let mut doc = common::document(DID);
+    doc.additional.insert("nickname".to_string(), json!("example"));
+    doc.additional.insert("weight".to_string(), json!(-1.5));
+    let service = &mut doc.service.as_mut().expect("should have services")[0];
+    service.additional.insert("routingKeys".to_string(), json!(["did:example:mediator#key-1"]));
+    doc
+}
+
+// Unknown properties survive consumption and production in every
+// representation.
+#[test]
+fn round_trip() {
+    let doc = document();
+    assert_eq!(doc.additional["nickname"], "example");
+    let service = &doc.service.as_ref().expect("should have services")[0];
+    assert_eq!(service.additional["routingKeys"], json!(["did:example:mediator#key-1"]));
+
+    let json = doc.produce(Representation::Json).expect("should produce JSON");
+    let value: Value = serde_json::from_slice(&json).expect("should parse");
+    assert!(value.get("@context").is_none());
+    assert_eq!(value["nickname"], "example");
+    assert_eq!(value["service"][0]["routingKeys"][0], "did:example:mediator#key-1");
+    let consumed = Document::consume(&json, Representation::Json).expect("should consume");
+    assert_eq!(
+        consumed,
+        Document {
+            context: vec![],
+            ..doc.clone()
+        }
+    );
+
+    let ld = doc.produce(Representation::JsonLd).expect("should produce JSON-LD");
+    let consumed = Document::consume(&ld, Representation::JsonLd).expect("should consume");
+    assert_eq!(consumed, doc);
+
+    let cbor = doc.produce(Representation::Cbor).expect("should produce CBOR");
+    assert!(cbor.len() < json.len());
+    let consumed = Document::consume(&cbor, Representation::Cbor).expect("should consume");
+    assert_eq!(
+        consumed,
+        Document {
+            context: vec![],
+            ..doc
+        }
+    );
+}
+
+// JSON-LD requires the DID context first, and producing JSON-LD adds it.
+#[test]
+fn json_ld_context() {
+    let content = json!({ "@context": ["https://w3id.org/security/multikey/v1"], "id": DID });
+    let content = serde_json::to_vec(&content).expect("should serialize");
+    Document::consume(&content, Representation::JsonLd).expect_err("should fail");
+
+    let doc = Document::consume(&content, Representation::Json).expect("should consume");
+    let ld = doc.produce(Representation::JsonLd).expect("should produce JSON-LD");
+    let value: Value = serde_json::from_slice(&ld).expect("should parse");
+    assert_eq!(
+        value["@context"],
+        json!(["https://www.w3.org/ns/did/v1", "https://w3id.org/security/multikey/v1"])
+    );
+}
+
+// CBOR documents are encoded deterministically and invalid CBOR is rejected.
+#[test]
+fn cbor_encoding() {
+    let doc = Document::consume(br#"{"id":"did:web:a.b","n":-24,"f":0.5}"#, Representation::Json)
+        .expect("should consume");
+    let cbor = doc.produce(Representation::Cbor).expect("should produce CBOR");
+    assert_eq!(
+        cbor,
+        [
+            [0xa3, 0x61, b'f', 0xfa, 0x3f, 0x00, 0x00, 0x00].as_slice(),
+            &[0x61, b'n', 0x37],
+            &[0x62, b'i', b'd', 0x6b],
+            b"did:web:a.b",
+        ]
+        .concat()
+    );
+
+    // a map with a byte string value
+    Document::consume(&[0xa1, 0x62, b'i', b'd', 0x41, 0x00], Representation::Cbor)
+        .expect_err("should fail");
+    // truncated
+    Document::consume(&cbor[..cbor.len() - 1], Representation::Cbor).expect_err("should fail");
+    // not a map
+    Document::consume(&[0x80], Representation::Cbor).expect_err("should fail");
+}
+
+// The representation is selected using the `accept` resolution option.
+#[tokio::test]
+async fn resolve_representation() {
+    for (accept, content_type) in [
+        (None, DID_JSON),
+        (Some("application/did+ld+json"), DID_LD_JSON),
+        (Some("application/did+json;q=0.5, application/did+cbor"), DID_CBOR),
+        (Some("*/*"), DID_JSON),
+    ] {
+        let options = ResolutionOptions {
+            accept: accept.map(ToString::to_string),
+            ..ResolutionOptions::default()
+        };
+        let result = resolve_did(DID, Some(&options), &WebResolver::new([document()])).await;
+        assert_eq!(result.did_resolution_metadata.content_type.as_deref(), Some(content_type));
+
+        let stream = result.did_document_stream().expect("should produce").expect("should exist");
+        let representation = content_type.parse().expect("should parse");
+        let doc = Document::consume(&stream, representation).expect("should consume");
+        assert_eq!(doc.id, DID);
+    }
+
+    let options = ResolutionOptions {
+        accept: Some("application/did+cbor;q=0".to_string()),
+        ..ResolutionOptions::default()
+    };
+    let result = resolve_did(DID, Some(&options), &WebResolver::new([document()])).await;
+    assert_eq!(result.error_code(), Some(ErrorCode::RepresentationNotSupported));
+}
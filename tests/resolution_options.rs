@@ -0,0 +1,160 @@
+//! Tests for resolution options: validation, combination with DID URL query
+//! parameters and passing them on to the identity resolver.
+
+use std::sync::{Arc, Mutex};
+
+use credibil_identity::did::{
+    DocumentBuilder, DocumentMetadataBuilder, ErrorCode, QueryParams, ResolutionOptions, Resource,
+    dereference, resolve_did,
+};
+use credibil_identity::{Error, Identity, IdentityResolver};
+
+const DID_KEY: &str = "did:key:z6MkhaXgBZDvotDkL5257faiztiGiC2QtKLGpbnnEGta2doK";
+const DID_WEBVH: &str = "did:webvh:QmaJp6pmb6RUk4oaDyWQcjeqYbvxsc3kvmHWPpz7B5JwDU:example.com";
+
+// Serves a single version of a `did:webvh` document, recording the options
+// it is called with.
+#[derive(Clone, Default)]
+struct RecordingResolver {
+    options: Arc<Mutex<Vec<ResolutionOptions>>>,
+}
+
+impl IdentityResolver for RecordingResolver {
+    async fn resolve(&self, url: &str) -> anyhow::Result<Identity> {
+        self.resolve_with_options(url, &ResolutionOptions::default()).await
+    }
+
+    async fn resolve_with_options(
+        &self, _url: &str, options: &ResolutionOptions,
+    ) -> anyhow::Result<Identity> {
+        self.options.lock().expect("should lock").push(options.clone());
+        let md = DocumentMetadataBuilder::new()
+            .additional("versionId", "2-QmHash")
+            .additional("versionTime", "2025-01-01T00:00:00Z")
+            .build();
+        Ok(Identity::DidDocument(DocumentBuilder::new(DID_WEBVH).metadata(md).build()))
+    }
+}
+
+impl RecordingResolver {
+    fn last(&self) -> ResolutionOptions {
+        self.options.lock().expect("should lock").last().cloned().expect("should be called")
+    }
+}
+
+// Serves a `did:webvh` document without version metadata.
+#[derive(Clone)]
+struct UnversionedResolver;
+
+impl IdentityResolver for UnversionedResolver {
+    async fn resolve(&self, _url: &str) -> anyhow::Result<Identity> {
+        Ok(Identity::DidDocument(DocumentBuilder::new(DID_WEBVH).build()))
+    }
+}
+
+// Options and query parameters combine as long as they agree.
+#[test]
+fn merge() {
+    let options = ResolutionOptions {
+        version_id: Some("2-QmHash".to_string()),
+        no_cache: true,
+        ..ResolutionOptions::default()
+    };
+    let query = QueryParams {
+        version_id: Some("2-QmHash".to_string()),
+        version_time: Some("2025-01-01T00:00:00Z".to_string()),
+        ..QueryParams::default()
+    };
+    let merged = ResolutionOptions::merge(Some(&options), Some(&query)).expect("should merge");
+    assert_eq!(merged.version_time, query.version_time);
+    assert!(merged.no_cache);
+    assert!(merged.selects_version());
+
+    let conflict = QueryParams {
+        version_id: Some("1-QmOther".to_string()),
+        ..QueryParams::default()
+    };
+    let err = ResolutionOptions::merge(Some(&options), Some(&conflict)).expect_err("should fail");
+    let Some(Error::Resolution(err)) = err.downcast_ref::<Error>() else {
+        panic!("should be a resolution error");
+    };
+    assert_eq!(err.code(), ErrorCode::InvalidOptions);
+
+    let json = serde_json::to_value(&options).expect("should serialize");
+    assert_eq!(json, serde_json::json!({ "versionId": "2-QmHash", "noCache": true }));
+}
+
+// Options are passed on to the resolver and the resolved version is checked
+// against the version requested.
+#[tokio::test]
+async fn webvh_version() {
+    let resolver = RecordingResolver::default();
+    let options = ResolutionOptions {
+        version_id: Some("2-QmHash".to_string()),
+        no_cache: true,
+        ..ResolutionOptions::default()
+    };
+    let resource =
+        dereference(DID_WEBVH, Some(&options), &resolver).await.expect("should dereference");
+    assert!(matches!(resource, Resource::Document(_)));
+    assert_eq!(resolver.last(), options);
+
+    // query parameters are passed on as options
+    let url = format!("{DID_WEBVH}?versionTime=2025-06-01T00:00:00Z");
+    dereference(&url, None, &resolver).await.expect("should dereference");
+    assert_eq!(resolver.last().version_time.as_deref(), Some("2025-06-01T00:00:00Z"));
+
+    let options = ResolutionOptions {
+        version_id: Some("1-QmOther".to_string()),
+        ..ResolutionOptions::default()
+    };
+    dereference(DID_WEBVH, Some(&options), &resolver).await.expect_err("wrong version");
+
+    let options = ResolutionOptions {
+        version_time: Some("2024-01-01T00:00:00Z".to_string()),
+        ..ResolutionOptions::default()
+    };
+    dereference(DID_WEBVH, Some(&options), &resolver).await.expect_err("later version");
+
+    // a document without version metadata is not the selected version
+    let err = dereference(DID_WEBVH, Some(&options), &UnversionedResolver)
+        .await
+        .expect_err("should fail");
+    let Some(Error::Resolution(err)) = err.downcast_ref::<Error>() else {
+        panic!("should be a resolution error");
+    };
+    assert_eq!(err.code(), ErrorCode::NotFound);
+    dereference(DID_WEBVH, None, &UnversionedResolver).await.expect("should dereference");
+}
+
+// Methods without a version history reject version selection, and
+// unsupported representations are rejected.
+#[tokio::test]
+async fn unsupported_options() {
+    let resolver = RecordingResolver::default();
+    let options = ResolutionOptions {
+        version_id: Some("1".to_string()),
+        ..ResolutionOptions::default()
+    };
+    let result = resolve_did(DID_KEY, Some(&options), &resolver).await;
+    assert_eq!(result.error_code(), Some(ErrorCode::InvalidOptions));
+    assert_eq!(result.http_status(), 400);
+
+    let result = resolve_did("did:web:example.com", Some(&options), &resolver).await;
+    assert_eq!(result.error_code(), Some(ErrorCode::InvalidOptions));
+
+    let options = ResolutionOptions {
+        accept: Some("application/xml".to_string()),
+        ..ResolutionOptions::default()
+    };
+    let result = resolve_did(DID_KEY, Some(&options), &resolver).await;
+    assert_eq!(result.error_code(), Some(ErrorCode::RepresentationNotSupported));
+    assert_eq!(result.http_status(), 406);
+
+    let options = ResolutionOptions {
+        accept: Some("application/xml, application/did+json;q=0.5".to_string()),
+        ..ResolutionOptions::default()
+    };
+    let result = resolve_did(DID_KEY, Some(&options), &resolver).await;
+    assert!(result.error_code().is_none());
+}
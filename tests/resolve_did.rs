@@ -0,0 +1,107 @@
+//! Tests for resolving DIDs into W3C DID Resolution results, including
+//! resolution metadata and standard error codes.
+
+mod common;
+
+use credibil_identity::did::{
+    DID_JSON, DocumentBuilder, DocumentMetadataBuilder, ErrorCode, ResolutionResult, resolve_did,
+};
+use serde_json::json;
+
+use common::{NoopResolver, WebResolver};
+
+const DID_KEY: &str = "did:key:z6MkhaXgBZDvotDkL5257faiztiGiC2QtKLGpbnnEGta2doK";
+
+// Serves an active and a deactivated `did:web` document.
+fn resolver() -> WebResolver {
+    let md = DocumentMetadataBuilder::new().deactivated(true).build();
+    WebResolver::new([
+        DocumentBuilder::new("did:web:active.example.com").build(),
+        DocumentBuilder::new("did:web:retired.example.com").metadata(md).build(),
+    ])
+}
+
+// A successful resolution separates the document from its metadata.
+#[tokio::test]
+async fn resolved() {
+    let did = "did:web:active.example.com";
+    let result = resolve_did(did, None, &resolver()).await;
+    assert_eq!(result.http_status(), 200);
+    assert_eq!(result.did_resolution_metadata.content_type.as_deref(), Some(DID_JSON));
+    assert!(result.error_code().is_none());
+
+    let doc = result.did_document.as_ref().expect("should have document");
+    assert_eq!(doc.id, did);
+    assert!(doc.did_document_metadata.is_none());
+    assert!(result.did_document_metadata.is_some());
+
+    let json = serde_json::to_value(&result).expect("should serialize");
+    assert_eq!(json["didResolutionMetadata"], json!({ "contentType": DID_JSON }));
+    assert_eq!(json["didDocument"]["id"], did);
+    assert!(json["didDocumentMetadata"]["created"].is_string());
+
+    let de: ResolutionResult = serde_json::from_value(json.clone()).expect("should deserialize");
+    assert_eq!(serde_json::to_value(de).expect("should serialize"), json);
+
+    // self-describing methods have no document metadata
+    let result = resolve_did(DID_KEY, None, &resolver()).await;
+    assert_eq!(result.did_document.expect("should have document").id, DID_KEY);
+    let json = serde_json::to_value(ResolutionResult::default()).expect("should serialize");
+    assert_eq!(json["didDocumentMetadata"], json!({}));
+}
+
+// Errors are reported as problem details with a standard error code.
+#[tokio::test]
+async fn errors() {
+    let cases = [
+        ("not-a-did", ErrorCode::InvalidDid, 400),
+        ("did:key:z6Mk", ErrorCode::InvalidDid, 400),
+        (&format!("{DID_KEY}#key-1"), ErrorCode::InvalidDid, 400),
+        ("did:example:123", ErrorCode::MethodNotSupported, 501),
+        ("did:web:missing.example.com", ErrorCode::NotFound, 404),
+    ];
+    for (did, code, status) in cases {
+        let result = resolve_did(did, None, &resolver()).await;
+        assert_eq!(result.error_code(), Some(code), "{did}");
+        assert_eq!(result.http_status(), status, "{did}");
+        assert!(result.did_document.is_none());
+        assert!(result.did_resolution_metadata.content_type.is_none());
+    }
+
+    // a failing resolver is an internal error
+    let result = resolve_did("did:web:broken.example.com", None, &NoopResolver).await;
+    assert_eq!(result.error_code(), Some(ErrorCode::InternalError));
+    assert_eq!(result.http_status(), 500);
+
+    let result = resolve_did("did:web:missing.example.com", None, &resolver()).await;
+    let json = serde_json::to_value(&result).expect("should serialize");
+    assert_eq!(
+        json,
+        json!({
+            "didResolutionMetadata": {
+                "error": {
+                    "type": "https://www.w3.org/ns/did#NOT_FOUND",
+                    "title": "DID not found",
+                    "detail": "no DID at https://missing.example.com/.well-known/did.json"
+                }
+            },
+            "didDocumentMetadata": {}
+        })
+    );
+    let de: ResolutionResult = serde_json::from_value(json).expect("should deserialize");
+    assert_eq!(de, result);
+}
+
+// A deactivated DID resolves, but maps to `410 Gone`.
+#[tokio::test]
+async fn deactivated() {
+    let result = resolve_did("did:web:retired.example.com", None, &resolver()).await;
+    assert!(result.error_code().is_none());
+    assert!(result.deactivated());
+    assert!(result.did_document.is_some());
+    assert_eq!(result.http_status(), 410);
+
+    let result = resolve_did("did:web:active.example.com", None, &resolver()).await;
+    assert!(!result.deactivated());
+    assert_eq!(result.http_status(), 200);
+}
@@ -0,0 +1,146 @@
+//! Tests for checking DID documents conform to DID Core, and for builders
+//! refusing to emit non-conformant documents.
+
+use credibil_identity::Error;
+use credibil_identity::core::Kind;
+use credibil_identity::did::webvh::{CreateBuilder, UpdateBuilder, default_did};
+use credibil_identity::did::{
+    Document, DocumentBuilder, FindingKind, KeyPurpose, ServiceBuilder, VerificationMethod,
+};
+use kms::Keyring;
+use serde_json::json;
+
+const DID: &str = "did:web:example.com";
+const KEY: &str = "z6MkhaXgBZDvotDkL5257faiztiGiC2QtKLGpbnnEGta2doK";
+
+fn method(id: &str, method_type: &str, key: &str) -> serde_json::Value {
+    json!({
+        "id": id,
+        "type": method_type,
+        "controller": DID,
+        "publicKeyMultibase": key
+    })
+}
+
+// A conformant document has no findings.
+#[test]
+fn conformant() {
+    let doc: Document = serde_json::from_value(json!({
+        "@context": ["https://www.w3.org/ns/did/v1"],
+        "id": DID,
+        "controller": ["did:example:123", "did:web:example.com%3A8443:users:alice"],
+        "verificationMethod": [
+            method("#key-1", "Multikey", KEY),
+            method(&format!("{DID}#key-2"), "Ed25519VerificationKey2020", KEY)
+        ],
+        "authentication": ["#key-1", format!("{DID}#key-2")],
+        "keyAgreement": [method(
+            "#key-3",
+            "X25519KeyAgreementKey2020",
+            "z6LSbysY2xFMRpGMhb7tFTLMpeuPRaqaWM1yECx2AtzE3KCc"
+        )],
+        "service": [{
+            "id": "#didcomm",
+            "type": "DIDCommMessaging",
+            "serviceEndpoint": [{ "uri": "https://example.com/didcomm" }, "didcomm:transport/queue"]
+        }]
+    }))
+    .expect("should deserialize");
+    assert_eq!(doc.validate(), []);
+}
+
+// Each kind of problem is reported with the path to the offending value.
+#[test]
+fn findings() {
+    let doc: Document = serde_json::from_value(json!({
+        "@context": ["https://www.w3.org/ns/did/v1"],
+        "id": "did:Web:example.com",
+        "controller": "example.com",
+        "verificationMethod": [
+            method("#key-1", "Multikey", KEY),
+            method("#key-1", "Multikey", KEY),
+            method("key-3", "X25519KeyAgreementKey2020", KEY),
+            {
+                "id": "#key-4",
+                "type": "Multikey",
+                "controller": DID,
+                "publicKeyJwk": {
+                    "kty": "OKP",
+                    "crv": "Ed25519",
+                    "x": "11qYAYKxCrfVS_7TyWQHOg7hcvPapiMlrwIaaPcHURo"
+                }
+            }
+        ],
+        "assertionMethod": ["#key-1", "#key-typo"],
+        "service": [{
+            "id": "#key-1",
+            "type": "LinkedDomains",
+            "serviceEndpoint": "not a uri"
+        }]
+    }))
+    .expect("should deserialize");
+
+    let findings = doc.validate().into_iter().map(|f| (f.kind, f.path)).collect::<Vec<_>>();
+    assert_eq!(
+        findings,
+        [
+            (FindingKind::InvalidDid, "/id".to_string()),
+            (FindingKind::InvalidDid, "/controller".to_string()),
+            (FindingKind::DuplicateId, "/verificationMethod/1/id".to_string()),
+            (FindingKind::InvalidDidUrl, "/verificationMethod/2/id".to_string()),
+            (FindingKind::KeyMismatch, "/verificationMethod/2".to_string()),
+            (FindingKind::KeyMismatch, "/verificationMethod/3".to_string()),
+            (FindingKind::UnresolvedReference, "/assertionMethod/1".to_string()),
+            (FindingKind::DuplicateId, "/service/0/id".to_string()),
+            (FindingKind::InvalidServiceEndpoint, "/service/0/serviceEndpoint".to_string()),
+        ]
+    );
+}
+
+// Builders can refuse to emit a non-conformant document.
+#[tokio::test]
+async fn refuse_non_conformant() {
+    let typo = Kind::<VerificationMethod>::String(format!("{DID}#key-typo"));
+    let err = DocumentBuilder::new(DID)
+        .add_verification_method(&typo, &KeyPurpose::Authentication)
+        .expect("should add reference")
+        .build_conformant()
+        .expect_err("should refuse");
+    let Some(Error::Conformance(findings)) = err.downcast_ref::<Error>() else {
+        panic!("should be a conformance error");
+    };
+    assert_eq!(findings[0].kind, FindingKind::UnresolvedReference);
+
+    let mut signer = Keyring::new("validate_refuse").await.expect("should create keyring");
+    let update_multi = signer.multibase("signing").await.expect("should get multibase key");
+    let did = default_did("https://credibil.io/issuers/example").expect("should get default DID");
+    // the `{SCID}` placeholder is only replaced when the log entry is built
+    let doc = DocumentBuilder::new(&did).build();
+    let created = CreateBuilder::new()
+        .document(&doc)
+        .expect("should apply document")
+        .update_keys(&[&update_multi])
+        .expect("should apply update keys")
+        .signer(&signer)
+        .conformant(true)
+        .build()
+        .await
+        .expect("should build document");
+
+    let service = ServiceBuilder::new(&format!("{}#files", created.did))
+        .service_type(&"relativeRef")
+        .endpoint_str(&"files")
+        .build();
+    let doc = DocumentBuilder::from(&created.document).add_service(&service).build();
+    let err = UpdateBuilder::from(created.log.as_slice(), None)
+        .await
+        .expect("should create builder")
+        .document(&doc)
+        .expect("should apply document")
+        .conformant(true)
+        .signer(&signer)
+        .build()
+        .await
+        .expect_err("should refuse");
+    assert!(matches!(err.downcast_ref::<Error>(), Some(Error::Conformance(_))));
+}
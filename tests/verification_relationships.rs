@@ -0,0 +1,60 @@
+//! Tests for looking up the verification methods a DID document authorizes
+//! for each purpose.
+
+use credibil_identity::did::{Document, KeyPurpose};
+use serde_json::json;
+
+const DID: &str = "did:web:example.com";
+const KEY: &str = "z6MkhaXgBZDvotDkL5257faiztiGiC2QtKLGpbnnEGta2doK";
+
+fn document() -> Document {
+    let method = |id: &str| {
+        json!({
+            "id": id,
+            "type": "Multikey",
+            "controller": DID,
+            "publicKeyMultibase": KEY
+        })
+    };
+    serde_json::from_value(json!({
+        "@context": ["https://www.w3.org/ns/did/v1"],
+        "id": DID,
+        "verificationMethod": [method("#key-1"), method(&format!("{DID}#key-2"))],
+        "authentication": ["#key-1", method("#auth-1")],
+        "assertionMethod": [format!("{DID}#key-2"), "#missing"],
+        "capabilityInvocation": ["#auth-1"]
+    }))
+    .expect("should deserialize")
+}
+
+// Referenced and embedded methods are returned for each purpose.
+#[test]
+fn methods_for_purpose() {
+    let doc = document();
+
+    let ids = |purpose| {
+        doc.verification_methods(&purpose).iter().map(|vm| vm.id.clone()).collect::<Vec<_>>()
+    };
+    assert_eq!(ids(KeyPurpose::VerificationMethod), ["#key-1", format!("{DID}#key-2").as_str()]);
+    assert_eq!(ids(KeyPurpose::Authentication), ["#key-1", "#auth-1"]);
+    assert_eq!(ids(KeyPurpose::AssertionMethod), [format!("{DID}#key-2")]);
+    assert_eq!(ids(KeyPurpose::CapabilityInvocation), ["#auth-1"]);
+    assert_eq!(ids(KeyPurpose::KeyAgreement), Vec::<String>::new());
+}
+
+// A method is only authorized for the purposes that embed or reference it.
+#[test]
+fn authorized() {
+    let doc = document();
+
+    assert!(doc.is_authorized(&format!("{DID}#key-1"), &KeyPurpose::Authentication));
+    assert!(doc.is_authorized("#key-2", &KeyPurpose::AssertionMethod));
+    assert!(doc.is_authorized(&format!("{DID}#auth-1"), &KeyPurpose::Authentication));
+    assert!(doc.is_authorized("#auth-1", &KeyPurpose::CapabilityInvocation));
+    assert!(doc.is_authorized("#key-2", &KeyPurpose::VerificationMethod));
+
+    assert!(!doc.is_authorized("#key-1", &KeyPurpose::AssertionMethod));
+    assert!(!doc.is_authorized("#auth-1", &KeyPurpose::VerificationMethod));
+    assert!(!doc.is_authorized("#missing", &KeyPurpose::AssertionMethod));
+    assert!(!doc.is_authorized("did:web:other.com#key-1", &KeyPurpose::Authentication));
+}
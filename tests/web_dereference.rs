@@ -2,10 +2,12 @@
 
 use std::str::FromStr;
 
+use credibil_identity::Error;
 use credibil_identity::core::Kind;
-use credibil_identity::did::{document_resource, web, DocumentBuilder, KeyPurpose, MethodType, PublicKeyFormat, Resource, ServiceBuilder, Url, VerificationMethod, VerificationMethodBuilder, VmKeyId
+use credibil_identity::did::{document_resource, service_endpoints, web, Document, DocumentBuilder, ErrorCode, KeyPurpose, MethodType, PublicKeyFormat, Resource, ServiceBuilder, Url, VerificationMethod, VerificationMethodBuilder, VmKeyId
 };
 use kms::Keyring;
+use serde_json::json;
 
 // Create a new `did:web` document and dereference a resource from it.
 #[tokio::test]
@@ -37,7 +39,7 @@ async fn create_then_deref() {
 
     let url = Url::from_str(&vm.id).expect("should parse DID");
 
-    let deref_vm = document_resource(&url, &doc).expect("should dereference VM");
+    let deref_vm = document_resource(&url, None, &doc).expect("should dereference VM");
     let Resource::VerificationMethod(deref_vm) = deref_vm else {
         panic!("should be a verification method");
     };
@@ -47,3 +49,118 @@ async fn create_then_deref() {
     };
     assert_eq!(public_key_jwk.x, vk.x);
 }
+
+// Dereference a service endpoint URL, resolving a relative reference against
+// each kind of endpoint.
+#[tokio::test]
+async fn deref_service() {
+    let did = web::default_did("https://credibil.io/issuers/example").expect("should get DID");
+
+    let files = ServiceBuilder::new(&format!("{did}#files"))
+        .service_type(&"LinkedResource")
+        .endpoint_str(&"https://credibil.io/files/")
+        .add_endpoint_str(&"https://mirror.credibil.io/files/")
+        .build();
+    let messaging = ServiceBuilder::new(&"#messaging")
+        .service_type(&"DIDCommMessaging")
+        .endpoint_json(&json!({"uri": "https://credibil.io/didcomm", "accept": ["didcomm/v2"]}))
+        .build();
+    let doc = DocumentBuilder::new(&did).add_service(&files).add_service(&messaging).build();
+
+    let deref = |s: &str| document_resource(&Url::from_str(s).expect("should parse"), None, &doc);
+
+    let resource = deref(&format!("{did}?service=files&relativeRef=/schema.json")).expect("deref");
+    let Resource::Url(url) = resource else {
+        panic!("should be a URL");
+    };
+    assert_eq!(url, "https://credibil.io/schema.json");
+
+    let resource = deref(&format!("{did}?service=files&relativeRef=v1%2Fschema.json#name"))
+        .expect("should dereference");
+    let Resource::Url(url) = resource else {
+        panic!("should be a URL");
+    };
+    assert_eq!(url, "https://credibil.io/files/v1/schema.json#name");
+
+    let resource = deref(&format!("{did}?service=messaging")).expect("should dereference");
+    let Resource::Url(url) = resource else {
+        panic!("should be a URL");
+    };
+    assert_eq!(url, "https://credibil.io/didcomm");
+
+    // every endpoint is resolved
+    let urls = service_endpoints(&files, Some("schema.json")).expect("should construct URLs");
+    assert_eq!(urls, [
+        "https://credibil.io/files/schema.json",
+        "https://mirror.credibil.io/files/schema.json"
+    ]);
+
+    let err = deref(&format!("{did}?service=missing")).expect_err("should fail");
+    let Some(Error::Resolution(err)) = err.downcast_ref::<Error>() else {
+        panic!("should be a resolution error");
+    };
+    assert_eq!(err.code(), ErrorCode::NotFound);
+
+    let err = deref(&format!("{did}?relativeRef=/schema.json")).expect_err("should fail");
+    let Some(Error::Resolution(err)) = err.downcast_ref::<Error>() else {
+        panic!("should be a resolution error");
+    };
+    assert_eq!(err.code(), ErrorCode::InvalidDidUrl);
+}
+
+// Dereference fragments in a document produced by another implementation,
+// using relative IDs and embedded verification methods.
+#[test]
+fn deref_fragments() {
+    let did = "did:web:example.com";
+    let doc: Document = serde_json::from_value(json!({
+        "@context": ["https://www.w3.org/ns/did/v1"],
+        "id": did,
+        "verificationMethod": [{
+            "id": "#key-1",
+            "type": "Multikey",
+            "controller": did,
+            "publicKeyMultibase": "z6MkhaXgBZDvotDkL5257faiztiGiC2QtKLGpbnnEGta2doK"
+        }],
+        "authentication": ["#key-1", {
+            "id": format!("{did}#auth-1"),
+            "type": "Multikey",
+            "controller": did,
+            "publicKeyMultibase": "z6MkhaXgBZDvotDkL5257faiztiGiC2QtKLGpbnnEGta2doK"
+        }],
+        "keyAgreement": [{
+            "id": "#agreement-1",
+            "type": "Multikey",
+            "controller": did,
+            "publicKeyMultibase": "z6LSbysY2xFMRpGMhb7tFTLMpeuPRaqaWM1yECx2AtzE3KCc"
+        }],
+        "service": [{
+            "id": "#whois",
+            "type": "LinkedVerifiablePresentation",
+            "serviceEndpoint": "https://example.com/.well-known/whois.vp"
+        }]
+    }))
+    .expect("should deserialize");
+
+    let deref = |s: &str| document_resource(&Url::from_str(s).expect("should parse"), None, &doc);
+
+    for fragment in ["key-1", "auth-1", "agreement-1"] {
+        let resource = deref(&format!("{did}#{fragment}")).expect("should dereference");
+        let Resource::VerificationMethod(vm) = resource else {
+            panic!("should be a verification method");
+        };
+        assert!(vm.id.ends_with(&format!("#{fragment}")));
+    }
+
+    let resource = deref(&format!("{did}#whois")).expect("should dereference");
+    let Resource::Service(service) = resource else {
+        panic!("should be a service");
+    };
+    assert_eq!(service.type_, "LinkedVerifiablePresentation");
+
+    let err = deref(&format!("{did}#missing")).expect_err("should fail");
+    let Some(Error::Resolution(err)) = err.downcast_ref::<Error>() else {
+        panic!("should be a resolution error");
+    };
+    assert_eq!(err.code(), ErrorCode::NotFound);
+}
@@ -0,0 +1,273 @@
+//! Tests for end-to-end resolution of a `did:webvh` DID from its published
+//! `did.jsonl` and `did-witness.json` files.
+
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+
+use anyhow::bail;
+use credibil_identity::did::webvh::{
+    CreateBuilder, CreateResult, Fetcher, Resolver, Witness, WitnessEntry, WitnessWeight,
+    default_did, resolve_http,
+};
+use credibil_identity::did::{
+    DocumentBuilder, ErrorCode, Registry, Resource, ServiceBuilder, Url, hashlink,
+};
+use credibil_identity::{Error, Identity, IdentityResolver, Key, LogErrorKind, SignerExt};
+use kms::Keyring;
+
+const LOG_URL: &str = "https://credibil.io/issuers/example/did.jsonl";
+const WITNESS_URL: &str = "https://credibil.io/issuers/example/did-witness.json";
+
+// Serves published files from memory.
+#[derive(Clone, Default)]
+struct Files {
+    files: Arc<Mutex<HashMap<String, Vec<u8>>>>,
+}
+
+impl Files {
+    fn publish(&self, url: &str, content: impl Into<Vec<u8>>) {
+        self.files.lock().expect("should lock").insert(url.to_string(), content.into());
+    }
+}
+
+impl Fetcher for Files {
+    async fn fetch(&self, url: &str) -> anyhow::Result<Option<Vec<u8>>> {
+        Ok(self.files.lock().expect("should lock").get(url).cloned())
+    }
+}
+
+// The identity resolver is not used when the log is fetched directly.
+#[derive(Clone)]
+struct NoResolver;
+
+impl IdentityResolver for NoResolver {
+    async fn resolve(&self, url: &str) -> anyhow::Result<Identity> {
+        bail!("unexpected resolution of {url}")
+    }
+}
+
+// Create a DID with two witnesses, returning the result and the witness
+// proofs for the log entry.
+async fn create(name: &str) -> (CreateResult, Vec<WitnessEntry>) {
+    let mut signer = Keyring::new(name).await.expect("should create keyring");
+    let update_multi = signer.multibase("signing").await.expect("should get multibase key");
+
+    let mut witness_keyrings = vec![];
+    let mut witnesses = vec![];
+    for i in 0..2 {
+        let keyring =
+            Keyring::new(format!("{name}_witness{i}")).await.expect("should create keyring");
+        let Key::KeyId(id) = keyring.verification_method().await.expect("should get key id") else {
+            panic!("should get key id");
+        };
+        witnesses.push(WitnessWeight { id, weight: 50 });
+        witness_keyrings.push(keyring);
+    }
+
+    let did = default_did("https://credibil.io/issuers/example").expect("should get default DID");
+    let doc = DocumentBuilder::new(&did).build();
+    let result = CreateBuilder::new()
+        .document(&doc)
+        .expect("should apply document")
+        .update_keys(&[&update_multi])
+        .expect("should apply update keys")
+        .witness(&Witness {
+            threshold: 100,
+            witnesses,
+        })
+        .expect("should apply witnesses")
+        .signer(&signer)
+        .build()
+        .await
+        .expect("should build document");
+
+    let mut proof = vec![];
+    for keyring in &witness_keyrings {
+        proof.push(result.log[0].proof(keyring).await.expect("should get witness proof"));
+    }
+    let witness_proofs = vec![WitnessEntry {
+        version_id: result.log[0].version_id.clone(),
+        proof,
+    }];
+    (result, witness_proofs)
+}
+
+// Serialize a log to JSON Lines.
+fn jsonl(result: &CreateResult, separator: &str) -> String {
+    let lines = result.log.iter().map(|e| serde_json::to_string(e).expect("should serialize"));
+    lines.collect::<Vec<_>>().join(separator)
+}
+
+// Fetch, verify and resolve the published log.
+#[tokio::test]
+async fn resolve_published() {
+    let (result, witness_proofs) = create("webvh_fetch_resolve").await;
+
+    let files = Files::default();
+    files.publish(LOG_URL, format!("{}\r\n\r\n", jsonl(&result, "\r\n")));
+    files.publish(WITNESS_URL, serde_json::to_vec(&witness_proofs).expect("should serialize"));
+
+    let url = Url::from_str(&result.did).expect("should parse");
+    let doc = resolve_http(&url, None, &files).await.expect("should resolve");
+    assert_eq!(doc.id, result.did);
+
+    // witness proofs are published using camel case
+    let json = serde_json::to_value(&witness_proofs).expect("should serialize");
+    assert!(json[0]["versionId"].is_string());
+
+    // the version requested must be in the log
+    let url = Url::from_str(&format!("{}?versionId=2-QmMissing", result.did)).expect("parse");
+    let err = resolve_http(&url, None, &files).await.expect_err("should fail");
+    let Some(Error::Resolution(err)) = err.downcast_ref::<Error>() else {
+        panic!("should be a resolution error");
+    };
+    assert_eq!(err.code(), ErrorCode::NotFound);
+
+    // the method can replace the built-in `did:webvh` method
+    let registry = Registry::default().register(Resolver::new(files.clone()));
+    let resource =
+        registry.dereference(&result.did, None, &NoResolver).await.expect("should dereference");
+    let Resource::Document(doc) = resource else {
+        panic!("should be a document");
+    };
+    assert_eq!(doc.id, result.did);
+
+    let result = registry.resolve_did("did:webvh:QmMissing:example.com", None, &NoResolver).await;
+    assert_eq!(result.error_code(), Some(ErrorCode::NotFound));
+}
+
+// A log that does not verify is rejected.
+#[tokio::test]
+async fn reject_unverified() {
+    let (result, witness_proofs) = create("webvh_fetch_reject").await;
+    let url = Url::from_str(&result.did).expect("should parse");
+
+    // witness proofs are required for DIDs with witnesses
+    let files = Files::default();
+    files.publish(LOG_URL, jsonl(&result, "\n"));
+    let err = resolve_http(&url, None, &files).await.expect_err("should fail");
+    let err = err.downcast_ref::<Error>().expect("should be a typed error");
+    let Error::LogVerification { kind, .. } = err else {
+        panic!("should be a log verification error");
+    };
+    assert_eq!(*kind, LogErrorKind::Witness);
+
+    // the log must be for the requested DID
+    files.publish(WITNESS_URL, serde_json::to_vec(&witness_proofs).expect("should serialize"));
+    let scid = result.log[0].parameters.scid.as_str();
+    let other = Url::from_str(&result.did.replace(scid, "QmOther")).expect("should parse");
+    let err = resolve_http(&other, None, &files).await.expect_err("should fail");
+    let err = err.downcast_ref::<Error>().expect("should be a typed error");
+    let Error::LogVerification { kind, .. } = err else {
+        panic!("should be a log verification error");
+    };
+    assert_eq!(*kind, LogErrorKind::Did);
+    assert_eq!(err.version_id(), Some(result.log[0].version_id.as_str()));
+
+    // tampered entries fail verification
+    let mut tampered = result.clone();
+    tampered.log[0].parameters.ttl += 1;
+    files.publish(LOG_URL, jsonl(&tampered, "\n"));
+    let err = resolve_http(&url, None, &files).await.expect_err("should fail");
+    assert_eq!(
+        err.downcast_ref::<Error>().and_then(Error::version_id),
+        Some(result.log[0].version_id.as_str())
+    );
+}
+
+// DID URL paths dereference to files published alongside the log, using the
+// implicit `#files` and `#whois` services.
+#[tokio::test]
+async fn dereference_paths() {
+    let (result, witness_proofs) = create("webvh_fetch_paths").await;
+
+    let files = Files::default();
+    files.publish(LOG_URL, jsonl(&result, "\n"));
+    files.publish(WITNESS_URL, serde_json::to_vec(&witness_proofs).expect("should serialize"));
+    files.publish("https://credibil.io/issuers/example/schemas/v1.json", "{}");
+    files.publish("https://credibil.io/issuers/example/whois.vp", "presentation");
+
+    // the implicit services are added to the resolved document
+    let url = Url::from_str(&result.did).expect("should parse");
+    let doc = resolve_http(&url, None, &files).await.expect("should resolve");
+    let service = doc.get_service("#whois").expect("should have whois service");
+    assert_eq!(service.type_, "LinkedVerifiablePresentation");
+    assert!(doc.get_service("#files").is_some());
+
+    let registry = Registry::default().register(Resolver::new(files.clone()));
+    let deref = async |path: &str| {
+        registry.dereference(&format!("{}{path}", result.did), None, &NoResolver).await
+    };
+
+    let Resource::Content(content) = deref("/schemas/v1.json").await.expect("should dereference")
+    else {
+        panic!("should be content");
+    };
+    assert_eq!(content, b"{}");
+
+    let Resource::Content(content) = deref("/whois").await.expect("should dereference") else {
+        panic!("should be content");
+    };
+    assert_eq!(content, b"presentation");
+
+    // file content is verified against the hashlink
+    let hl = hashlink(b"presentation");
+    deref(&format!("/whois?hl={hl}")).await.expect("should dereference");
+    let err = deref(&format!("/schemas/v1.json?hl={hl}")).await.expect_err("should fail");
+    assert!(matches!(err.downcast_ref::<Error>(), Some(Error::Hashlink(_))));
+
+    let Resource::Url(url) =
+        deref("?service=files&relativeRef=schemas%2Fv2.json").await.expect("should dereference")
+    else {
+        panic!("should be a URL");
+    };
+    assert_eq!(url, "https://credibil.io/issuers/example/schemas/v2.json");
+
+    let err = deref("/schemas/v2.json").await.expect_err("should fail");
+    let Some(Error::Resolution(err)) = err.downcast_ref::<Error>() else {
+        panic!("should be a resolution error");
+    };
+    assert_eq!(err.code(), ErrorCode::NotFound);
+}
+
+// Services defined in the document override the implicit services.
+#[tokio::test]
+async fn override_files() {
+    let mut signer = Keyring::new("webvh_fetch_override").await.expect("should create keyring");
+    let update_multi = signer.multibase("signing").await.expect("should get multibase key");
+
+    let did = default_did("https://credibil.io/issuers/example").expect("should get default DID");
+    let service = ServiceBuilder::new(&format!("{did}#files"))
+        .service_type(&"relativeRef")
+        .endpoint_str(&"https://cdn.credibil.io/example/")
+        .build();
+    let doc = DocumentBuilder::new(&did).add_service(&service).build();
+    let result = CreateBuilder::new()
+        .document(&doc)
+        .expect("should apply document")
+        .update_keys(&[&update_multi])
+        .expect("should apply update keys")
+        .signer(&signer)
+        .build()
+        .await
+        .expect("should build document");
+
+    let files = Files::default();
+    files.publish(LOG_URL, jsonl(&result, "\n"));
+    files.publish("https://cdn.credibil.io/example/logo.png", "logo");
+
+    let url = Url::from_str(&result.did).expect("should parse");
+    let doc = resolve_http(&url, None, &files).await.expect("should resolve");
+    let services = doc.service.expect("should have services");
+    assert_eq!(services.iter().filter(|s| s.id.ends_with("#files")).count(), 1);
+
+    let registry = Registry::default().register(Resolver::new(files.clone()));
+    let did_url = format!("{}/logo.png", result.did);
+    let resource =
+        registry.dereference(&did_url, None, &NoResolver).await.expect("should dereference");
+    let Resource::Content(content) = resource else {
+        panic!("should be content");
+    };
+    assert_eq!(content, b"logo");
+}
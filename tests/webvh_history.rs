@@ -0,0 +1,134 @@
+//! Tests for listing the versions of a `did:webvh` DID and selecting a version
+//! by version number.
+
+use std::str::FromStr;
+
+use credibil_identity::Error;
+use credibil_identity::did::webvh::{
+    CreateBuilder, DidLog, UpdateBuilder, Witness, WitnessWeight, default_did, history, resolve_log,
+};
+use credibil_identity::did::{DocumentBuilder, ErrorCode, QueryParams, ServiceBuilder, Url};
+use kms::Keyring;
+
+// Create a DID then update it twice, adding a service and then witnesses.
+async fn three_versions(name: &str) -> DidLog {
+    let mut signer = Keyring::new(name).await.expect("should create keyring");
+    let update_multi = signer.multibase("signing").await.expect("should get multibase key");
+
+    let did = default_did("https://credibil.io/issuers/example").expect("should get default DID");
+    let doc = DocumentBuilder::new(&did).build();
+    let created = CreateBuilder::new()
+        .document(&doc)
+        .expect("should apply document")
+        .update_keys(&[&update_multi])
+        .expect("should apply update keys")
+        .signer(&signer)
+        .build()
+        .await
+        .expect("should build document");
+
+    let service = ServiceBuilder::new(&format!("{}#files", created.did))
+        .service_type(&"relativeRef")
+        .endpoint_str(&"https://cdn.credibil.io/example/")
+        .build();
+    let doc = DocumentBuilder::from(&created.document).add_service(&service).build();
+    let updated = UpdateBuilder::from(created.log.as_slice(), None)
+        .await
+        .expect("should create builder")
+        .document(&doc)
+        .expect("should apply document")
+        .signer(&signer)
+        .build()
+        .await
+        .expect("should build document");
+
+    let witness = Witness {
+        threshold: 1,
+        witnesses: vec![WitnessWeight {
+            id: "did:key:z6MkhaXgBZDvotDkL5257faiztiGiC2QtKLGpbnnEGta2doK".to_string(),
+            weight: 1,
+        }],
+    };
+    let doc = DocumentBuilder::from(&updated.document).build();
+    let updated = UpdateBuilder::from(updated.log.as_slice(), None)
+        .await
+        .expect("should create builder")
+        .document(&doc)
+        .expect("should apply document")
+        .witness(&witness)
+        .expect("should apply witness")
+        .signer(&signer)
+        .build()
+        .await
+        .expect("should build document");
+    updated.log
+}
+
+// Every version in the log is listed, in order.
+#[tokio::test]
+async fn list_versions() {
+    let log = three_versions("webvh_history_list").await;
+
+    let versions = history(&log, None).await.expect("should list versions");
+    assert_eq!(versions.len(), 3);
+    for (i, version) in versions.iter().enumerate() {
+        assert_eq!(version.version_number, i as u64 + 1);
+        assert_eq!(version.version_id, log[i].version_id);
+        assert_eq!(version.version_time, log[i].version_time);
+    }
+
+    assert!(versions[0].parameters.changed.contains(&"updateKeys".to_string()));
+    assert!(versions[0].document.service.is_none());
+
+    assert_eq!(versions[1].parameters.changed, Vec::<String>::new());
+    assert!(versions[1].document.service.is_some());
+
+    assert_eq!(versions[2].parameters.changed, ["witness"]);
+    assert_eq!(versions[2].parameters.witness_threshold, Some(1));
+    assert_eq!(versions[2].parameters.witnesses, 1);
+    assert_eq!(versions[2].parameters.update_keys, 1);
+
+    let metadata = versions[1].document.did_document_metadata.as_ref().expect("metadata");
+    let version_id = metadata.additional.as_ref().and_then(|a| a.get("versionId"));
+    assert_eq!(version_id.and_then(|v| v.as_str()), Some(log[1].version_id.as_str()));
+}
+
+// A version can be selected by version number.
+#[tokio::test]
+async fn select_version_number() {
+    let log = three_versions("webvh_history_select").await;
+
+    let url = Url::from_str(&format!("{}?versionNumber=2", log[0].state.id)).expect("should parse");
+    assert!(url.to_string().ends_with("?versionNumber=2"));
+
+    let doc = resolve_log(&log, None, url.query.as_ref()).await.expect("should resolve");
+    let metadata = doc.did_document_metadata.expect("should have metadata");
+    let version_id = metadata.additional.as_ref().and_then(|a| a.get("versionId"));
+    assert_eq!(version_id.and_then(|v| v.as_str()), Some(log[1].version_id.as_str()));
+
+    let params = QueryParams {
+        version_number: Some("two".to_string()),
+        ..QueryParams::default()
+    };
+    let err = resolve_log(&log, None, Some(&params)).await.expect_err("should fail");
+    let Some(Error::Resolution(err)) = err.downcast_ref::<Error>() else {
+        panic!("should be a resolution error");
+    };
+    assert_eq!(err.code(), ErrorCode::InvalidOptions);
+}
+
+// A version number past the end of the log is not found.
+#[tokio::test]
+async fn version_number_not_found() {
+    let log = three_versions("webvh_history_not_found").await;
+
+    let params = QueryParams {
+        version_number: Some("4".to_string()),
+        ..QueryParams::default()
+    };
+    let err = resolve_log(&log, None, Some(&params)).await.expect_err("should fail");
+    let Some(Error::Resolution(err)) = err.downcast_ref::<Error>() else {
+        panic!("should be a resolution error");
+    };
+    assert_eq!(err.code(), ErrorCode::NotFound);
+}
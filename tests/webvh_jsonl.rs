@@ -0,0 +1,101 @@
+//! Tests for reading and writing `did.jsonl` DID log files.
+
+use credibil_identity::Error;
+use credibil_identity::did::DocumentBuilder;
+use credibil_identity::did::webvh::{
+    CreateBuilder, DidLog, default_did, parse_log, read_log, read_log_stream, resolve_log,
+    to_jsonl, write_log,
+};
+use kms::Keyring;
+
+// Create a DID log with a single entry.
+async fn create_log(name: &str) -> DidLog {
+    let mut signer = Keyring::new(name).await.expect("should create keyring");
+    let update_multi = signer.multibase("signing").await.expect("should get multibase key");
+
+    let did = default_did("https://credibil.io/issuers/example").expect("should get default DID");
+    let doc = DocumentBuilder::new(&did).build();
+    let result = CreateBuilder::new()
+        .document(&doc)
+        .expect("should apply document")
+        .update_keys(&[&update_multi])
+        .expect("should apply update keys")
+        .signer(&signer)
+        .build()
+        .await
+        .expect("should build document");
+    result.log
+}
+
+// A log written as JSON Lines can be read back from bytes, a reader or a
+// stream.
+#[tokio::test]
+async fn round_trip() {
+    let log = create_log("webvh_jsonl_round_trip").await;
+
+    let jsonl = to_jsonl(&log).expect("should serialize");
+    assert!(jsonl.ends_with('\n'));
+    assert_eq!(jsonl.lines().count(), 1);
+
+    let mut written = vec![];
+    write_log(&log, &mut written).expect("should write");
+    assert_eq!(written, jsonl.as_bytes());
+
+    let parsed = parse_log(jsonl.as_bytes()).expect("should parse");
+    assert_eq!(to_jsonl(&parsed).expect("should serialize"), jsonl);
+    resolve_log(&parsed, None, None).await.expect("should resolve");
+
+    let read = read_log(jsonl.as_bytes()).expect("should read");
+    assert_eq!(read.len(), 1);
+
+    // chunks need not align with lines
+    let bytes = jsonl.repeat(2).into_bytes();
+    let chunks = bytes.chunks(7).map(|c| Ok::<_, std::io::Error>(c.to_vec())).collect::<Vec<_>>();
+    let streamed =
+        read_log_stream(futures_util::stream::iter(chunks)).await.expect("should read stream");
+    assert_eq!(streamed.len(), 2);
+}
+
+// Blank lines and CRLF line endings are accepted.
+#[tokio::test]
+async fn line_endings() {
+    let log = create_log("webvh_jsonl_line_endings").await;
+    let line = to_jsonl(&log).expect("should serialize").trim_end().to_string();
+
+    let jsonl = format!("\r\n{line}\r\n  \n{line}");
+    assert_eq!(parse_log(jsonl.as_bytes()).expect("should parse").len(), 2);
+    assert_eq!(read_log(jsonl.as_bytes()).expect("should read").len(), 2);
+}
+
+// Parse failures report the line number and version ID.
+#[tokio::test]
+async fn diagnostics() {
+    let log = create_log("webvh_jsonl_diagnostics").await;
+    let line = to_jsonl(&log).expect("should serialize").trim_end().to_string();
+    let version_id = &log[0].version_id;
+
+    // an entry with an invalid field
+    let broken = line.replace(r#""versionTime":""#, r#""versionTime":"yesterday"#);
+    let jsonl = format!("{line}\n\n{broken}\n");
+    let err = parse_log(jsonl.as_bytes()).expect_err("should fail");
+    let Some(Error::LogParse { line: number, .. }) = err.downcast_ref::<Error>() else {
+        panic!("should be a log parse error");
+    };
+    assert_eq!(*number, 3);
+    assert_eq!(err.downcast_ref::<Error>().and_then(Error::version_id), Some(version_id.as_str()));
+    assert!(err.to_string().starts_with(&format!("line 3 (version {version_id}): ")));
+
+    // a line that is not JSON
+    let jsonl = format!("{line}\n{{\"versionId\":");
+    let err = read_log(jsonl.as_bytes()).expect_err("should fail");
+    let Some(Error::LogParse {
+        line: number,
+        version_id,
+        ..
+    }) = err.downcast_ref::<Error>()
+    else {
+        panic!("should be a log parse error");
+    };
+    assert_eq!(*number, 2);
+    assert!(version_id.is_none());
+}
@@ -4,9 +4,12 @@ use credibil_identity::core::Kind;
 use credibil_identity::did::{
     DocumentBuilder, KeyPurpose, MethodType, PublicKeyFormat, ServiceBuilder, VerificationMethod,
     VerificationMethodBuilder, VmKeyId,
-    webvh::{CreateBuilder, SCID_PLACEHOLDER, Witness, WitnessWeight, default_did, verify_proofs},
+    webvh::{
+        CreateBuilder, SCID_PLACEHOLDER, Witness, WitnessWeight, default_did, resolve_log,
+        verify_proofs,
+    },
 };
-use credibil_identity::{Key, SignerExt};
+use credibil_identity::{Error, Key, LogErrorKind, SignerExt};
 use kms::Keyring;
 
 // Create a minimal document and then verify the proof. Should verify without
@@ -136,3 +139,57 @@ async fn complex_proof() {
 
     verify_proofs(&result.log[0]).await.expect("should verify proof");
 }
+
+// Tamper with a log entry. Verification should fail with a typed error that
+// identifies the offending entry.
+#[tokio::test]
+async fn tampered_log() {
+    let domain_and_path = "https://credibil.io/issuers/example";
+
+    let mut signer = Keyring::new("webvh_tampered_log").await.expect("should create keyring");
+    let update_multi = signer.multibase("signing").await.expect("should get multibase key");
+    let update_keys = vec![update_multi.as_str()];
+
+    let did = default_did(domain_and_path).expect("should get default DID");
+    let doc = DocumentBuilder::new(&did).build();
+
+    let result = CreateBuilder::new()
+        .document(&doc)
+        .expect("should apply document")
+        .update_keys(&update_keys)
+        .expect("should apply update keys")
+        .signer(&signer)
+        .build()
+        .await
+        .expect("should build document");
+
+    // Changing the entry invalidates the controller's proof.
+    let mut entry = result.log[0].clone();
+    entry.parameters.ttl += 1;
+    let err = resolve_log(&[entry.clone()], None, None).await.expect_err("should fail");
+    let Some(Error::LogVerification { kind, version_id, .. }) = err.downcast_ref::<Error>() else {
+        panic!("should be a log verification error");
+    };
+    assert_eq!(*kind, LogErrorKind::Proof);
+    assert_eq!(version_id.as_deref(), Some(entry.version_id.as_str()));
+
+    // Re-signing the entry does not fix the entry hash.
+    entry.proof.clear();
+    entry.sign(&signer).await.expect("should sign");
+    let err = resolve_log(&[entry.clone()], None, None).await.expect_err("should fail");
+    let err = err.downcast_ref::<Error>().expect("should be a typed error");
+    let Error::LogVerification { kind, .. } = err else {
+        panic!("should be a log verification error");
+    };
+    assert_eq!(*kind, LogErrorKind::Hash);
+    assert_eq!(err.version_id(), Some(entry.version_id.as_str()));
+    assert_eq!(err.to_string(), "log entry hash does not match version id");
+
+    let err = resolve_log(&[], None, None).await.expect_err("should fail");
+    let err = err.downcast_ref::<Error>().expect("should be a typed error");
+    let Error::LogVerification { kind, .. } = err else {
+        panic!("should be a log verification error");
+    };
+    assert_eq!(*kind, LogErrorKind::Empty);
+    assert!(err.version_id().is_none());
+}
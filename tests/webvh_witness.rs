@@ -0,0 +1,31 @@
+//! Tests for reading and writing `did-witness.json` entries.
+
+use credibil_identity::did::webvh::WitnessEntry;
+use serde_json::json;
+
+// Entries are written with the `versionId` property from the `did:webvh`
+// specification.
+#[test]
+fn camel_case() {
+    let entry: WitnessEntry =
+        serde_json::from_value(json!({ "versionId": "1-QmExample", "proof": [] }))
+            .expect("should deserialize");
+    assert_eq!(entry.version_id, "1-QmExample");
+
+    let value = serde_json::to_value(&entry).expect("should serialize");
+    assert_eq!(value, json!({ "versionId": "1-QmExample", "proof": [] }));
+    let entry: WitnessEntry = serde_json::from_value(value).expect("should deserialize");
+    assert_eq!(entry.version_id, "1-QmExample");
+}
+
+// Files written by earlier releases, using `version_id`, can still be read.
+#[test]
+fn snake_case() {
+    let entry: WitnessEntry =
+        serde_json::from_value(json!({ "version_id": "1-QmExample", "proof": [] }))
+            .expect("should deserialize");
+    assert_eq!(entry.version_id, "1-QmExample");
+
+    let value = serde_json::to_value(&entry).expect("should serialize");
+    assert_eq!(value, json!({ "versionId": "1-QmExample", "proof": [] }));
+}
@@ -0,0 +1,136 @@
+//! Tests for certificate chains returned by an identity resolver: parsing,
+//! validation against trust anchors and verification of key IDs referencing
+//! `x5u`/`x5c` material.
+
+use std::collections::HashMap;
+
+use anyhow::anyhow;
+use chrono::{Duration, TimeZone, Utc};
+use credibil_identity::did::x509::{FingerprintAlgorithm, Policy, did_from_ca};
+use credibil_identity::did::{Resource, dereference};
+use credibil_identity::x509::{Chain, TrustAnchors, resolve_key};
+use credibil_identity::{Identity, IdentityResolver, Key};
+
+const LEAF: &str = include_str!("fixtures/x509/leaf.pem");
+const INTERMEDIATE: &str = include_str!("fixtures/x509/intermediate.pem");
+const ROOT: &str = include_str!("fixtures/x509/root.pem");
+
+// Serves certificate chains by URL, standing in for an `x5u` endpoint or a
+// cache of `x5c` header parameters.
+#[derive(Clone, Default)]
+struct Certificates {
+    chains: HashMap<String, Chain>,
+}
+
+impl IdentityResolver for Certificates {
+    async fn resolve(&self, url: &str) -> anyhow::Result<Identity> {
+        let chain = self.chains.get(url).cloned();
+        Ok(Identity::X509Chain(chain.ok_or_else(|| anyhow!("no chain for {url}"))?))
+    }
+}
+
+// The leaf and intermediate certificates, without the root.
+fn chain() -> Chain {
+    Chain::from_pem(format!("{LEAF}{INTERMEDIATE}")).expect("should parse chain")
+}
+
+fn root() -> TrustAnchors {
+    TrustAnchors::from_pem(ROOT).expect("should parse trust anchors")
+}
+
+// Chains can be parsed from PEM and round-trip through the `x5c`
+// representation used for serialization.
+#[test]
+fn parse_chain() {
+    let chain = chain();
+    assert_eq!(chain.certificates().len(), 2);
+
+    let x5c = chain.to_x5c().expect("should encode");
+    assert_eq!(Chain::from_x5c(&x5c).expect("should decode"), chain);
+
+    let identity = Identity::X509Chain(chain);
+    let json = serde_json::to_value(&identity).expect("should serialize");
+    assert_eq!(json, serde_json::json!({ "X509Chain": x5c }));
+    let de: Identity = serde_json::from_value(json).expect("should deserialize");
+    assert_eq!(de, identity);
+
+    Chain::from_pem("").expect_err("empty chain should fail");
+    Chain::from_x5c(&["not base64!"]).expect_err("invalid encoding should fail");
+}
+
+// A chain is trusted if it ends at, or is issued by, a trust anchor that is
+// valid at the validation time.
+#[test]
+fn trust_anchors() {
+    let chain = chain();
+    root().verify(&chain, Utc::now()).expect("should verify against root");
+
+    let full = Chain::from_pem(format!("{LEAF}{INTERMEDIATE}{ROOT}")).expect("should parse chain");
+    root().verify(&full, Utc::now()).expect("should verify chain ending at root");
+
+    // the intermediate can be trusted directly
+    let intermediate = TrustAnchors::from_pem(INTERMEDIATE).expect("should parse");
+    intermediate.verify(&chain, Utc::now()).expect("should verify against intermediate");
+
+    // the leaf is not a CA, so cannot issue anything
+    let leaf = TrustAnchors::new().add_anchor(chain.leaf().clone());
+    let leaf_only = Chain::new(vec![chain.leaf().clone()]).expect("should create chain");
+    leaf.verify(&leaf_only, Utc::now()).expect("a trusted certificate is its own path");
+    let err = leaf.verify(&chain, Utc::now()).expect_err("should fail");
+    assert!(err.to_string().contains("is not issued by a trust anchor"));
+
+    let err = TrustAnchors::new().verify(&chain, Utc::now()).expect_err("should fail");
+    assert!(err.to_string().contains("no trust anchors"));
+
+    let expired = Utc::now() + Duration::days(365 * 200);
+    let err = root().verify(&chain, expired).expect_err("should fail");
+    assert!(err.to_string().contains("is not valid at"));
+}
+
+// A key ID referencing a certificate chain resolves to the leaf certificate's
+// key once the chain is validated.
+#[tokio::test]
+async fn resolve_key_id() {
+    let chain = chain();
+    let x5u = "https://issuer.example.com/certs/chain.pem";
+    let resolver = Certificates {
+        chains: HashMap::from([(x5u.to_string(), chain.clone())]),
+    };
+
+    // validation is at a fixed time within the certificates' validity
+    let time = Utc.with_ymd_and_hms(2030, 1, 1, 0, 0, 0).unwrap();
+    let key = Key::KeyId(x5u.to_string());
+    let jwk = resolve_key(&key, &resolver, &root(), time).await.expect("should resolve key");
+    assert_eq!(jwk, chain.public_key_jwk().expect("should get leaf key"));
+
+    let before = Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap();
+    let err = resolve_key(&key, &resolver, &root(), before).await.expect_err("should fail");
+    assert!(err.to_string().contains("is not valid at"));
+
+    let leaf = TrustAnchors::new().add_anchor(chain.leaf().clone());
+    resolve_key(&key, &resolver, &leaf, time).await.expect_err("untrusted chain should fail");
+    let unknown = Key::KeyId("https://unknown.example.com".to_string());
+    resolve_key(&unknown, &resolver, &root(), time).await.expect_err("unknown key ID should fail");
+    resolve_key(&Key::Jwk(jwk), &resolver, &root(), time).await.expect_err("JWK should fail");
+}
+
+// A `did:x509` URL can be dereferenced when the resolver returns the chain
+// presented with the DID.
+#[tokio::test]
+async fn dereference_did_x509() {
+    let chain = Chain::from_pem(format!("{LEAF}{INTERMEDIATE}{ROOT}")).expect("should parse");
+    let policies = [Policy::Eku("1.3.6.1.5.5.7.3.3".to_string())];
+    let did = did_from_ca(&chain.certificates()[2], FingerprintAlgorithm::Sha256, &policies)
+        .expect("should create DID");
+    let resolver = Certificates {
+        chains: HashMap::from([(did.clone(), chain.clone())]),
+    };
+
+    let url = format!("{did}#key-1");
+    let Resource::VerificationMethod(vm) =
+        dereference(&url, None, &resolver).await.expect("should dereference")
+    else {
+        panic!("should be a verification method");
+    };
+    assert_eq!(vm.id, url);
+}
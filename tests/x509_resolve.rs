@@ -0,0 +1,184 @@
+//! Tests for resolving a `did:x509` into a DID document from a certificate
+//! chain.
+//!
+//! The fixture chain is a leaf (`P-256`) certificate issued by an
+//! intermediate (`P-384`) CA, issued in turn by an RSA root CA.
+
+use std::str::FromStr;
+
+use chrono::Utc;
+use credibil_identity::core::Kind;
+use credibil_identity::did::x509::{
+    self, DidX509, FingerprintAlgorithm, Policy, SanType, did_from_ca,
+};
+use credibil_identity::did::{MethodType, PublicKeyFormat, Resource, Url};
+use credibil_identity::x509::{Certificate, Chain, public_key_jwk, verify_chain};
+use x509_cert::der::DecodePem;
+
+const LEAF: &str = include_str!("fixtures/x509/leaf.pem");
+const INTERMEDIATE: &str = include_str!("fixtures/x509/intermediate.pem");
+const ROOT: &str = include_str!("fixtures/x509/root.pem");
+
+// A leaf issued by a CA whose key usage does not include keyCertSign.
+const NO_CERT_SIGN: &str = include_str!("fixtures/x509/no_cert_sign.pem");
+
+// A leaf whose outer signature algorithm (ecdsa-with-SHA384) differs from the
+// algorithm in its signed content (ecdsa-with-SHA256), and its CA.
+const ALGORITHM_MISMATCH: &str = include_str!("fixtures/x509/algorithm_mismatch.pem");
+
+fn chain() -> Vec<Certificate> {
+    [LEAF, INTERMEDIATE, ROOT]
+        .iter()
+        .map(|pem| Certificate::from_pem(pem).expect("should parse certificate"))
+        .collect()
+}
+
+fn subject_policy() -> Policy {
+    Policy::Subject(vec![
+        ("CN".to_string(), "issuer.example.com".to_string()),
+        ("O".to_string(), "Credibil Example".to_string()),
+    ])
+}
+
+// A chain satisfying the DID's policies resolves to a document with the leaf
+// key as a JWK verification method.
+#[test]
+fn resolve_chain() {
+    let chain = chain();
+    let policies = [
+        subject_policy(),
+        Policy::San(SanType::Dns, "issuer.example.com".to_string()),
+        Policy::Eku("1.3.6.1.5.5.7.3.3".to_string()),
+    ];
+    let did =
+        did_from_ca(&chain[2], FingerprintAlgorithm::Sha256, &policies).expect("should create DID");
+    assert!(did.contains("::subject:CN:issuer.example.com:O:Credibil%20Example::"));
+
+    let url = Url::from_str(&format!("{did}#key-1")).expect("should parse URL");
+    let Resource::VerificationMethod(vm) = x509::resolve(&url, &chain).expect("should resolve")
+    else {
+        panic!("should be a verification method");
+    };
+    assert_eq!(vm.id, format!("{did}#key-1"));
+    assert_eq!(vm.type_, MethodType::JsonWebKey2020);
+    assert_eq!(
+        vm.key,
+        PublicKeyFormat::PublicKeyJwk {
+            public_key_jwk: public_key_jwk(&chain[0]).expect("should get leaf key")
+        }
+    );
+
+    let doc = x509::document(&url, &chain).expect("should resolve");
+    assert_eq!(doc.id, did);
+    let vm_ref = Kind::String(vm.id);
+    assert_eq!(doc.authentication, Some(vec![vm_ref.clone()]));
+    assert_eq!(doc.assertion_method, Some(vec![vm_ref]));
+
+    // The leaf certificate's key usage is limited to digital signatures.
+    assert!(doc.key_agreement.is_none());
+    assert!(doc.did_document_metadata.is_none());
+}
+
+// The CA fingerprint can identify any CA certificate in the chain, but not
+// the leaf.
+#[test]
+fn ca_fingerprint() {
+    let chain = chain();
+    let policies = [subject_policy()];
+
+    let did =
+        did_from_ca(&chain[1], FingerprintAlgorithm::Sha384, &policies).expect("should create DID");
+    let did_x509 = DidX509::from_str(&did).expect("should parse DID");
+    assert_eq!(did_x509.algorithm(), FingerprintAlgorithm::Sha384);
+    did_x509.validate(&chain, Utc::now()).expect("should validate");
+
+    let did =
+        did_from_ca(&chain[0], FingerprintAlgorithm::Sha256, &policies).expect("should create DID");
+    let did_x509 = DidX509::from_str(&did).expect("should parse DID");
+    let err = did_x509.validate(&chain, Utc::now()).expect_err("should fail");
+    assert!(err.to_string().contains("CA fingerprint"));
+}
+
+// The leaf certificate must satisfy every policy.
+#[test]
+fn policy_mismatch() {
+    let chain = chain();
+    let mismatches = [
+        Policy::Subject(vec![("O".to_string(), "Someone Else".to_string())]),
+        Policy::San(SanType::Email, "someone@example.com".to_string()),
+        Policy::San(SanType::Uri, "issuer.example.com".to_string()),
+        Policy::Eku("1.3.6.1.5.5.7.3.1".to_string()),
+    ];
+    for policy in mismatches {
+        let did =
+            did_from_ca(&chain[2], FingerprintAlgorithm::Sha256, std::slice::from_ref(&policy))
+                .expect("should create DID");
+        let did_x509 = DidX509::from_str(&did).expect("should parse DID");
+        did_x509.validate(&chain, Utc::now()).expect_err(&format!("{policy} should fail"));
+    }
+}
+
+// The chain must be complete, in order and valid at the time of validation.
+#[test]
+fn invalid_chain() {
+    let chain = chain();
+    verify_chain(&chain, Utc::now()).expect("should verify");
+
+    let missing = [chain[0].clone(), chain[2].clone()];
+    let err = verify_chain(&missing, Utc::now()).expect_err("should fail");
+    assert!(err.to_string().contains("is not issued by"));
+
+    let reversed = chain.iter().rev().cloned().collect::<Vec<_>>();
+    verify_chain(&reversed, Utc::now()).expect_err("should fail");
+
+    let expired = Utc::now() + chrono::Duration::days(365 * 200);
+    let err = verify_chain(&chain, expired).expect_err("should fail");
+    assert!(err.to_string().contains("is not valid at"));
+}
+
+// Issuing certificates must be allowed to sign certificates and each
+// certificate's signature algorithm must match its signed content.
+#[test]
+fn invalid_certificates() {
+    let chain = Chain::from_pem(NO_CERT_SIGN).expect("should parse chain");
+    let err = verify_chain(chain.certificates(), Utc::now()).expect_err("should fail");
+    assert!(err.to_string().contains("does not include keyCertSign"));
+
+    let chain = Chain::from_pem(ALGORITHM_MISMATCH).expect("should parse chain");
+    let err = verify_chain(chain.certificates(), Utc::now()).expect_err("should fail");
+    assert!(err.to_string().contains("signature algorithm does not match"));
+}
+
+// Policy values are percent-decoded and malformed DIDs are rejected.
+#[test]
+fn parse_did() {
+    let did = "did:x509:0:sha256:WE4P5dd8DnLHSkyHaIjhp4udlkF9LqoKwCvu9gl38jk::subject:C:US:O:Example%20Inc::eku:1.3.6.1.5.5.7.3.3";
+    let did_x509 = DidX509::from_str(did).expect("should parse DID");
+    assert_eq!(did_x509.to_string(), did);
+    assert_eq!(
+        did_x509.policies(),
+        [
+            Policy::Subject(vec![
+                ("C".to_string(), "US".to_string()),
+                ("O".to_string(), "Example Inc".to_string())
+            ]),
+            Policy::Eku("1.3.6.1.5.5.7.3.3".to_string()),
+        ]
+    );
+
+    let invalid = [
+        // unsupported version
+        "did:x509:1:sha256:WE4P5dd8DnLHSkyHaIjhp4udlkF9LqoKwCvu9gl38jk::eku:1.2.3",
+        // fingerprint length does not match algorithm
+        "did:x509:0:sha384:WE4P5dd8DnLHSkyHaIjhp4udlkF9LqoKwCvu9gl38jk::eku:1.2.3",
+        // no policies
+        "did:x509:0:sha256:WE4P5dd8DnLHSkyHaIjhp4udlkF9LqoKwCvu9gl38jk",
+        // unsupported policy
+        "did:x509:0:sha256:WE4P5dd8DnLHSkyHaIjhp4udlkF9LqoKwCvu9gl38jk::wibble:1",
+        // duplicate subject key
+        "did:x509:0:sha256:WE4P5dd8DnLHSkyHaIjhp4udlkF9LqoKwCvu9gl38jk::subject:O:a:O:b",
+    ];
+    for did in invalid {
+        DidX509::from_str(did).expect_err(&format!("{did} should fail"));
+    }
+}